@@ -1,7 +1,7 @@
-use super::expression::*;
+use super::{expression::*, from_variant::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::{
         cli::depict::*,
         std::{immutable::*, iter::*},
@@ -29,6 +29,25 @@ impl Call {
         Self { name, arguments }
     }
 
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        let name = expect_text(get_required(map, "$name")?)?.clone();
+
+        let arguments = match map.into_get("$arguments") {
+            Some(arguments) => {
+                expect_list(arguments)?.into_iter().map(Expression::from_variant).collect::<Result<_, _>>()?
+            }
+            None => Default::default(),
+        };
+
+        Ok(Self::new(name, arguments))
+    }
+
     /// Evaluate.
     #[cfg(feature = "plugins")]
     pub fn evaluate<StoreT, AnnotatedT>(
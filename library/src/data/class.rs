@@ -1,9 +1,12 @@
-use super::{super::store::*, depict::*, directory::*, id::*, kind::*, metadata::*};
+use super::{
+    super::store::*, depict::*, directory::*, event_handler::*, from_variant::*, id::*, kind::*, lifecycle::*,
+    metadata::*, property_definition::*,
+};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::{cli::depict::*, std::immutable::*},
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -16,14 +19,31 @@ pub struct Class {
     /// ID.
     pub id: ID,
 
+    /// Revision.
+    ///
+    /// Set by the store whenever it is written. 0 means that it has not been stored.
+    pub revision: u64,
+
     /// Metadata.
     pub metadata: Metadata,
 
-    /// TODO: Parent class IDs.
+    /// Parent class IDs. Members of this class are also members of its ancestors.
     pub parent_class_ids: Vec<ID>,
 
-    /// TODO: Child class IDs.
+    /// Child class IDs. The back links of the children's parent class IDs.
     pub child_class_ids: Vec<ID>,
+
+    /// Property definitions for member vertexes and edges. Inherited by child classes, which may
+    /// override them.
+    pub property_definitions: BTreeMap<ByteString, PropertyDefinition>,
+
+    /// Event handlers for member vertexes and edges. Inherited by child classes, which may override
+    /// them per event ID.
+    pub event_handlers: Vec<EventHandler>,
+
+    /// Allowed lifecycle transitions for member vertexes and edges. Combined with those of the
+    /// ancestors.
+    pub lifecycle_transitions: Vec<LifecycleTransition>,
 }
 
 impl Class {
@@ -36,12 +56,73 @@ impl Class {
     pub fn new_with(id: ID) -> Self {
         Self {
             id,
+            revision: 0,
             metadata: Default::default(),
             parent_class_ids: Default::default(),
             child_class_ids: Default::default(),
+            property_definitions: Default::default(),
+            event_handlers: Default::default(),
+            lifecycle_transitions: Default::default(),
+        }
+    }
+
+    /// Remove a class ID from the parent and child class IDs.
+    ///
+    /// Returns true if removed.
+    pub fn remove_class_id(&mut self, class_id: &ID) -> bool {
+        let length = self.parent_class_ids.len() + self.child_class_ids.len();
+        self.parent_class_ids.retain(|parent_class_id| parent_class_id != class_id);
+        self.child_class_ids.retain(|child_class_id| child_class_id != class_id);
+        length != self.parent_class_ids.len() + self.child_class_ids.len()
+    }
+
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.id.remap(ids);
+        for parent_class_id in &mut self.parent_class_ids {
+            parent_class_id.remap(ids);
+        }
+        for child_class_id in &mut self.child_class_ids {
+            child_class_id.remap(ids);
         }
     }
 
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        let mut class = Self::new_with(get_entity_id(map, Kind::Class)?);
+        class.revision = get_revision(map)?;
+        class.metadata = get_metadata(map)?;
+        class.parent_class_ids = get_ids(map, "parent_class_ids", Kind::Class)?;
+        class.child_class_ids = get_ids(map, "child_class_ids", Kind::Class)?;
+
+        if let Some(property_definitions) = map.into_get("property_definitions") {
+            for (name, property_definition) in expect_map(property_definitions)? {
+                class
+                    .property_definitions
+                    .insert(expect_text(name)?.clone(), PropertyDefinition::from_variant(property_definition)?);
+            }
+        }
+
+        if let Some(event_handlers) = map.into_get("event_handlers") {
+            class.event_handlers =
+                expect_list(event_handlers)?.into_iter().map(EventHandler::from_variant).collect::<Result<_, _>>()?;
+        }
+
+        if let Some(lifecycle_transitions) = map.into_get("lifecycle_transitions") {
+            class.lifecycle_transitions = expect_list(lifecycle_transitions)?
+                .into_iter()
+                .map(LifecycleTransition::from_variant)
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(class)
+    }
+
     /// To Compris variant.
     pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
     where
@@ -51,8 +132,41 @@ impl Class {
 
         map.into_insert("kind", self.id.kind.to_string());
         map.into_insert("id", self.id.to_string());
+        if self.revision != 0 {
+            map.into_insert("revision", self.revision);
+        }
         map.into_insert("metadata", self.metadata.clone().into_annotated());
 
+        if !self.parent_class_ids.is_empty() {
+            let parent_class_ids: List<_> = self.parent_class_ids.iter().map(|id| id.to_string().into()).collect();
+            map.into_insert("parent_class_ids", parent_class_ids);
+        }
+
+        if !self.child_class_ids.is_empty() {
+            let child_class_ids: List<_> = self.child_class_ids.iter().map(|id| id.to_string().into()).collect();
+            map.into_insert("child_class_ids", child_class_ids);
+        }
+
+        if !self.property_definitions.is_empty() {
+            let mut property_definitions = Map::default();
+            for (name, property_definition) in &self.property_definitions {
+                property_definitions.into_insert(name.clone(), property_definition.to_variant());
+            }
+            map.into_insert("property_definitions", property_definitions);
+        }
+
+        if !self.event_handlers.is_empty() {
+            let event_handlers: List<_> =
+                self.event_handlers.iter().map(|event_handler| event_handler.to_variant()).collect();
+            map.into_insert("event_handlers", event_handlers);
+        }
+
+        if !self.lifecycle_transitions.is_empty() {
+            let lifecycle_transitions: List<_> =
+                self.lifecycle_transitions.iter().map(|transition| transition.to_variant()).collect();
+            map.into_insert("lifecycle_transitions", lifecycle_transitions);
+        }
+
         map.into()
     }
 
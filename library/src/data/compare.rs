@@ -0,0 +1,26 @@
+use {compris::normal::*, std::cmp::*};
+
+/// Compare [Variant]s.
+///
+/// Numbers compare by value regardless of representation. Other values compare only with values of
+/// the same type.
+pub fn compare_variants<AnnotatedT>(left: &Variant<AnnotatedT>, right: &Variant<AnnotatedT>) -> Option<Ordering> {
+    match (left, right) {
+        (Variant::Integer(left), Variant::UnsignedInteger(right)) => {
+            (left.inner as i128).partial_cmp(&(right.inner as i128))
+        }
+        (Variant::UnsignedInteger(left), Variant::Integer(right)) => {
+            (left.inner as i128).partial_cmp(&(right.inner as i128))
+        }
+        (Variant::Float(left), Variant::Integer(right)) => left.inner.into_inner().partial_cmp(&(right.inner as f64)),
+        (Variant::Float(left), Variant::UnsignedInteger(right)) => {
+            left.inner.into_inner().partial_cmp(&(right.inner as f64))
+        }
+        (Variant::Integer(left), Variant::Float(right)) => (left.inner as f64).partial_cmp(&right.inner.into_inner()),
+        (Variant::UnsignedInteger(left), Variant::Float(right)) => {
+            (left.inner as f64).partial_cmp(&right.inner.into_inner())
+        }
+        _ if left.type_name() == right.type_name() => left.partial_cmp(right),
+        _ => None,
+    }
+}
@@ -24,6 +24,11 @@ impl Directory {
     pub fn add_last_segment(&mut self, segment: ByteString) {
         self.0.push(segment);
     }
+
+    /// True if the directory is the same as or a subdirectory of the provided directory.
+    pub fn starts_with(&self, directory: &Directory) -> bool {
+        self.0.starts_with(&directory.0)
+    }
 }
 
 impl FromIterator<ByteString> for Directory {
@@ -1,9 +1,9 @@
-use super::{super::store::*, depict::*, id::*, instance::*};
+use super::{super::store::*, depict::*, from_variant::*, id::*, instance::*, kind::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::cli::depict::*,
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -24,6 +24,27 @@ pub struct Edge {
 }
 
 impl Edge {
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.instance.remap_ids(ids);
+        self.source_vertex_id.remap(ids);
+        self.target_vertex_id.remap(ids);
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        Ok(Self {
+            instance: Instance::from_variant(map, Kind::Edge)?,
+            source_vertex_id: get_id(get_required(map, "source_vertex_id")?, Kind::Vertex)?,
+            target_vertex_id: get_id(get_required(map, "target_vertex_id")?, Kind::Vertex)?,
+        })
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -1,9 +1,11 @@
-use super::{super::store::*, depict::*, directory::*, edge::*, id::*, kind::*, template::*, vertex_selector::*};
+use super::{
+    super::store::*, depict::*, directory::*, edge::*, from_variant::*, id::*, kind::*, template::*, vertex_selector::*,
+};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::{cli::depict::*, std::immutable::*},
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -60,28 +62,59 @@ impl EdgeTemplate {
     }
 
     /// Instantiate.
-    pub fn instantiate<StoreT>(
+    ///
+    /// Applies the property definitions of the edge's classes (see
+    /// [Instance::apply_property_definitions](super::Instance::apply_property_definitions)).
+    pub fn instantiate<StoreT, ErrorRecipientT>(
         &self,
         directory: &Directory,
         source_vertex_id: ID,
         target_vertex_id: ID,
         store: &StoreT,
-    ) -> Result<ID, StoreError>
+        errors: &mut ErrorRecipientT,
+    ) -> Result<ID, super::super::FloriaError>
     where
         StoreT: Store,
+        ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
     {
-        let edge = Edge {
+        let mut edge = Edge {
             instance: self.template.instantiate(Kind::Edge, directory, store)?,
             source_vertex_id,
             target_vertex_id,
         };
 
+        edge.instance.apply_property_definitions(store, errors)?;
+
         let edge_id = edge.instance.id.clone();
         store.add_edge(edge)?;
 
         Ok(edge_id)
     }
 
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.template.remap_ids(ids);
+        self.containing_source_vertex_template_id.remap(ids);
+        self.target_selector.remap_ids(ids);
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        Ok(Self {
+            template: Template::from_variant(map, Kind::EdgeTemplate)?,
+            containing_source_vertex_template_id: get_id(
+                get_required(map, "containing_source_vertex_template_id")?,
+                Kind::VertexTemplate,
+            )?,
+            target_selector: VertexSelector::from_variant(get_required(map, "target_selector")?)?,
+        })
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -96,7 +129,7 @@ impl EdgeTemplate {
 
         self.template.to_variant(&mut map, debug, store)?;
 
-        map.into_insert("containing_source_vertex_template_id", self.containing_source_vertex_template_id.id.clone());
+        map.into_insert("containing_source_vertex_template_id", self.containing_source_vertex_template_id.to_string());
         map.into_insert("target_selector", self.target_selector.to_variant());
 
         Ok(map.into())
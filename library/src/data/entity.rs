@@ -0,0 +1,216 @@
+use super::{
+    super::store::*, class::*, edge::*, edge_template::*, from_variant::*, id::*, kind::*, metadata::*, vertex::*,
+    vertex_template::*,
+};
+
+use {
+    compris::{annotate::*, normal::*, resolve::*},
+    std::collections::*,
+};
+
+//
+// Entity
+//
+
+/// Entity of any [Kind](super::kind::Kind).
+#[derive(Clone, Debug)]
+pub enum Entity {
+    /// Class.
+    Class(Class),
+
+    /// Vertex template.
+    VertexTemplate(VertexTemplate),
+
+    /// Edge template.
+    EdgeTemplate(EdgeTemplate),
+
+    /// Vertex.
+    Vertex(Vertex),
+
+    /// Edge.
+    Edge(Edge),
+}
+
+impl Entity {
+    /// ID.
+    pub fn get_id(&self) -> &ID {
+        match self {
+            Self::Class(class) => &class.id,
+            Self::VertexTemplate(vertex_template) => &vertex_template.template.id,
+            Self::EdgeTemplate(edge_template) => &edge_template.template.id,
+            Self::Vertex(vertex) => &vertex.instance.id,
+            Self::Edge(edge) => &edge.instance.id,
+        }
+    }
+
+    /// Metadata.
+    pub fn get_metadata(&self) -> &Metadata {
+        match self {
+            Self::Class(class) => &class.metadata,
+            Self::VertexTemplate(vertex_template) => &vertex_template.template.metadata,
+            Self::EdgeTemplate(edge_template) => &edge_template.template.metadata,
+            Self::Vertex(vertex) => &vertex.instance.metadata,
+            Self::Edge(edge) => &edge.instance.metadata,
+        }
+    }
+
+    /// Class IDs.
+    ///
+    /// Classes themselves are not members of classes, so for them this is always empty.
+    pub fn get_class_ids(&self) -> &[ID] {
+        match self {
+            Self::Class(_) => &[],
+            Self::VertexTemplate(vertex_template) => &vertex_template.template.class_ids,
+            Self::EdgeTemplate(edge_template) => &edge_template.template.class_ids,
+            Self::Vertex(vertex) => &vertex.instance.class_ids,
+            Self::Edge(edge) => &edge.instance.class_ids,
+        }
+    }
+
+    /// Origin template ID.
+    ///
+    /// Only instances (vertexes and edges) can have an origin template.
+    pub fn get_origin_template_id(&self) -> Option<&ID> {
+        match self {
+            Self::Vertex(vertex) => vertex.instance.origin_template_id.as_ref(),
+            Self::Edge(edge) => edge.instance.origin_template_id.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Remap IDs.
+    ///
+    /// Replaces the entity's own ID as well as all the IDs it refers to. IDs that are not in the
+    /// map are kept as is.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        match self {
+            Self::Class(class) => class.remap_ids(ids),
+            Self::VertexTemplate(vertex_template) => vertex_template.remap_ids(ids),
+            Self::EdgeTemplate(edge_template) => edge_template.remap_ids(ids),
+            Self::Vertex(vertex) => vertex.remap_ids(ids),
+            Self::Edge(edge) => edge.remap_ids(ids),
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// The entity's kind is determined by its "kind" key.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        Ok(match get_kind(get_required(expect_map(variant)?, "kind")?)? {
+            Kind::Class => Class::from_variant(variant)?.into(),
+            Kind::VertexTemplate => VertexTemplate::from_variant(variant)?.into(),
+            Kind::EdgeTemplate => EdgeTemplate::from_variant(variant)?.into(),
+            Kind::Vertex => Vertex::from_variant(variant)?.into(),
+            Kind::Edge => Edge::from_variant(variant)?.into(),
+        })
+    }
+
+    /// To Compris variant.
+    pub fn to_variant<StoreT, AnnotatedT>(
+        &self,
+        embedded: bool,
+        store: &StoreT,
+    ) -> Result<Variant<AnnotatedT>, StoreError>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+        StoreT: Store,
+    {
+        match self {
+            Self::Class(class) => Ok(class.to_variant()),
+            Self::VertexTemplate(vertex_template) => vertex_template.to_variant(embedded, store),
+            Self::EdgeTemplate(edge_template) => edge_template.to_variant(embedded, store),
+            Self::Vertex(vertex) => vertex.to_variant(embedded, store),
+            Self::Edge(edge) => edge.to_variant(embedded, store),
+        }
+    }
+}
+
+// Conversions
+
+impl From<Class> for Entity {
+    fn from(class: Class) -> Self {
+        Self::Class(class)
+    }
+}
+
+impl From<VertexTemplate> for Entity {
+    fn from(vertex_template: VertexTemplate) -> Self {
+        Self::VertexTemplate(vertex_template)
+    }
+}
+
+impl From<EdgeTemplate> for Entity {
+    fn from(edge_template: EdgeTemplate) -> Self {
+        Self::EdgeTemplate(edge_template)
+    }
+}
+
+impl From<Vertex> for Entity {
+    fn from(vertex: Vertex) -> Self {
+        Self::Vertex(vertex)
+    }
+}
+
+impl From<Edge> for Entity {
+    fn from(edge: Edge) -> Self {
+        Self::Edge(edge)
+    }
+}
+
+impl TryFrom<Entity> for Class {
+    type Error = Entity;
+
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        match entity {
+            Entity::Class(class) => Ok(class),
+            _ => Err(entity),
+        }
+    }
+}
+
+impl TryFrom<Entity> for VertexTemplate {
+    type Error = Entity;
+
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        match entity {
+            Entity::VertexTemplate(vertex_template) => Ok(vertex_template),
+            _ => Err(entity),
+        }
+    }
+}
+
+impl TryFrom<Entity> for EdgeTemplate {
+    type Error = Entity;
+
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        match entity {
+            Entity::EdgeTemplate(edge_template) => Ok(edge_template),
+            _ => Err(entity),
+        }
+    }
+}
+
+impl TryFrom<Entity> for Vertex {
+    type Error = Entity;
+
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        match entity {
+            Entity::Vertex(vertex) => Ok(vertex),
+            _ => Err(entity),
+        }
+    }
+}
+
+impl TryFrom<Entity> for Edge {
+    type Error = Entity;
+
+    fn try_from(entity: Entity) -> Result<Self, Self::Error> {
+        match entity {
+            Entity::Edge(edge) => Ok(edge),
+            _ => Err(entity),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{event_handler::*, id::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::std::immutable::*,
+};
+
+//
+// Event
+//
+
+/// Event.
+///
+/// Raising an event on a vertex or an edge calls its matching event handlers (see
+/// [StoreEventHandlerUtilities](super::super::StoreEventHandlerUtilities)). The event ID and then
+/// the payload, if there is one, are appended to the arguments of each handler's call.
+#[derive(Clone, Debug)]
+pub struct Event {
+    /// Event ID.
+    pub event_id: ByteString,
+
+    /// Payload.
+    pub payload: Option<Variant<WithoutAnnotations>>,
+
+    /// Whether to propagate up the containment tree.
+    ///
+    /// Vertexes propagate to their containing vertex and edges propagate to their source vertex.
+    pub propagate: bool,
+}
+
+impl Event {
+    /// Constructor.
+    pub fn new(event_id: ByteString) -> Self {
+        Self { event_id, payload: Default::default(), propagate: false }
+    }
+
+    /// With payload.
+    pub fn with_payload(mut self, payload: Variant<WithoutAnnotations>) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// With propagate.
+    pub fn with_propagate(mut self, propagate: bool) -> Self {
+        self.propagate = propagate;
+        self
+    }
+
+    /// Raise on a vertex or an edge.
+    ///
+    /// Handler errors are given to the error recipient and have no result. Propagation stops at a
+    /// vertex or an edge that was already raised on, so that containment cycles are tolerated.
+    ///
+    /// Returns the results in order of raising.
+    #[cfg(feature = "plugins")]
+    pub fn raise<StoreT, AnnotatedT, ErrorRecipientT>(
+        &self,
+        id: &ID,
+        library: &mut super::super::plugins::Library<StoreT>,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<Vec<EventResult<AnnotatedT>>, super::super::FloriaError>
+    where
+        StoreT: Clone + Send + super::super::Store,
+        AnnotatedT: Annotated + Default,
+        ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
+    {
+        use {
+            super::super::{plugins::*, store::*},
+            super::{expression::*, kind::*},
+            kutil::std::error::*,
+            std::collections::*,
+        };
+
+        tracing::debug!("raise: {} on {}", self.event_id, id);
+
+        let mut results = Vec::default();
+        let mut visited: HashSet<ID> = HashSet::default();
+        let mut next_id = Some(id.clone());
+
+        while let Some(id) = next_id.take() {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+
+            for event_handler in library.store.get_event_handlers(&id)? {
+                if event_handler.event_id != self.event_id {
+                    continue;
+                }
+
+                let mut call = event_handler.call.clone();
+                call.arguments.push(Expression::Literal(self.event_id.clone().into()));
+                if let Some(payload) = &self.payload {
+                    call.arguments.push(Expression::Literal(payload.clone()));
+                }
+
+                let site = Site::new(id.clone(), Default::default());
+                if let Some(value) = unwrap_or_give!(call.evaluate(&site, library).map(Some), errors, None) {
+                    results.push(EventResult::new(id.clone(), event_handler, value));
+                }
+            }
+
+            if self.propagate {
+                next_id = match id.kind {
+                    Kind::Vertex => library.store.get_vertex(&id)?.and_then(|vertex| vertex.containing_vertex_id),
+                    _ => library.store.get_edge(&id)?.map(|edge| edge.source_vertex_id),
+                };
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+//
+// EventResult
+//
+
+/// Event result.
+#[derive(Clone, Debug)]
+pub struct EventResult<AnnotatedT> {
+    /// ID of the vertex or edge that handled the event.
+    pub id: ID,
+
+    /// Event handler.
+    pub event_handler: EventHandler,
+
+    /// Value returned by the event handler's call.
+    pub value: Variant<AnnotatedT>,
+}
+
+impl<AnnotatedT> EventResult<AnnotatedT> {
+    /// Constructor.
+    pub fn new(id: ID, event_handler: EventHandler, value: Variant<AnnotatedT>) -> Self {
+        Self { id, event_handler, value }
+    }
+}
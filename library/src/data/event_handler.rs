@@ -1,6 +1,9 @@
-use super::call::*;
+use super::{call::*, from_variant::*};
 
-use kutil::std::immutable::*;
+use {
+    compris::{annotate::*, normal::*, resolve::*},
+    kutil::std::immutable::*,
+};
 
 //
 // EventHandler
@@ -17,3 +20,33 @@ pub struct EventHandler {
     /// Call.
     pub call: Call,
 }
+
+impl EventHandler {
+    /// Constructor.
+    pub fn new(event_id: ByteString, call: Call) -> Self {
+        Self { event_id, call }
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+        Ok(Self::new(
+            expect_text(get_required(map, "event_id")?)?.clone(),
+            Call::from_variant(get_required(map, "call")?)?,
+        ))
+    }
+
+    /// To Compris variant.
+    pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
+    where
+        AnnotatedT: Annotated + Default,
+    {
+        let mut map = Map::default();
+        map.into_insert("event_id", self.event_id.clone());
+        map.into_insert("call", &self.call);
+        map.into()
+    }
+}
@@ -1,7 +1,7 @@
-use super::call::*;
+use super::{call::*, from_variant::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::cli::depict::*,
     std::{fmt, io},
 };
@@ -21,6 +21,24 @@ pub enum Expression {
 }
 
 impl Expression {
+    /// From Compris variant.
+    ///
+    /// Expects either a "$literal" or a "$call" key.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        if let Some(literal) = map.into_get("$literal") {
+            Ok(Self::Literal(literal.clone().into_annotated()))
+        } else if let Some(call) = map.into_get("$call") {
+            Ok(Self::Call(Call::from_variant(call)?))
+        } else {
+            Err(malformed("Expression", "must have \"$literal\" or \"$call\"", variant))
+        }
+    }
+
     /// True if literal undefined.
     pub fn is_undefined(&self) -> bool {
         if let Self::Literal(Variant::Undefined) = self {
@@ -45,6 +63,25 @@ impl Expression {
             Self::Call(call) => call.evaluate(site, library),
         }
     }
+
+    /// Evaluate the expression asynchronously.
+    ///
+    /// Evaluation runs plugins, which are synchronous, so it is wrapped in
+    /// [try_block_in_place](super::super::try_block_in_place). It will thus fail within a
+    /// current-thread runtime. Use a [SyncStoreAdapter](super::super::SyncStoreAdapter) for the
+    /// library's store in order to evaluate against an [AsyncStore](super::super::AsyncStore).
+    #[cfg(all(feature = "plugins", feature = "tokio"))]
+    pub async fn evaluate_async<StoreT, AnnotatedT>(
+        &self,
+        site: &super::super::plugins::Site,
+        library: &mut super::super::plugins::Library<StoreT>,
+    ) -> Result<Variant<AnnotatedT>, super::super::FloriaError>
+    where
+        AnnotatedT: Annotated + Default,
+        StoreT: Clone + Send + super::super::Store,
+    {
+        super::super::try_block_in_place(|| self.evaluate(site, library))?
+    }
 }
 
 impl Default for Expression {
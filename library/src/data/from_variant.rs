@@ -0,0 +1,179 @@
+use super::{id::*, kind::*, metadata::*, property::*};
+
+use {
+    compris::{annotate::*, normal::*, resolve::*},
+    kutil::std::immutable::*,
+    std::collections::*,
+};
+
+// Utilities for decoding entities from their Compris variant representations. Errors are annotated
+// with the offending variant's annotations.
+
+/// Expect a [Map].
+pub fn expect_map<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<&Map<AnnotatedT>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match variant {
+        Variant::Map(map) => Ok(map),
+        _ => Err(IncompatibleVariantTypeError::new_from(variant, &["map"]).into()),
+    }
+}
+
+/// Expect a [List].
+pub fn expect_list<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<&List<AnnotatedT>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match variant {
+        Variant::List(list) => Ok(list),
+        _ => Err(IncompatibleVariantTypeError::new_from(variant, &["list"]).into()),
+    }
+}
+
+/// Expect a [Text].
+pub fn expect_text<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<&ByteString, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match variant {
+        Variant::Text(text) => Ok(&text.inner),
+        _ => Err(IncompatibleVariantTypeError::new_from(variant, &["text"]).into()),
+    }
+}
+
+/// Expect a [Boolean].
+pub fn expect_boolean<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<bool, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match variant {
+        Variant::Boolean(boolean) => Ok(boolean.inner),
+        _ => Err(IncompatibleVariantTypeError::new_from(variant, &["boolean"]).into()),
+    }
+}
+
+/// Get a required value from a [Map].
+pub fn get_required<'own, AnnotatedT>(
+    map: &'own Map<AnnotatedT>,
+    key: &'static str,
+) -> Result<&'own Variant<AnnotatedT>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    map.into_get(key).ok_or_else(|| MissingRequiredKeyError::new(Variant::from(key).with_annotations_from(map)).into())
+}
+
+/// Get the [ID] of an entity from a [Map].
+///
+/// The "kind" key must match the expected kind.
+pub fn get_entity_id<AnnotatedT>(map: &Map<AnnotatedT>, kind: Kind) -> Result<ID, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    let kind_variant = get_required(map, "kind")?;
+    if get_kind(kind_variant)? != kind {
+        return Err(malformed("ID", &format!("kind is not {}", kind), kind_variant));
+    }
+
+    get_id(get_required(map, "id")?, kind)
+}
+
+/// Get a [Kind].
+pub fn get_kind<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Kind, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    let kind: &str = expect_text(variant)?.as_ref();
+    kind.parse().map_err(|_| malformed("Kind", &format!("unknown kind: {}", kind), variant))
+}
+
+/// Get an [ID].
+pub fn get_id<AnnotatedT>(variant: &Variant<AnnotatedT>, kind: Kind) -> Result<ID, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    Ok(ID::parse(kind, expect_text(variant)?))
+}
+
+/// Get an optional [ID] from a [Map].
+pub fn get_optional_id<AnnotatedT>(
+    map: &Map<AnnotatedT>,
+    key: &'static str,
+    kind: Kind,
+) -> Result<Option<ID>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    map.into_get(key).map(|id| get_id(id, kind)).transpose()
+}
+
+/// Get optional [ID]s from a [Map].
+///
+/// Returns an empty [Vec] if the key is missing.
+pub fn get_ids<AnnotatedT>(
+    map: &Map<AnnotatedT>,
+    key: &'static str,
+    kind: Kind,
+) -> Result<Vec<ID>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match map.into_get(key) {
+        Some(ids) => expect_list(ids)?.into_iter().map(|id| get_id(id, kind)).collect(),
+        None => Ok(Default::default()),
+    }
+}
+
+/// Get the optional revision from a [Map].
+///
+/// Returns 0 if the key is missing.
+pub fn get_revision<AnnotatedT>(map: &Map<AnnotatedT>) -> Result<u64, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match map.into_get("revision") {
+        Some(Variant::UnsignedInteger(revision)) => Ok(revision.inner),
+        Some(Variant::Integer(revision)) if revision.inner >= 0 => Ok(revision.inner as u64),
+        Some(revision) => Err(IncompatibleVariantTypeError::new_from(revision, &["unsigned integer"]).into()),
+        None => Ok(0),
+    }
+}
+
+/// Get optional [Metadata] from a [Map].
+pub fn get_metadata<AnnotatedT>(map: &Map<AnnotatedT>) -> Result<Metadata, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    match map.into_get("metadata") {
+        Some(metadata) => Ok(expect_map(metadata)?.clone().into_annotated()),
+        None => Ok(Default::default()),
+    }
+}
+
+/// Get optional [Property]s from a [Map].
+///
+/// Returns an empty [BTreeMap] if the key is missing.
+pub fn get_properties<AnnotatedT>(
+    map: &Map<AnnotatedT>,
+    key: &'static str,
+) -> Result<BTreeMap<ByteString, Property>, ResolveError<AnnotatedT>>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    let mut properties = BTreeMap::default();
+    if let Some(properties_) = map.into_get(key) {
+        for (name, property) in expect_map(properties_)? {
+            properties.insert(expect_text(name)?.clone(), Property::from_variant(property)?);
+        }
+    }
+    Ok(properties)
+}
+
+/// Malformed error.
+pub fn malformed<AnnotatedT>(type_name: &str, reason: &str, variant: &Variant<AnnotatedT>) -> ResolveError<AnnotatedT>
+where
+    AnnotatedT: Annotated + Clone + Default,
+{
+    MalformedError::new(type_name.into(), reason.into()).with_annotations_from(variant).into()
+}
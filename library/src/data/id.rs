@@ -2,7 +2,7 @@ use super::{directory::*, kind::*};
 
 use {
     kutil::{cli::depict::*, std::immutable::*},
-    std::{fmt, io},
+    std::{collections::*, fmt, io},
 };
 
 //
@@ -53,6 +53,15 @@ impl ID {
         directory.split(":").map(|segment| segment.into()).collect()
     }
 
+    /// Remap.
+    ///
+    /// Does nothing if the ID is not in the map.
+    pub fn remap(&mut self, ids: &HashMap<ID, ID>) {
+        if let Some(id) = ids.get(self) {
+            *self = id.clone();
+        }
+    }
+
     /// To [Directory].
     pub fn to_directory(&self) -> Directory {
         let mut directory = self.directory.clone();
@@ -1,7 +1,10 @@
-use super::{super::store::*, directory::*, id::*, kind::*, metadata::*, property::*};
+use super::{
+    super::store::*, directory::*, event_handler::*, from_variant::*, id::*, kind::*, lifecycle::*, metadata::*,
+    property::*,
+};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::std::immutable::*,
     std::collections::*,
 };
@@ -16,6 +19,11 @@ pub struct Instance {
     /// ID.
     pub id: ID,
 
+    /// Revision.
+    ///
+    /// Set by the store whenever it is written. 0 means that it has not been stored.
+    pub revision: u64,
+
     /// Origin template ID.
     pub origin_template_id: Option<ID>,
 
@@ -27,6 +35,18 @@ pub struct Instance {
 
     /// Properties.
     pub properties: BTreeMap<ByteString, Property>,
+
+    /// Event handlers.
+    ///
+    /// These override those inherited from the origin template and the classes per event ID (see
+    /// [StoreEventHandlerUtilities]).
+    pub event_handlers: Vec<EventHandler>,
+
+    /// Lifecycle state.
+    pub lifecycle_state: LifecycleState,
+
+    /// Lifecycle history. The transitions that led to the lifecycle state, oldest first.
+    pub lifecycle_history: Vec<LifecycleTransition>,
 }
 
 impl Instance {
@@ -39,11 +59,96 @@ impl Instance {
     pub fn new_with(id: ID, origin_template_id: Option<ID>) -> Self {
         Self {
             id,
+            revision: 0,
             origin_template_id,
             metadata: Default::default(),
             class_ids: Default::default(),
             properties: Default::default(),
+            event_handlers: Default::default(),
+            lifecycle_state: Default::default(),
+            lifecycle_history: Default::default(),
+        }
+    }
+
+    /// Remove a class ID, including from the properties.
+    ///
+    /// Returns true if removed.
+    pub fn remove_class_id(&mut self, class_id: &ID) -> bool {
+        let length = self.class_ids.len();
+        self.class_ids.retain(|class_id_| class_id_ != class_id);
+        let mut removed = length != self.class_ids.len();
+
+        for property in self.properties.values_mut() {
+            if property.remove_class_id(class_id) {
+                removed = true;
+            }
+        }
+
+        removed
+    }
+
+    /// Remove the origin template ID if it is the provided one.
+    ///
+    /// Returns true if removed.
+    pub fn remove_origin_template_id(&mut self, template_id: &ID) -> bool {
+        if self.origin_template_id.as_ref() == Some(template_id) {
+            self.origin_template_id = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remap IDs, including in the properties.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.id.remap(ids);
+        if let Some(origin_template_id) = &mut self.origin_template_id {
+            origin_template_id.remap(ids);
         }
+        for class_id in &mut self.class_ids {
+            class_id.remap(ids);
+        }
+        for property in self.properties.values_mut() {
+            property.remap_ids(ids);
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// The kind is that of the instance (vertex or edge).
+    pub fn from_variant<AnnotatedT>(map: &Map<AnnotatedT>, kind: Kind) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let origin_template_kind = if kind == Kind::Vertex { Kind::VertexTemplate } else { Kind::EdgeTemplate };
+
+        let mut instance = Self::new_with(
+            get_entity_id(map, kind)?,
+            get_optional_id(map, "origin_template_id", origin_template_kind)?,
+        );
+
+        instance.revision = get_revision(map)?;
+        instance.metadata = get_metadata(map)?;
+        instance.class_ids = get_ids(map, "class_ids", Kind::Class)?;
+        instance.properties = get_properties(map, "properties")?;
+
+        if let Some(event_handlers) = map.into_get("event_handlers") {
+            instance.event_handlers =
+                expect_list(event_handlers)?.into_iter().map(EventHandler::from_variant).collect::<Result<_, _>>()?;
+        }
+
+        if let Some(lifecycle_state) = map.into_get("lifecycle_state") {
+            instance.lifecycle_state = LifecycleState::from_variant(lifecycle_state)?;
+        }
+
+        if let Some(lifecycle_history) = map.into_get("lifecycle_history") {
+            instance.lifecycle_history = expect_list(lifecycle_history)?
+                .into_iter()
+                .map(LifecycleTransition::from_variant)
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(instance)
     }
 
     /// To Compris variant.
@@ -59,6 +164,9 @@ impl Instance {
     {
         map.into_insert("kind", self.id.kind.to_string());
         map.into_insert("id", self.id.to_string());
+        if self.revision != 0 {
+            map.into_insert("revision", self.revision);
+        }
         if let Some(origin_template_id) = &self.origin_template_id {
             map.into_insert("origin_template_id", origin_template_id.to_string());
         }
@@ -87,10 +195,88 @@ impl Instance {
             map.into_insert("properties", properties);
         }
 
+        if !self.event_handlers.is_empty() {
+            let event_handlers: List<_> =
+                self.event_handlers.iter().map(|event_handler| event_handler.to_variant()).collect();
+            map.into_insert("event_handlers", event_handlers);
+        }
+
+        if self.lifecycle_state != LifecycleState::Initial {
+            map.into_insert("lifecycle_state", self.lifecycle_state.to_string());
+        }
+
+        if !self.lifecycle_history.is_empty() {
+            let lifecycle_history: List<_> =
+                self.lifecycle_history.iter().map(|transition| transition.to_variant()).collect();
+            map.into_insert("lifecycle_history", lifecycle_history);
+        }
+
         Ok(())
     }
 
+    /// Apply the property definitions of the instance's classes, including inherited ones.
+    ///
+    /// Missing properties and properties without a value are assigned the default value, if there
+    /// is one. Violations are given to the error recipient as
+    /// [PropertyViolation](super::super::FloriaError::PropertyViolation).
+    ///
+    /// Returns true if a property was assigned.
+    pub fn apply_property_definitions<StoreT, ErrorRecipientT>(
+        &mut self,
+        store: &StoreT,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<bool, super::super::FloriaError>
+    where
+        StoreT: Store,
+        ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
+    {
+        use {super::super::errors::*, kutil::std::error::*};
+
+        let mut assigned = false;
+
+        for (property_name, property_definition) in store.get_property_definitions(&self.class_ids)? {
+            let value = match self.properties.get_mut(&property_name) {
+                Some(property) => {
+                    if property.value.is_none()
+                        && let Some(default) = &property_definition.default
+                    {
+                        property.value = Some(default.clone());
+                        assigned = true;
+                    }
+                    property.value.as_ref()
+                }
+
+                None => match &property_definition.default {
+                    Some(default) => {
+                        assigned = true;
+                        self.properties
+                            .entry(property_name.clone())
+                            .or_insert(Property::new(Some(default.clone()), None, None, false))
+                            .value
+                            .as_ref()
+                    }
+
+                    None => None,
+                },
+            };
+
+            for violation in property_definition.check(value) {
+                errors.give(PropertyViolationError::new(
+                    self.id.clone(),
+                    vec![property_name.to_string()],
+                    violation,
+                ))?;
+            }
+        }
+
+        Ok(assigned)
+    }
+
     /// Update.
+    ///
+    /// Properties without their own updaters use those of their property definitions (see
+    /// [apply_property_definitions](Self::apply_property_definitions)). Missing properties that
+    /// have definitions with updaters are added.
     #[cfg(feature = "plugins")]
     pub fn update<StoreT, ErrorRecipientT>(
         &mut self,
@@ -101,10 +287,21 @@ impl Instance {
         StoreT: Clone + Send + super::super::Store,
         ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
     {
+        let property_definitions = library.store.get_property_definitions(&self.class_ids)?;
+
+        for (property_name, property_definition) in &property_definitions {
+            if property_definition.updater.is_some() && !self.properties.contains_key(property_name) {
+                self.properties.insert(property_name.clone(), Property::new(None, None, None, false));
+            }
+        }
+
         let mut updated = false;
 
         for (property_name, property) in self.properties.iter_mut() {
-            if property.update(&self.id, &property_name, library, errors)? {
+            let class_updater = property_definitions
+                .get(property_name)
+                .and_then(|property_definition| property_definition.updater.as_ref());
+            if property.update(&self.id, &property_name, class_updater, library, errors)? {
                 updated = true;
             }
         }
@@ -113,6 +310,9 @@ impl Instance {
     }
 
     /// Validate.
+    ///
+    /// Properties without their own validators use those of their property definitions (see
+    /// [apply_property_definitions](Self::apply_property_definitions)).
     #[cfg(feature = "plugins")]
     pub fn validate<StoreT, ErrorRecipientT>(
         &mut self,
@@ -123,10 +323,15 @@ impl Instance {
         StoreT: Clone + Send + super::super::Store,
         ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
     {
+        let property_definitions = library.store.get_property_definitions(&self.class_ids)?;
+
         let mut valid = true;
 
         for (property_name, property) in self.properties.iter_mut() {
-            if !property.validate(&self.id, &property_name, library, errors)? {
+            let class_validator = property_definitions
+                .get(property_name)
+                .and_then(|property_definition| property_definition.validator.as_ref());
+            if !property.validate(&self.id, &property_name, class_validator, library, errors)? {
                 valid = false;
             }
         }
@@ -0,0 +1,140 @@
+use super::{event::*, from_variant::*};
+
+use {
+    compris::{annotate::*, normal::*, resolve::*},
+    kutil::std::*,
+    std::fmt,
+};
+
+//
+// LifecycleState
+//
+
+/// Lifecycle state of a vertex or an edge.
+///
+/// The allowed transitions are declared by classes (see
+/// [StoreLifecycleUtilities](super::super::StoreLifecycleUtilities)).
+#[derive(Clone, Copy, Debug, Default, Display, Eq, FromStr, Hash, PartialEq)]
+#[display(lowercase)]
+#[from_str(lowercase)]
+pub enum LifecycleState {
+    /// Initial.
+    #[default]
+    Initial,
+
+    /// Creating.
+    Creating,
+
+    /// Created.
+    Created,
+
+    /// Configuring.
+    Configuring,
+
+    /// Started.
+    Started,
+
+    /// Stopping.
+    Stopping,
+
+    /// Deleted.
+    Deleted,
+
+    /// Error.
+    Error,
+}
+
+impl LifecycleState {
+    /// Transition a vertex or an edge to this state and raise the event.
+    ///
+    /// The transition is recorded in the store (see
+    /// [transition_lifecycle](super::super::StoreLifecycleUtilities::transition_lifecycle)) before
+    /// the event is raised, so handlers see the new state.
+    #[cfg(feature = "plugins")]
+    pub fn enter<StoreT, AnnotatedT, ErrorRecipientT>(
+        self,
+        id: &super::id::ID,
+        library: &mut super::super::plugins::Library<StoreT>,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<Vec<EventResult<AnnotatedT>>, super::super::FloriaError>
+    where
+        StoreT: Clone + Send + super::super::Store,
+        AnnotatedT: Annotated + Default,
+        ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
+    {
+        use super::super::store::*;
+
+        let transition = library.store.transition_lifecycle(id, self)?;
+        transition.to_event().raise(id, library, errors)
+    }
+
+    /// Parse.
+    pub fn parse(state: &str) -> Result<Self, String> {
+        state.parse().map_err(|_| format!("unknown lifecycle state: {}", state))
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        Self::parse(expect_text(variant)?).map_err(|error| malformed("LifecycleState", &error, variant))
+    }
+}
+
+//
+// LifecycleTransition
+//
+
+/// Lifecycle transition.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LifecycleTransition {
+    /// From.
+    pub from: LifecycleState,
+
+    /// To.
+    pub to: LifecycleState,
+}
+
+impl LifecycleTransition {
+    /// Constructor.
+    pub fn new(from: LifecycleState, to: LifecycleState) -> Self {
+        Self { from, to }
+    }
+
+    /// To [Event].
+    ///
+    /// The event ID is the name of the new state and the payload is the transition.
+    pub fn to_event(&self) -> Event {
+        Event::new(self.to.to_string().into()).with_payload(self.to_variant())
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+        Ok(Self::new(
+            LifecycleState::from_variant(get_required(map, "from")?)?,
+            LifecycleState::from_variant(get_required(map, "to")?)?,
+        ))
+    }
+
+    /// To Compris variant.
+    pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
+    where
+        AnnotatedT: Annotated + Default,
+    {
+        let mut map = Map::default();
+        map.into_insert("from", self.from.to_string());
+        map.into_insert("to", self.to.to_string());
+        map.into()
+    }
+}
+
+impl fmt::Display for LifecycleTransition {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} -> {}", self.from, self.to)
+    }
+}
@@ -1,16 +1,23 @@
 mod call;
 mod class;
+mod compare;
 mod depict;
 mod directory;
 mod edge;
 mod edge_template;
+mod entity;
+mod event;
 mod event_handler;
 mod expression;
+pub(crate) mod from_variant;
 mod id;
 mod instance;
 mod kind;
+mod lifecycle;
 mod metadata;
 mod property;
+mod property_definition;
+mod revision;
 mod template;
 mod vertex;
 mod vertex_finder;
@@ -19,7 +26,8 @@ mod vertex_template;
 
 #[allow(unused_imports)]
 pub use {
-    call::*, class::*, depict::*, directory::*, edge::*, edge_template::*, event_handler::*, expression::*, id::*,
-    instance::*, kind::*, metadata::*, property::*, template::*, vertex::*, vertex_finder::*, vertex_selector::*,
+    call::*, class::*, compare::*, depict::*, directory::*, edge::*, edge_template::*, entity::*, event::*,
+    event_handler::*, expression::*, id::*, instance::*, kind::*, lifecycle::*, metadata::*, property::*,
+    property_definition::*, revision::*, template::*, vertex::*, vertex_finder::*, vertex_selector::*,
     vertex_template::*,
 };
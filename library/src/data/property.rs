@@ -1,9 +1,9 @@
-use super::{super::store::*, depict::*, expression::*, id::*, metadata::*};
+use super::{super::store::*, depict::*, expression::*, from_variant::*, id::*, kind::*, metadata::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::cli::depict::*,
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -45,6 +45,45 @@ impl Property {
         Self { metadata: Default::default(), class_ids: Default::default(), value, updater, validator, read_only }
     }
 
+    /// Remove a class ID.
+    ///
+    /// Returns true if removed.
+    pub fn remove_class_id(&mut self, class_id: &ID) -> bool {
+        let length = self.class_ids.len();
+        self.class_ids.retain(|class_id_| class_id_ != class_id);
+        length != self.class_ids.len()
+    }
+
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        for class_id in &mut self.class_ids {
+            class_id.remap(ids);
+        }
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        let mut property = Self::new(
+            map.into_get("value").map(|value| value.clone().into_annotated()),
+            map.into_get("updater").map(Expression::from_variant).transpose()?,
+            map.into_get("validator").map(Expression::from_variant).transpose()?,
+            match map.into_get("read_only") {
+                Some(read_only) => expect_boolean(read_only)?,
+                None => false,
+            },
+        );
+
+        property.metadata = get_metadata(map)?;
+        property.class_ids = get_ids(map, "class_ids", Kind::Class)?;
+
+        Ok(property)
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -101,11 +140,14 @@ impl Property {
     }
 
     /// Update.
+    ///
+    /// The class updater is used if the property does not have its own updater.
     #[cfg(feature = "plugins")]
     pub fn update<StoreT, ErrorRecipientT>(
         &mut self,
         id: &ID,
         property_name: &str,
+        class_updater: Option<&Expression>,
         library: &mut super::super::plugins::Library<StoreT>,
         errors: &mut ErrorRecipientT,
     ) -> Result<bool, super::super::FloriaError>
@@ -120,7 +162,7 @@ impl Property {
             return Ok(false);
         }
 
-        if let Some(updater) = &self.updater {
+        if let Some(updater) = self.updater.as_ref().or(class_updater) {
             let site = Site::new(id.clone(), vec![property_name.into()]);
             self.value = unwrap_or_give!(updater.evaluate(&site, library).map(Some), errors, None);
             return Ok(true);
@@ -130,11 +172,14 @@ impl Property {
     }
 
     /// Validate.
+    ///
+    /// The class validator is used if the property does not have its own validator.
     #[cfg(feature = "plugins")]
     pub fn validate<StoreT, ErrorRecipientT>(
         &mut self,
         id: &ID,
         property_name: &str,
+        class_validator: Option<&Expression>,
         library: &mut super::super::plugins::Library<StoreT>,
         errors: &mut ErrorRecipientT,
     ) -> Result<bool, super::super::FloriaError>
@@ -147,7 +192,7 @@ impl Property {
             kutil::std::error::*,
         };
 
-        if let Some(validator) = &self.validator {
+        if let Some(validator) = self.validator.as_ref().or(class_validator) {
             let site = Site::new(id.clone(), vec![property_name.into()]);
             let valid =
                 unwrap_or_give!(validator.evaluate::<_, WithoutAnnotations>(&site, library).map(Some), errors, None,);
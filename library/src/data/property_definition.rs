@@ -0,0 +1,405 @@
+use super::{compare::*, expression::*, from_variant::*};
+
+use {
+    compris::{annotate::*, normal::*, resolve::*},
+    kutil::std::{iter::*, *},
+    regex::*,
+    std::{cmp::*, fmt},
+};
+
+//
+// PropertyDefinition
+//
+
+/// Property definition.
+///
+/// Declared by a [Class](super::Class) for the properties of its member vertexes and edges.
+#[derive(Clone, Debug, Default)]
+pub struct PropertyDefinition {
+    /// Data type. [None] means any type.
+    pub data_type: Option<DataType>,
+
+    /// Required.
+    ///
+    /// A required property must have a value, unless it has a default.
+    pub required: bool,
+
+    /// Default value.
+    ///
+    /// Assigned to properties that are missing or have no value.
+    pub default: Option<Variant<WithoutAnnotations>>,
+
+    /// Constraints.
+    pub constraints: Vec<PropertyConstraint>,
+
+    /// Updater.
+    ///
+    /// Used for properties that do not have their own updater.
+    pub updater: Option<Expression>,
+
+    /// Validator.
+    ///
+    /// Used for properties that do not have their own validator.
+    pub validator: Option<Expression>,
+}
+
+impl PropertyDefinition {
+    /// With data type.
+    pub fn with_data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = Some(data_type);
+        self
+    }
+
+    /// With required.
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// With default.
+    pub fn with_default(mut self, default: Variant<WithoutAnnotations>) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// With constraint.
+    pub fn with_constraint(mut self, constraint: PropertyConstraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// With updater.
+    pub fn with_updater(mut self, updater: Expression) -> Self {
+        self.updater = Some(updater);
+        self
+    }
+
+    /// With validator.
+    pub fn with_validator(mut self, validator: Expression) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Violations of a value, if any. [None] means no value.
+    pub fn check(&self, value: Option<&Variant<WithoutAnnotations>>) -> Vec<PropertyViolation> {
+        let mut violations = Vec::default();
+
+        match value {
+            Some(value) => {
+                if let Some(data_type) = &self.data_type
+                    && !data_type.matches(value)
+                {
+                    violations.push(PropertyViolation::DataType(*data_type));
+                }
+
+                for constraint in &self.constraints {
+                    if !constraint.matches(value) {
+                        violations.push(PropertyViolation::Constraint(constraint.clone().into()));
+                    }
+                }
+            }
+
+            None => {
+                if self.required {
+                    violations.push(PropertyViolation::Missing);
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        let mut property_definition = Self::default();
+
+        if let Some(data_type) = map.into_get("data_type") {
+            let data_type_: &str = expect_text(data_type)?.as_ref();
+            property_definition.data_type = Some(data_type_.parse().map_err(|_| {
+                malformed("PropertyDefinition", &format!("unknown data type: {}", data_type_), data_type)
+            })?);
+        }
+
+        if let Some(required) = map.into_get("required") {
+            property_definition.required = expect_boolean(required)?;
+        }
+
+        property_definition.default = map.into_get("default").map(|default| default.clone().into_annotated());
+
+        if let Some(constraints) = map.into_get("constraints") {
+            for constraint in expect_list(constraints)? {
+                property_definition.constraints.push(PropertyConstraint::from_variant(constraint)?);
+            }
+        }
+
+        property_definition.updater = map.into_get("updater").map(Expression::from_variant).transpose()?;
+        property_definition.validator = map.into_get("validator").map(Expression::from_variant).transpose()?;
+
+        Ok(property_definition)
+    }
+
+    /// To Compris variant.
+    pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let mut map = Map::default();
+
+        if let Some(data_type) = &self.data_type {
+            map.into_insert("data_type", data_type.to_string());
+        }
+
+        map.into_insert("required", self.required);
+
+        if let Some(default) = &self.default {
+            map.into_insert("default", default.clone().into_annotated());
+        }
+
+        if !self.constraints.is_empty() {
+            let constraints: List<_> = self.constraints.iter().map(|constraint| constraint.to_variant()).collect();
+            map.into_insert("constraints", constraints);
+        }
+
+        if let Some(updater) = &self.updater {
+            map.into_insert("updater", updater);
+        }
+
+        if let Some(validator) = &self.validator {
+            map.into_insert("validator", validator);
+        }
+
+        map.into()
+    }
+}
+
+//
+// DataType
+//
+
+/// Property data type.
+#[derive(Clone, Copy, Debug, Display, Eq, FromStr, Hash, PartialEq)]
+#[display(lowercase)]
+#[from_str(lowercase)]
+pub enum DataType {
+    /// Boolean.
+    Boolean,
+
+    /// Integer. Unsigned integers that fit are accepted.
+    Integer,
+
+    /// Unsigned integer. Non-negative integers are accepted.
+    #[strings("unsigned_integer")]
+    UnsignedInteger,
+
+    /// Float. Integers are accepted.
+    Float,
+
+    /// Text.
+    Text,
+
+    /// Blob.
+    Blob,
+
+    /// List.
+    List,
+
+    /// Map.
+    Map,
+}
+
+impl DataType {
+    /// True if the value is of this type.
+    pub fn matches<AnnotatedT>(&self, value: &Variant<AnnotatedT>) -> bool {
+        match (self, value) {
+            (Self::Boolean, Variant::Boolean(_))
+            | (Self::Integer, Variant::Integer(_))
+            | (Self::UnsignedInteger, Variant::UnsignedInteger(_))
+            | (Self::Float, Variant::Float(_) | Variant::Integer(_) | Variant::UnsignedInteger(_))
+            | (Self::Text, Variant::Text(_))
+            | (Self::Blob, Variant::Blob(_))
+            | (Self::List, Variant::List(_))
+            | (Self::Map, Variant::Map(_)) => true,
+
+            (Self::Integer, Variant::UnsignedInteger(unsigned_integer)) => unsigned_integer.inner <= i64::MAX as u64,
+            (Self::UnsignedInteger, Variant::Integer(integer)) => integer.inner >= 0,
+
+            _ => false,
+        }
+    }
+}
+
+//
+// PropertyConstraint
+//
+
+/// Property constraint.
+#[derive(Clone, Debug)]
+pub enum PropertyConstraint {
+    /// Inclusive range. Numbers are compared by value regardless of representation, other values
+    /// only with values of the same type.
+    Range {
+        /// Minimum.
+        minimum: Option<Variant<WithoutAnnotations>>,
+
+        /// Maximum.
+        maximum: Option<Variant<WithoutAnnotations>>,
+    },
+
+    /// Regular expression that text values must match. Use "^" and "$" to match the whole text.
+    Pattern(Regex),
+
+    /// Valid values.
+    Enumeration(Vec<Variant<WithoutAnnotations>>),
+}
+
+impl PropertyConstraint {
+    /// True if the value satisfies the constraint.
+    pub fn matches(&self, value: &Variant<WithoutAnnotations>) -> bool {
+        match self {
+            Self::Range { minimum, maximum } => {
+                minimum.as_ref().is_none_or(|minimum| {
+                    matches!(compare_variants(value, minimum), Some(Ordering::Greater | Ordering::Equal))
+                }) && maximum.as_ref().is_none_or(|maximum| {
+                    matches!(compare_variants(value, maximum), Some(Ordering::Less | Ordering::Equal))
+                })
+            }
+
+            Self::Pattern(pattern) => match value {
+                Variant::Text(text) => pattern.is_match(&text.inner),
+                _ => false,
+            },
+
+            Self::Enumeration(values) => {
+                values.iter().any(|value_| compare_variants(value, value_) == Some(Ordering::Equal))
+            }
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// A single-key map, with "range", "pattern", or "enumeration" as the key.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        if map.inner.len() != 1 {
+            return Err(malformed("PropertyConstraint", "not a single-key map", variant));
+        }
+
+        if let Some(range) = map.into_get("range") {
+            let range = expect_map(range)?;
+            Ok(Self::Range {
+                minimum: range.into_get("minimum").map(|minimum| minimum.clone().into_annotated()),
+                maximum: range.into_get("maximum").map(|maximum| maximum.clone().into_annotated()),
+            })
+        } else if let Some(pattern) = map.into_get("pattern") {
+            Ok(Self::Pattern(
+                Regex::new(expect_text(pattern)?)
+                    .map_err(|error| malformed("PropertyConstraint", &error.to_string(), pattern))?,
+            ))
+        } else if let Some(values) = map.into_get("enumeration") {
+            Ok(Self::Enumeration(
+                expect_list(values)?.into_iter().map(|value| value.clone().into_annotated()).collect(),
+            ))
+        } else {
+            Err(malformed("PropertyConstraint", "unknown constraint", variant))
+        }
+    }
+
+    /// To Compris variant.
+    pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let mut map = Map::default();
+
+        match self {
+            Self::Range { minimum, maximum } => {
+                let mut range = Map::default();
+                if let Some(minimum) = minimum {
+                    range.into_insert("minimum", minimum.clone().into_annotated());
+                }
+                if let Some(maximum) = maximum {
+                    range.into_insert("maximum", maximum.clone().into_annotated());
+                }
+                map.into_insert("range", range);
+            }
+
+            Self::Pattern(pattern) => {
+                map.into_insert("pattern", pattern.as_str().to_string());
+            }
+
+            Self::Enumeration(values) => {
+                let values: List<_> = values.iter().map(|value| value.clone().into_annotated()).collect();
+                map.into_insert("enumeration", values);
+            }
+        }
+
+        map.into()
+    }
+}
+
+impl fmt::Display for PropertyConstraint {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Range { minimum, maximum } => {
+                write!(formatter, "range ")?;
+                if let Some(minimum) = minimum {
+                    write!(formatter, "{}", minimum)?;
+                }
+                write!(formatter, "..")?;
+                if let Some(maximum) = maximum {
+                    write!(formatter, "{}", maximum)?;
+                }
+                Ok(())
+            }
+
+            Self::Pattern(pattern) => write!(formatter, "pattern {}", pattern),
+
+            Self::Enumeration(values) => {
+                write!(formatter, "enumeration ")?;
+                for (value, first) in IterateWithFirst::new(values) {
+                    if !first {
+                        write!(formatter, ", ")?;
+                    }
+                    write!(formatter, "{}", value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+//
+// PropertyViolation
+//
+
+/// Property violation.
+#[derive(Clone, Debug)]
+pub enum PropertyViolation {
+    /// Required but missing.
+    Missing,
+
+    /// Wrong data type.
+    DataType(DataType),
+
+    /// Constraint not satisfied.
+    Constraint(Box<PropertyConstraint>),
+}
+
+impl fmt::Display for PropertyViolation {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(formatter, "missing required value"),
+            Self::DataType(data_type) => write!(formatter, "not of type {}", data_type),
+            Self::Constraint(constraint) => write!(formatter, "violates {}", constraint),
+        }
+    }
+}
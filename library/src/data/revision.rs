@@ -0,0 +1,89 @@
+use super::{class::*, edge::*, edge_template::*, entity::*, vertex::*, vertex_template::*};
+
+//
+// Revisioned
+//
+
+/// Has a revision.
+///
+/// The revision is set by the store whenever the entity is written. 0 means that the entity has
+/// not been stored.
+pub trait Revisioned {
+    /// Revision.
+    fn get_revision(&self) -> u64;
+
+    /// Set revision.
+    fn set_revision(&mut self, revision: u64);
+}
+
+impl Revisioned for Class {
+    fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+}
+
+impl Revisioned for VertexTemplate {
+    fn get_revision(&self) -> u64 {
+        self.template.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.template.revision = revision;
+    }
+}
+
+impl Revisioned for EdgeTemplate {
+    fn get_revision(&self) -> u64 {
+        self.template.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.template.revision = revision;
+    }
+}
+
+impl Revisioned for Vertex {
+    fn get_revision(&self) -> u64 {
+        self.instance.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.instance.revision = revision;
+    }
+}
+
+impl Revisioned for Edge {
+    fn get_revision(&self) -> u64 {
+        self.instance.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.instance.revision = revision;
+    }
+}
+
+impl Revisioned for Entity {
+    fn get_revision(&self) -> u64 {
+        match self {
+            Self::Class(class) => class.get_revision(),
+            Self::VertexTemplate(vertex_template) => vertex_template.get_revision(),
+            Self::EdgeTemplate(edge_template) => edge_template.get_revision(),
+            Self::Vertex(vertex) => vertex.get_revision(),
+            Self::Edge(edge) => edge.get_revision(),
+        }
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        match self {
+            Self::Class(class) => class.set_revision(revision),
+            Self::VertexTemplate(vertex_template) => vertex_template.set_revision(revision),
+            Self::EdgeTemplate(edge_template) => edge_template.set_revision(revision),
+            Self::Vertex(vertex) => vertex.set_revision(revision),
+            Self::Edge(edge) => edge.set_revision(revision),
+        }
+    }
+}
@@ -1,7 +1,10 @@
-use super::{super::store::*, directory::*, event_handler::*, id::*, instance::*, kind::*, metadata::*, property::*};
+use super::{
+    super::store::*, directory::*, event_handler::*, from_variant::*, id::*, instance::*, kind::*, metadata::*,
+    property::*,
+};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::std::immutable::*,
     std::collections::*,
 };
@@ -16,6 +19,11 @@ pub struct Template {
     /// ID.
     pub id: ID,
 
+    /// Revision.
+    ///
+    /// Set by the store whenever it is written. 0 means that it has not been stored.
+    pub revision: u64,
+
     /// Metadata.
     pub metadata: Metadata,
 
@@ -34,6 +42,7 @@ impl Template {
     pub fn new(id: ID) -> Self {
         Self {
             id,
+            revision: 0,
             metadata: Default::default(),
             class_ids: Default::default(),
             property_templates: Default::default(),
@@ -41,6 +50,56 @@ impl Template {
         }
     }
 
+    /// Remove a class ID, including from the property templates.
+    ///
+    /// Returns true if removed.
+    pub fn remove_class_id(&mut self, class_id: &ID) -> bool {
+        let length = self.class_ids.len();
+        self.class_ids.retain(|class_id_| class_id_ != class_id);
+        let mut removed = length != self.class_ids.len();
+
+        for property_template in self.property_templates.values_mut() {
+            if property_template.remove_class_id(class_id) {
+                removed = true;
+            }
+        }
+
+        removed
+    }
+
+    /// Remap IDs, including in the property templates.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.id.remap(ids);
+        for class_id in &mut self.class_ids {
+            class_id.remap(ids);
+        }
+        for property_template in self.property_templates.values_mut() {
+            property_template.remap_ids(ids);
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// The kind is that of the template (vertex template or edge template).
+    pub fn from_variant<AnnotatedT>(map: &Map<AnnotatedT>, kind: Kind) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let mut template = Self::new(get_entity_id(map, kind)?);
+
+        template.revision = get_revision(map)?;
+        template.metadata = get_metadata(map)?;
+        template.class_ids = get_ids(map, "class_ids", Kind::Class)?;
+        template.property_templates = get_properties(map, "property_templates")?;
+
+        if let Some(event_handlers) = map.into_get("event_handlers") {
+            template.event_handlers =
+                expect_list(event_handlers)?.into_iter().map(EventHandler::from_variant).collect::<Result<_, _>>()?;
+        }
+
+        Ok(template)
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -54,6 +113,9 @@ impl Template {
     {
         map.into_insert("kind", self.id.kind.to_string());
         map.into_insert("id", self.id.to_string());
+        if self.revision != 0 {
+            map.into_insert("revision", self.revision);
+        }
         map.into_insert("metadata", self.metadata.clone().into_annotated());
 
         if !self.class_ids.is_empty() {
@@ -80,10 +142,19 @@ impl Template {
             map.into_insert("property_templates", property_templates);
         }
 
+        if !self.event_handlers.is_empty() {
+            let event_handlers: List<_> =
+                self.event_handlers.iter().map(|event_handler| event_handler.to_variant()).collect();
+            map.into_insert("event_handlers", event_handlers);
+        }
+
         Ok(())
     }
 
     /// Instantiate.
+    ///
+    /// The event handlers are not copied, because they are found via the instance's origin
+    /// template (see [StoreEventHandlerUtilities](super::super::StoreEventHandlerUtilities)).
     pub fn instantiate<StoreT>(&self, kind: Kind, directory: &Directory, store: &StoreT) -> Result<Instance, StoreError>
     where
         StoreT: Store,
@@ -1,12 +1,12 @@
-use super::{super::store::*, depict::*, directory::*, id::*, instance::*, kind::*};
+use super::{super::store::*, depict::*, directory::*, from_variant::*, id::*, instance::*, kind::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::{
         cli::depict::*,
         std::{immutable::*, iter::*},
     },
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -59,6 +59,41 @@ impl Vertex {
         }
     }
 
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.instance.remap_ids(ids);
+        if let Some(containing_vertex_id) = &mut self.containing_vertex_id {
+            containing_vertex_id.remap(ids);
+        }
+        for contained_vertex_id in &mut self.contained_vertex_ids {
+            contained_vertex_id.remap(ids);
+        }
+        for outgoing_edge_id in &mut self.outgoing_edge_ids {
+            outgoing_edge_id.remap(ids);
+        }
+        for incoming_edge_id in &mut self.incoming_edge_ids {
+            incoming_edge_id.remap(ids);
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// Expects the non-embedded representation.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        Ok(Self {
+            instance: Instance::from_variant(map, Kind::Vertex)?,
+            containing_vertex_id: get_optional_id(map, "containing_vertex_id", Kind::Vertex)?,
+            contained_vertex_ids: get_ids(map, "contained_vertex_ids", Kind::Vertex)?,
+            outgoing_edge_ids: get_ids(map, "outgoing_edge_ids", Kind::Edge)?,
+            incoming_edge_ids: get_ids(map, "incoming_edge_ids", Kind::Edge)?,
+        })
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -129,6 +164,12 @@ impl Vertex {
     }
 
     /// Update.
+    ///
+    /// Updates the properties, applies the property definitions, and then validates the properties.
+    /// Also applies the property definitions of the outgoing edges.
+    ///
+    /// Writes are conditional on the revision, so if the vertex was modified in the store since it
+    /// was read then [StoreError::Conflict] is returned.
     #[cfg(feature = "plugins")]
     pub fn update<StoreT, ErrorRecipientT>(
         &mut self,
@@ -139,10 +180,21 @@ impl Vertex {
         StoreT: Clone + Send + Store,
         ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
     {
-        if self.instance.update(library, errors)? {
-            library.store.add_vertex(self.clone())?;
-            if !self.instance.validate(library, errors)? {
-                library.store.add_vertex(self.clone())?;
+        let updated = self.instance.update(library, errors)?;
+        if self.instance.apply_property_definitions(&library.store, errors)? || updated {
+            self.instance.revision = library.store.update_vertex_if_revision(self.clone())?;
+        }
+
+        // Validators may be inherited from classes, so we validate even if nothing was updated
+        if !self.instance.validate(library, errors)? {
+            self.instance.revision = library.store.update_vertex_if_revision(self.clone())?;
+        }
+
+        for edge_id in &self.outgoing_edge_ids {
+            if let Some(mut edge) = library.store.get_edge(edge_id)?
+                && edge.instance.apply_property_definitions(&library.store, errors)?
+            {
+                library.store.update_edge_if_revision(edge)?;
             }
         }
 
@@ -179,16 +231,16 @@ impl Vertex {
             }
         }
 
-        let mut vertex = self.clone();
+        let mut outgoing_edge_ids = Vec::default();
 
-        match &vertex.instance.origin_template_id {
+        match &self.instance.origin_template_id {
             Some(origin_template_id) => match library.store.get_vertex_template(origin_template_id)? {
                 Some(vertex_template) => {
                     for outgoing_edge_template_id in &vertex_template.outgoing_edge_template_ids {
                         match library.store.get_edge_template(outgoing_edge_template_id)? {
                             Some(outgoing_edge_template) => {
                                 match outgoing_edge_template.target_selector.select(
-                                    &vertex.instance.id,
+                                    &self.instance.id,
                                     outgoing_edge_template_id,
                                     library,
                                     errors,
@@ -196,12 +248,13 @@ impl Vertex {
                                     Some(target_vertex_id) => {
                                         let outgoing_edge_id = outgoing_edge_template.instantiate(
                                             directory,
-                                            vertex.instance.id.clone(),
+                                            self.instance.id.clone(),
                                             target_vertex_id,
                                             &library.store,
+                                            errors,
                                         )?;
 
-                                        vertex.outgoing_edge_ids.push(outgoing_edge_id);
+                                        outgoing_edge_ids.push(outgoing_edge_id);
                                     }
 
                                     None => errors.give(super::super::FloriaError::Instantiation(
@@ -223,7 +276,22 @@ impl Vertex {
             None => {}
         }
 
-        library.store.add_vertex(vertex)?;
+        if outgoing_edge_ids.is_empty() {
+            return Ok(());
+        }
+
+        // The vertex may have been modified in the store since it was read (e.g. by target
+        // selectors), so we read it again and retry on conflicts
+        while let Some(mut vertex) = library.store.get_vertex(&self.instance.id)? {
+            vertex.outgoing_edge_ids.extend(outgoing_edge_ids.iter().cloned());
+            match library.store.update_vertex_if_revision(vertex) {
+                Err(StoreError::Conflict(_)) => {}
+                result => {
+                    result?;
+                    break;
+                }
+            }
+        }
 
         Ok(())
     }
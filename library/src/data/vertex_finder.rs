@@ -1,7 +1,7 @@
-use super::{call::*, directory::*};
+use super::{call::*, directory::*, from_variant::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::cli::depict::*,
     std::io,
 };
@@ -26,6 +26,30 @@ impl VertexFinder {
         Self { directories: None, finder: filter }
     }
 
+    /// From Compris variant.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        let mut vertex_finder = Self::new(Call::from_variant(get_required(map, "finder")?)?);
+
+        if let Some(directories) = map.into_get("directories") {
+            let directories = expect_list(directories)?;
+            let mut directories_ = Vec::with_capacity(directories.inner.len());
+            for directory in directories {
+                let directory = expect_list(directory)?;
+                directories_.push(
+                    directory.into_iter().map(|segment| expect_text(segment).cloned()).collect::<Result<_, _>>()?,
+                );
+            }
+            vertex_finder.directories = Some(directories_);
+        }
+
+        Ok(vertex_finder)
+    }
+
     /// To Compris variant.
     pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
     where
@@ -1,9 +1,9 @@
-use super::{call::*, id::*, vertex_finder::*};
+use super::{call::*, from_variant::*, id::*, kind::*, vertex_finder::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::cli::depict::*,
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -31,6 +31,31 @@ impl VertexSelector {
         Self::Finder(VertexFinder::new(finder))
     }
 
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        if let Self::VertexID(id) = self {
+            id.remap(ids);
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// Expects either an "id" or a "finder" key.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        if let Some(id) = map.into_get("id") {
+            Ok(Self::VertexID(get_id(id, Kind::Vertex)?))
+        } else if let Some(vertex_finder) = map.into_get("finder") {
+            Ok(Self::Finder(VertexFinder::from_variant(vertex_finder)?))
+        } else {
+            Err(malformed("VertexSelector", "must have \"id\" or \"finder\"", variant))
+        }
+    }
+
     /// To Compris variant.
     pub fn to_variant<AnnotatedT>(&self) -> Variant<AnnotatedT>
     where
@@ -1,12 +1,12 @@
-use super::{super::store::*, depict::*, directory::*, id::*, kind::*, template::*};
+use super::{super::store::*, depict::*, directory::*, from_variant::*, id::*, kind::*, template::*};
 
 use {
-    compris::{annotate::*, normal::*},
+    compris::{annotate::*, normal::*, resolve::*},
     kutil::{
         cli::depict::*,
         std::{immutable::*, iter::*},
     },
-    std::io,
+    std::{collections::*, io},
 };
 
 //
@@ -56,6 +56,12 @@ impl VertexTemplate {
     }
 
     /// Instantiate.
+    ///
+    /// The property definitions of the vertexes' classes are applied when they are updated (see
+    /// [Vertex::update](super::Vertex::update)), and those of the edges' classes when they are
+    /// instantiated (see [EdgeTemplate::instantiate](super::EdgeTemplate::instantiate)).
+    ///
+    /// Instantiation is atomic: if it fails then all its writes to the store are rolled back.
     #[cfg(feature = "plugins")]
     pub fn instantiate<StoreT, ErrorRecipientT>(
         &self,
@@ -68,15 +74,41 @@ impl VertexTemplate {
         StoreT: Clone + Send + Store,
         ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
     {
-        let vertex_id = self.instantiate_vertexes(directory, containing_vertex_id, library, errors)?;
-        let mut vertex =
-            library.store.get_vertex(&vertex_id)?.ok_or_else(|| super::super::StoreError::ID(vertex_id.to_string()))?;
+        let store = library.store.clone();
+        store.transaction(|| {
+            let vertex_id = self.instantiate_vertexes(directory, containing_vertex_id, library, errors)?;
+            let mut vertex = library
+                .store
+                .get_vertex(&vertex_id)?
+                .ok_or_else(|| super::super::StoreError::ID(vertex_id.to_string()))?;
 
-        vertex.update(library, errors)?;
+            vertex.update(library, errors)?;
 
-        vertex.instantiate_edges(directory, library, errors)?;
+            vertex.instantiate_edges(directory, library, errors)?;
 
-        Ok(vertex)
+            Ok(vertex)
+        })
+    }
+
+    /// Instantiate asynchronously.
+    ///
+    /// Instantiation runs plugins, which are synchronous, so it is wrapped in
+    /// [try_block_in_place](super::super::try_block_in_place). It will thus fail within a
+    /// current-thread runtime. Use a [SyncStoreAdapter](super::super::SyncStoreAdapter) for the
+    /// library's store in order to instantiate into an [AsyncStore](super::super::AsyncStore).
+    #[cfg(all(feature = "plugins", feature = "tokio"))]
+    pub async fn instantiate_async<StoreT, ErrorRecipientT>(
+        &self,
+        directory: &Directory,
+        containing_vertex_id: Option<ID>,
+        library: &mut super::super::plugins::Library<StoreT>,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<super::Vertex, super::super::FloriaError>
+    where
+        StoreT: Clone + Send + Store,
+        ErrorRecipientT: kutil::std::error::ErrorRecipient<super::super::FloriaError>,
+    {
+        super::super::try_block_in_place(|| self.instantiate(directory, containing_vertex_id, library, errors))?
     }
 
     /// Instantiate vertexes.
@@ -123,6 +155,37 @@ impl VertexTemplate {
         Ok(vertex_id)
     }
 
+    /// Remap IDs.
+    pub fn remap_ids(&mut self, ids: &HashMap<ID, ID>) {
+        self.template.remap_ids(ids);
+        if let Some(containing_vertex_template_id) = &mut self.containing_vertex_template_id {
+            containing_vertex_template_id.remap(ids);
+        }
+        for contained_vertex_template_id in &mut self.contained_vertex_template_ids {
+            contained_vertex_template_id.remap(ids);
+        }
+        for outgoing_edge_template_id in &mut self.outgoing_edge_template_ids {
+            outgoing_edge_template_id.remap(ids);
+        }
+    }
+
+    /// From Compris variant.
+    ///
+    /// Expects the non-embedded representation.
+    pub fn from_variant<AnnotatedT>(variant: &Variant<AnnotatedT>) -> Result<Self, ResolveError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let map = expect_map(variant)?;
+
+        Ok(Self {
+            template: Template::from_variant(map, Kind::VertexTemplate)?,
+            containing_vertex_template_id: get_optional_id(map, "containing_vertex_template_id", Kind::VertexTemplate)?,
+            contained_vertex_template_ids: get_ids(map, "contained_vertex_template_ids", Kind::VertexTemplate)?,
+            outgoing_edge_template_ids: get_ids(map, "outgoing_edge_template_ids", Kind::EdgeTemplate)?,
+        })
+    }
+
     /// To Compris variant.
     pub fn to_variant<'own, StoreT, AnnotatedT>(
         &self,
@@ -139,7 +202,7 @@ impl VertexTemplate {
 
         if !debug {
             if let Some(containing_vertex_template_id) = &self.containing_vertex_template_id {
-                map.into_insert("containing_vertex_template_id", containing_vertex_template_id.id.clone());
+                map.into_insert("containing_vertex_template_id", containing_vertex_template_id.to_string());
             }
         }
 
@@ -157,7 +220,7 @@ impl VertexTemplate {
                 map.into_insert("contained_vertex_templates", contained_vertex_templates);
             } else {
                 let contained_vertex_template_ids: List<_> =
-                    self.contained_vertex_template_ids.iter().map(|id| id.id.clone().into()).collect();
+                    self.contained_vertex_template_ids.iter().map(|id| id.to_string().into()).collect();
                 map.into_insert("contained_vertex_template_ids", contained_vertex_template_ids);
             }
         }
@@ -176,7 +239,7 @@ impl VertexTemplate {
                 map.into_insert("outgoing_edge_templates", outgoing_edge_templates);
             } else {
                 let outgoing_edge_template_ids: List<_> =
-                    self.outgoing_edge_template_ids.iter().map(|id| id.id.clone().into()).collect();
+                    self.outgoing_edge_template_ids.iter().map(|id| id.to_string().into()).collect();
                 map.into_insert("outgoing_edge_template_ids", outgoing_edge_template_ids);
             }
         }
@@ -1,6 +1,7 @@
 use super::{
     super::{data::*, store::*},
     invalid_value::*,
+    property_violation::*,
 };
 
 use {
@@ -28,6 +29,11 @@ pub enum FloriaError {
     #[depict(as(depict))]
     InvalidValue(#[from] InvalidValueError),
 
+    /// Property violation.
+    #[error("property violation: {0}")]
+    #[depict(as(depict))]
+    PropertyViolation(#[from] PropertyViolationError),
+
     /// Store.
     #[error("store: {0}")]
     #[depict(as(depict))]
@@ -48,6 +54,8 @@ impl FloriaError {
 
             Self::InvalidValue(invalid_value) => Some(invalid_value.id.clone()),
 
+            Self::PropertyViolation(property_violation) => Some(property_violation.id.clone()),
+
             #[cfg(feature = "plugins")]
             Self::Plugin(plugin) => match plugin {
                 super::super::plugins::PluginError::Dispatch(dispatch) => Some(dispatch.site.id.clone().into()),
@@ -1,5 +1,6 @@
 mod floria;
 mod invalid_value;
+mod property_violation;
 
 #[allow(unused_imports)]
-pub use {floria::*, invalid_value::*};
+pub use {floria::*, invalid_value::*, property_violation::*};
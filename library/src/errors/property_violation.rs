@@ -0,0 +1,66 @@
+use super::super::data::*;
+
+use {
+    kutil::{cli::depict::*, std::iter::*},
+    std::{fmt, io},
+    thiserror::*,
+};
+
+//
+// PropertyViolationError
+//
+
+/// Property violation error.
+///
+/// A property does not conform to a [PropertyDefinition].
+#[derive(Debug, Error)]
+pub struct PropertyViolationError {
+    /// ID.
+    pub id: ID,
+
+    /// Path.
+    pub path: Vec<String>,
+
+    /// Violation.
+    pub violation: PropertyViolation,
+}
+
+impl PropertyViolationError {
+    /// Constructor.
+    pub fn new(id: ID, path: Vec<String>, violation: PropertyViolation) -> Self {
+        Self { id, path, violation }
+    }
+}
+
+impl Depict for PropertyViolationError {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        context.separate(writer)?;
+
+        if !self.path.is_empty() {
+            for (segment, last) in IterateWithLast::new(&self.path) {
+                context.theme.write_meta(writer, segment)?;
+                if !last {
+                    context.theme.write_delimiter(writer, '.')?;
+                }
+            }
+        } else {
+            context.theme.write_meta(writer, "no path")?;
+        }
+
+        context.indent_into_branch(writer, true)?;
+        context.theme.write_error(writer, &self.violation)
+    }
+}
+
+impl fmt::Display for PropertyViolationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(formatter, "ID: {}, path: {}, {}", self.id, self.path.join("."), self.violation)
+        } else {
+            write!(formatter, "ID: {}, {}", self.id, self.violation)
+        }
+    }
+}
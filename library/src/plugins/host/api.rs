@@ -30,9 +30,10 @@ where
     }
 
     fn get_entity(&mut self, id: host::Id) -> wasmtime::Result<Result<host::Any, String>> {
-        Ok(match self.library.store.get_entity_as_variant::<WithoutAnnotations>(&id.into())? {
+        let id: ID = id.into();
+        Ok(match self.library.store.get_entity_as_variant::<WithoutAnnotations>(&id)? {
             Some(entity) => Ok(self.to_any(entity)?),
-            None => todo!(),
+            None => Err(format!("entity not found: {}", id)),
         })
     }
 }
@@ -0,0 +1,187 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, query::*, store::*, transaction_lock::*},
+    store::*,
+};
+
+use {std::collections::*, tokio::task::*};
+
+//
+// AsyncStoreAdapter
+//
+
+/// [AsyncStore] over a [Store].
+///
+/// Calls are run on Tokio's blocking thread pool so that they would not block executor threads.
+/// They must thus be made within a Tokio runtime.
+///
+/// Because calls may run on different threads, the adapter is its own transaction owner (see
+/// [with_transaction_owner]). Clones share the owner, so concurrent tasks that use transactions
+/// should each have their own adapter.
+#[derive(Clone, Debug)]
+pub struct AsyncStoreAdapter<StoreT> {
+    /// Inner.
+    pub inner: StoreT,
+
+    transaction_owner: u64,
+}
+
+impl<StoreT> AsyncStoreAdapter<StoreT>
+where
+    StoreT: 'static + Clone + Send + Store,
+{
+    /// Constructor.
+    pub fn new(inner: StoreT) -> Self {
+        Self { inner, transaction_owner: new_transaction_owner() }
+    }
+
+    // Call the inner store on the blocking thread pool.
+    async fn call<FunctionT, ResultT>(&self, function: FunctionT) -> Result<ResultT, StoreError>
+    where
+        FunctionT: 'static + FnOnce(StoreT) -> Result<ResultT, StoreError> + Send,
+        ResultT: 'static + Send,
+    {
+        let inner = self.inner.clone();
+        let transaction_owner = self.transaction_owner;
+        spawn_blocking(move || with_transaction_owner(transaction_owner, || function(inner))).await?
+    }
+}
+
+impl<StoreT> AsyncStore for AsyncStoreAdapter<StoreT>
+where
+    StoreT: 'static + Clone + Send + Sync + Store,
+{
+    async fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.call(move |inner| inner.begin_transaction()).await
+    }
+
+    async fn commit_transaction(&self) -> Result<(), StoreError> {
+        self.call(move |inner| inner.commit_transaction()).await
+    }
+
+    async fn rollback_transaction(&self) -> Result<(), StoreError> {
+        self.call(move |inner| inner.rollback_transaction()).await
+    }
+
+    async fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.call(move |inner| inner.subscribe(filter)).await
+    }
+
+    async fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        let mut id_ = id.clone();
+        *id = self
+            .call(move |inner| {
+                inner.create_id(&mut id_)?;
+                Ok(id_)
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.call(move |inner| inner.get_next_ids()).await
+    }
+
+    async fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        let next_ids = next_ids.clone();
+        self.call(move |inner| inner.advance_next_ids(&next_ids)).await
+    }
+
+    async fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.get_class(&id)).await
+    }
+
+    async fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.call(move |inner| inner.add_class(class)).await
+    }
+
+    async fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        self.call(move |inner| inner.update_class_if_revision(class)).await
+    }
+
+    async fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.remove_class(&id)).await
+    }
+
+    async fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.get_vertex_template(&id)).await
+    }
+
+    async fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.call(move |inner| inner.add_vertex_template(vertex_template)).await
+    }
+
+    async fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        self.call(move |inner| inner.update_vertex_template_if_revision(vertex_template)).await
+    }
+
+    async fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.remove_vertex_template(&id)).await
+    }
+
+    async fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.get_edge_template(&id)).await
+    }
+
+    async fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.call(move |inner| inner.add_edge_template(edge_template)).await
+    }
+
+    async fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        self.call(move |inner| inner.update_edge_template_if_revision(edge_template)).await
+    }
+
+    async fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.remove_edge_template(&id)).await
+    }
+
+    async fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.get_vertex(&id)).await
+    }
+
+    async fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        let query = query.clone();
+        self.call(move |inner| inner.get_entities(kind, &query)).await
+    }
+
+    async fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        self.call(move |inner| inner.get_vertexes(directories)).await
+    }
+
+    async fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.call(move |inner| inner.add_vertex(vertex)).await
+    }
+
+    async fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        self.call(move |inner| inner.update_vertex_if_revision(vertex)).await
+    }
+
+    async fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.remove_vertex(&id)).await
+    }
+
+    async fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.get_edge(&id)).await
+    }
+
+    async fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.call(move |inner| inner.add_edge(edge)).await
+    }
+
+    async fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        self.call(move |inner| inner.update_edge_if_revision(edge)).await
+    }
+
+    async fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        let id = id.clone();
+        self.call(move |inner| inner.remove_edge(&id)).await
+    }
+}
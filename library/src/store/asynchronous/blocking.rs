@@ -0,0 +1,25 @@
+use super::super::errors::*;
+
+use tokio::{runtime::*, task::*};
+
+/// Call a blocking function from within a Tokio runtime.
+///
+/// Within a multi-threaded runtime the call is wrapped in [block_in_place], letting the runtime
+/// know that we are about to block its thread. Fails within a current-thread runtime, because its
+/// only thread cannot be blocked. Outside of a runtime the function is simply called.
+pub fn try_block_in_place<FunctionT, ResultT>(function: FunctionT) -> Result<ResultT, StoreError>
+where
+    FunctionT: FnOnce() -> ResultT,
+{
+    match Handle::try_current() {
+        Ok(handle) => match handle.runtime_flavor() {
+            RuntimeFlavor::CurrentThread => {
+                Err(StoreError::Concurrency("cannot block within a current-thread Tokio runtime".into()))
+            }
+
+            _ => Ok(block_in_place(function)),
+        },
+
+        Err(_) => Ok(function()),
+    }
+}
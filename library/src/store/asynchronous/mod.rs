@@ -0,0 +1,7 @@
+mod async_adapter;
+mod blocking;
+mod store;
+mod sync_adapter;
+
+#[allow(unused_imports)]
+pub use {async_adapter::*, blocking::*, store::*, sync_adapter::*};
@@ -0,0 +1,124 @@
+use super::super::{super::data::*, errors::*, events::*, query::*};
+
+use std::collections::*;
+
+//
+// AsyncStore
+//
+
+/// Async access to a Floria store.
+///
+/// Mirrors [Store](super::super::Store), which documents the semantics of each method.
+///
+/// Implementations should ensure that cloning is cheap and clones always refer to the same shared
+/// state.
+pub trait AsyncStore {
+    /// Begin a transaction.
+    fn begin_transaction(&self) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Commit the current transaction.
+    fn commit_transaction(&self) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Roll back the current transaction.
+    fn rollback_transaction(&self) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Subscribe to changes.
+    fn subscribe(
+        &self,
+        filter: StoreEventFilter,
+    ) -> impl Future<Output = Result<StoreEventReceiver, StoreError>> + Send;
+
+    /// Create ID.
+    fn create_id(&self, id: &mut ID) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Get the next ID counters.
+    fn get_next_ids(&self) -> impl Future<Output = Result<HashMap<Kind, u64>, StoreError>> + Send;
+
+    /// Advance the next ID counters.
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Get class.
+    fn get_class(&self, id: &ID) -> impl Future<Output = Result<Option<Class>, StoreError>> + Send;
+
+    /// Add class.
+    fn add_class(&self, class: Class) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Update class if its revision matches that of the stored class.
+    fn update_class_if_revision(&self, class: Class) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    /// Remove class.
+    fn remove_class(&self, id: &ID) -> impl Future<Output = Result<Option<Class>, StoreError>> + Send;
+
+    /// Get vertex template.
+    fn get_vertex_template(&self, id: &ID) -> impl Future<Output = Result<Option<VertexTemplate>, StoreError>> + Send;
+
+    /// Add vertex template.
+    fn add_vertex_template(
+        &self,
+        vertex_template: VertexTemplate,
+    ) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Update vertex template if its revision matches that of the stored vertex template.
+    fn update_vertex_template_if_revision(
+        &self,
+        vertex_template: VertexTemplate,
+    ) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    /// Remove vertex template.
+    fn remove_vertex_template(
+        &self,
+        id: &ID,
+    ) -> impl Future<Output = Result<Option<VertexTemplate>, StoreError>> + Send;
+
+    /// Get edge template.
+    fn get_edge_template(&self, id: &ID) -> impl Future<Output = Result<Option<EdgeTemplate>, StoreError>> + Send;
+
+    /// Add edge template.
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Update edge template if its revision matches that of the stored edge template.
+    fn update_edge_template_if_revision(
+        &self,
+        edge_template: EdgeTemplate,
+    ) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    /// Remove edge template.
+    fn remove_edge_template(&self, id: &ID) -> impl Future<Output = Result<Option<EdgeTemplate>, StoreError>> + Send;
+
+    /// Get vertex.
+    fn get_vertex(&self, id: &ID) -> impl Future<Output = Result<Option<Vertex>, StoreError>> + Send;
+
+    /// Get entities of a kind that match a query.
+    fn get_entities(
+        &self,
+        kind: Kind,
+        query: &StoreQuery,
+    ) -> impl Future<Output = Result<Vec<Entity>, StoreError>> + Send;
+
+    /// Get vertexes.
+    fn get_vertexes(
+        &self,
+        directories: Option<Vec<Directory>>,
+    ) -> impl Future<Output = Result<Vec<Vertex>, StoreError>> + Send;
+
+    /// Add vertex.
+    fn add_vertex(&self, vertex: Vertex) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Update vertex if its revision matches that of the stored vertex.
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    /// Remove vertex.
+    fn remove_vertex(&self, id: &ID) -> impl Future<Output = Result<Option<Vertex>, StoreError>> + Send;
+
+    /// Get edge.
+    fn get_edge(&self, id: &ID) -> impl Future<Output = Result<Option<Edge>, StoreError>> + Send;
+
+    /// Add edge.
+    fn add_edge(&self, edge: Edge) -> impl Future<Output = Result<(), StoreError>> + Send;
+
+    /// Update edge if its revision matches that of the stored edge.
+    fn update_edge_if_revision(&self, edge: Edge) -> impl Future<Output = Result<u64, StoreError>> + Send;
+
+    /// Remove edge.
+    fn remove_edge(&self, id: &ID) -> impl Future<Output = Result<Option<Edge>, StoreError>> + Send;
+}
@@ -0,0 +1,170 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, query::*, store::*},
+    blocking::*,
+    store::*,
+};
+
+use {std::collections::*, tokio::runtime::*};
+
+//
+// SyncStoreAdapter
+//
+
+/// [Store] over an [AsyncStore].
+///
+/// Calls block on the provided Tokio runtime. When made from within a runtime they are wrapped in
+/// [try_block_in_place], and so will fail within a current-thread runtime.
+#[derive(Clone, Debug)]
+pub struct SyncStoreAdapter<AsyncStoreT> {
+    /// Inner.
+    pub inner: AsyncStoreT,
+
+    /// Runtime handle.
+    pub handle: Handle,
+}
+
+impl<AsyncStoreT> SyncStoreAdapter<AsyncStoreT>
+where
+    AsyncStoreT: AsyncStore,
+{
+    /// Constructor.
+    pub fn new(inner: AsyncStoreT, handle: Handle) -> Self {
+        Self { inner, handle }
+    }
+
+    /// Constructor.
+    ///
+    /// Uses the current runtime. Will panic if not called from within a runtime.
+    pub fn new_current(inner: AsyncStoreT) -> Self {
+        Self::new(inner, Handle::current())
+    }
+
+    // Block on the runtime.
+    fn block_on<FutureT, ResultT>(&self, future: FutureT) -> Result<ResultT, StoreError>
+    where
+        FutureT: Future<Output = Result<ResultT, StoreError>>,
+    {
+        try_block_in_place(|| self.handle.block_on(future))?
+    }
+}
+
+impl<AsyncStoreT> Store for SyncStoreAdapter<AsyncStoreT>
+where
+    AsyncStoreT: AsyncStore,
+{
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.block_on(self.inner.begin_transaction())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        self.block_on(self.inner.commit_transaction())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        self.block_on(self.inner.rollback_transaction())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.block_on(self.inner.subscribe(filter))
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        self.block_on(self.inner.create_id(id))
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.block_on(self.inner.get_next_ids())
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        self.block_on(self.inner.advance_next_ids(next_ids))
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.block_on(self.inner.get_class(id))
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.block_on(self.inner.add_class(class))
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        self.block_on(self.inner.update_class_if_revision(class))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.block_on(self.inner.remove_class(id))
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.block_on(self.inner.get_vertex_template(id))
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.block_on(self.inner.add_vertex_template(vertex_template))
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        self.block_on(self.inner.update_vertex_template_if_revision(vertex_template))
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.block_on(self.inner.remove_vertex_template(id))
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.block_on(self.inner.get_edge_template(id))
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.block_on(self.inner.add_edge_template(edge_template))
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        self.block_on(self.inner.update_edge_template_if_revision(edge_template))
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.block_on(self.inner.remove_edge_template(id))
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.block_on(self.inner.get_vertex(id))
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        self.block_on(self.inner.get_entities(kind, query))
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        self.block_on(self.inner.get_vertexes(directories))
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.block_on(self.inner.add_vertex(vertex))
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        self.block_on(self.inner.update_vertex_if_revision(vertex))
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.block_on(self.inner.remove_vertex(id))
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.block_on(self.inner.get_edge(id))
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.block_on(self.inner.add_edge(edge))
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        self.block_on(self.inner.update_edge_if_revision(edge))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.block_on(self.inner.remove_edge(id))
+    }
+}
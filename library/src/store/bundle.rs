@@ -0,0 +1,249 @@
+use super::{
+    super::data::{from_variant::*, *},
+    errors::*,
+    formats::*,
+    query::*,
+    store::*,
+};
+
+use {
+    compris::{annotate::*, normal::*, resolve::*, *},
+    kutil::cli::depict::*,
+    std::{collections::*, fmt, io},
+    thiserror::*,
+};
+
+/// Bundle version.
+pub const BUNDLE_VERSION: u64 = 1;
+
+// Entities are exported and imported in this order.
+const BUNDLE_KINDS: [Kind; 5] = [Kind::Class, Kind::VertexTemplate, Kind::EdgeTemplate, Kind::Vertex, Kind::Edge];
+
+//
+// BundleError
+//
+
+/// Bundle error.
+#[derive(Debug, Error)]
+pub enum BundleError<AnnotatedT> {
+    /// Version.
+    #[error("unsupported bundle version: {0}")]
+    Version(String),
+
+    /// Format.
+    #[error("format: {0}")]
+    Format(String),
+
+    /// Resolve.
+    #[error("resolve: {0}")]
+    Resolve(#[from] ResolveError<AnnotatedT>),
+
+    /// Store.
+    #[error("store: {0}")]
+    Store(#[from] StoreError),
+}
+
+impl<AnnotatedT> Depict for BundleError<AnnotatedT>
+where
+    AnnotatedT: Annotated + fmt::Debug,
+{
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        match self {
+            Self::Version(version) => write!(writer, "unsupported bundle version: {}", context.theme.error(version)),
+            Self::Format(format) => write!(writer, "format: {}", context.theme.error(format)),
+            Self::Resolve(resolve) => resolve.depict(writer, context),
+            Self::Store(store) => store.depict(writer, context),
+        }
+    }
+}
+
+//
+// StoreBundleUtilities
+//
+
+/// Bundle utilities for [Store].
+///
+/// A bundle is a single [Variant] containing entities together with the store's next ID counters,
+/// so that it can be serialized into any format supported by Compris and loaded into another
+/// store.
+pub trait StoreBundleUtilities {
+    /// Export the entities that match a query into a bundle.
+    ///
+    /// To export a directory subtree use a query with that directory and
+    /// [recursive](StoreQuery::recursive) set to true. The default query exports the entire store.
+    fn export_bundle<AnnotatedT>(&self, query: &StoreQuery) -> Result<Variant<AnnotatedT>, StoreError>
+    where
+        AnnotatedT: Annotated + Clone + Default;
+
+    /// Export the entities that match a query into a bundle and write it in a format.
+    ///
+    /// See [export_bundle](Self::export_bundle).
+    fn write_bundle<WriteT>(
+        &self,
+        query: &StoreQuery,
+        format: Format,
+        writer: &mut WriteT,
+    ) -> Result<(), BundleError<WithoutAnnotations>>
+    where
+        WriteT: io::Write;
+
+    /// Import a bundle.
+    ///
+    /// When not remapping, the entities keep their IDs, replacing existing entities with the same
+    /// IDs, and the store's next ID counters are advanced to those of the bundle.
+    ///
+    /// When remapping, every entity is given a newly created ID (in the same directory) and all
+    /// references between entities in the bundle are updated accordingly. References to entities
+    /// outside the bundle are kept as is. This allows for merging bundles without collisions.
+    ///
+    /// The import is done in a single transaction. Returns the remapped IDs (empty when not
+    /// remapping).
+    fn import_bundle<AnnotatedT>(
+        &self,
+        bundle: &Variant<AnnotatedT>,
+        remap_ids: bool,
+    ) -> Result<HashMap<ID, ID>, BundleError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default;
+
+    /// Read a bundle in a format and import it.
+    ///
+    /// See [import_bundle](Self::import_bundle).
+    fn read_bundle<ReadT>(
+        &self,
+        reader: &mut ReadT,
+        format: Format,
+        remap_ids: bool,
+    ) -> Result<HashMap<ID, ID>, BundleError<WithoutAnnotations>>
+    where
+        ReadT: io::Read;
+}
+
+impl<StoreT> StoreBundleUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn export_bundle<AnnotatedT>(&self, query: &StoreQuery) -> Result<Variant<AnnotatedT>, StoreError>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let mut entities = List::default();
+        for kind in BUNDLE_KINDS {
+            for entity in self.get_entities(kind, query)? {
+                entities.inner.push(entity.to_variant(false, self)?);
+            }
+        }
+
+        let store_next_ids = self.get_next_ids()?;
+        let mut next_ids = Map::default();
+        for kind in BUNDLE_KINDS {
+            if let Some(next_id) = store_next_ids.get(&kind) {
+                next_ids.into_insert(kind.to_string(), *next_id);
+            }
+        }
+
+        let mut bundle = Map::default();
+        bundle.into_insert("version", BUNDLE_VERSION);
+        bundle.into_insert("next_ids", next_ids);
+        bundle.into_insert("entities", entities);
+        Ok(bundle.into())
+    }
+
+    fn write_bundle<WriteT>(
+        &self,
+        query: &StoreQuery,
+        format: Format,
+        writer: &mut WriteT,
+    ) -> Result<(), BundleError<WithoutAnnotations>>
+    where
+        WriteT: io::Write,
+    {
+        let bundle = self.export_bundle(query)?;
+        write_variant(&bundle, format, writer).map_err(BundleError::Format)
+    }
+
+    fn import_bundle<AnnotatedT>(
+        &self,
+        bundle: &Variant<AnnotatedT>,
+        remap_ids: bool,
+    ) -> Result<HashMap<ID, ID>, BundleError<AnnotatedT>>
+    where
+        AnnotatedT: Annotated + Clone + Default,
+    {
+        let bundle = expect_map(bundle)?;
+
+        match get_required(bundle, "version")? {
+            Variant::UnsignedInteger(version) if version.inner == BUNDLE_VERSION => {}
+            Variant::Integer(version) if version.inner == BUNDLE_VERSION as i64 => {}
+            Variant::UnsignedInteger(version) => return Err(BundleError::Version(version.inner.to_string())),
+            Variant::Integer(version) => return Err(BundleError::Version(version.inner.to_string())),
+            version => return Err(BundleError::Version(version.type_name().into())),
+        }
+
+        let mut next_ids = HashMap::default();
+        if let Some(next_ids_) = bundle.into_get("next_ids") {
+            for (kind, next_id) in expect_map(next_ids_)? {
+                let next_id = match next_id {
+                    Variant::UnsignedInteger(next_id) => next_id.inner,
+                    Variant::Integer(next_id) if next_id.inner >= 0 => next_id.inner as u64,
+                    _ => return Err(malformed("next ID", "not a non-negative integer", next_id).into()),
+                };
+                next_ids.insert(get_kind(kind)?, next_id);
+            }
+        }
+
+        let mut entities = Vec::default();
+        if let Some(entities_) = bundle.into_get("entities") {
+            for entity in expect_list(entities_)? {
+                entities.push(Entity::from_variant(entity)?);
+            }
+        }
+
+        Ok(self.transaction(|| {
+            let mut ids = HashMap::default();
+
+            if remap_ids {
+                for entity in &entities {
+                    let id = entity.get_id();
+                    let mut new_id = ID::new(id.kind, id.directory.clone());
+                    self.create_id(&mut new_id)?;
+                    ids.insert(id.clone(), new_id);
+                }
+            } else {
+                self.advance_next_ids(&next_ids)?;
+            }
+
+            for mut entity in entities {
+                if remap_ids {
+                    entity.remap_ids(&ids);
+                }
+
+                match entity {
+                    Entity::Class(class) => self.add_class(class)?,
+                    Entity::VertexTemplate(vertex_template) => self.add_vertex_template(vertex_template)?,
+                    Entity::EdgeTemplate(edge_template) => self.add_edge_template(edge_template)?,
+                    Entity::Vertex(vertex) => self.add_vertex(vertex)?,
+                    Entity::Edge(edge) => self.add_edge(edge)?,
+                }
+            }
+
+            Ok::<_, StoreError>(ids)
+        })?)
+    }
+
+    fn read_bundle<ReadT>(
+        &self,
+        reader: &mut ReadT,
+        format: Format,
+        remap_ids: bool,
+    ) -> Result<HashMap<ID, ID>, BundleError<WithoutAnnotations>>
+    where
+        ReadT: io::Read,
+    {
+        let bundle = read_variant(reader, format).map_err(BundleError::Format)?;
+        self.import_bundle(&bundle, remap_ids)
+    }
+}
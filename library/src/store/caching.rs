@@ -0,0 +1,365 @@
+use super::{super::data::*, errors::*, events::*, query::*, store::*};
+
+use std::{collections::*, fmt, sync::*};
+
+//
+// CachingStore
+//
+
+/// [Store] wrapper that caches entities read from the inner store.
+///
+/// The cache is bounded. When it is full the least recently used entity is evicted.
+///
+/// Writes through the wrapper invalidate the cached entity once they are done. Because removals can
+/// cascade to other entities, as can rolling back a transaction, these clear the entire cache. Note
+/// that writes that bypass the wrapper, e.g. by another process sharing the same database, will not
+/// be noticed.
+///
+/// Clones share the same cache.
+#[derive(Clone, Debug)]
+pub struct CachingStore<StoreT> {
+    /// Inner.
+    pub inner: StoreT,
+
+    cache: Arc<Mutex<StoreCache>>,
+}
+
+impl<StoreT> CachingStore<StoreT>
+where
+    StoreT: Store,
+{
+    /// Constructor.
+    ///
+    /// A capacity of 0 disables caching.
+    pub fn new(inner: StoreT, capacity: usize) -> Self {
+        Self { inner, cache: Arc::new(Mutex::new(StoreCache::new(capacity))) }
+    }
+
+    /// Statistics.
+    pub fn statistics(&self) -> Result<StoreCacheStatistics, StoreError> {
+        let cache = self.cache.lock()?;
+        Ok(StoreCacheStatistics { entries: cache.entries.len(), capacity: cache.capacity, ..cache.statistics })
+    }
+
+    /// Reset statistics.
+    pub fn reset_statistics(&self) -> Result<(), StoreError> {
+        self.cache.lock()?.statistics = Default::default();
+        Ok(())
+    }
+
+    /// Clear the cache.
+    pub fn clear_cache(&self) -> Result<(), StoreError> {
+        self.cache.lock()?.clear();
+        Ok(())
+    }
+
+    // Get from the cache, falling back to the inner store.
+    fn get_cached<EntityT, GetT>(&self, id: &ID, get: GetT) -> Result<Option<EntityT>, StoreError>
+    where
+        EntityT: Clone + Into<Entity> + TryFrom<Entity>,
+        GetT: FnOnce(&ID) -> Result<Option<EntityT>, StoreError>,
+    {
+        let generation = {
+            let mut cache = self.cache.lock()?;
+            if let Some(entity) = cache.get(id)
+                && let Ok(entity) = entity.try_into()
+            {
+                cache.statistics.hits += 1;
+                return Ok(Some(entity));
+            }
+
+            cache.statistics.misses += 1;
+            cache.generation
+        };
+
+        let entity = get(id)?;
+
+        if let Some(entity) = &entity {
+            self.cache.lock()?.put(id.clone(), entity.clone().into(), generation);
+        }
+
+        Ok(entity)
+    }
+
+    // Invalidate an entity after writing it, even if the write failed (it may have been partial).
+    //
+    // Invalidating before the write would not be enough, as a concurrent read could then cache
+    // the entity before it is written.
+    fn invalidate_after<ResultT>(&self, id: &ID, result: Result<ResultT, StoreError>) -> Result<ResultT, StoreError> {
+        self.cache.lock()?.invalidate(id);
+        result
+    }
+
+    // Clear the cache after writing, even if the write failed (it may have been partial).
+    fn clear_after<ResultT>(&self, result: Result<ResultT, StoreError>) -> Result<ResultT, StoreError> {
+        self.cache.lock()?.clear();
+        result
+    }
+}
+
+impl<StoreT> Store for CachingStore<StoreT>
+where
+    StoreT: Store,
+{
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.inner.begin_transaction()
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        self.inner.commit_transaction()
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        self.clear_after(self.inner.rollback_transaction())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.inner.subscribe(filter)
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        self.inner.create_id(id)
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.inner.get_next_ids()
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        self.inner.advance_next_ids(next_ids)
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.get_cached(id, |id| self.inner.get_class(id))
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        let id = class.id.clone();
+        self.invalidate_after(&id, self.inner.add_class(class))
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        let id = class.id.clone();
+        self.invalidate_after(&id, self.inner.update_class_if_revision(class))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.clear_after(self.inner.remove_class(id))
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.get_cached(id, |id| self.inner.get_vertex_template(id))
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        let id = vertex_template.template.id.clone();
+        self.invalidate_after(&id, self.inner.add_vertex_template(vertex_template))
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        let id = vertex_template.template.id.clone();
+        self.invalidate_after(&id, self.inner.update_vertex_template_if_revision(vertex_template))
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.clear_after(self.inner.remove_vertex_template(id))
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.get_cached(id, |id| self.inner.get_edge_template(id))
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        let id = edge_template.template.id.clone();
+        self.invalidate_after(&id, self.inner.add_edge_template(edge_template))
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        let id = edge_template.template.id.clone();
+        self.invalidate_after(&id, self.inner.update_edge_template_if_revision(edge_template))
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.clear_after(self.inner.remove_edge_template(id))
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.get_cached(id, |id| self.inner.get_vertex(id))
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        self.inner.get_entities(kind, query)
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        self.inner.get_vertexes(directories)
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        let id = vertex.instance.id.clone();
+        self.invalidate_after(&id, self.inner.add_vertex(vertex))
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        let id = vertex.instance.id.clone();
+        self.invalidate_after(&id, self.inner.update_vertex_if_revision(vertex))
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.clear_after(self.inner.remove_vertex(id))
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.get_cached(id, |id| self.inner.get_edge(id))
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        let id = edge.instance.id.clone();
+        self.invalidate_after(&id, self.inner.add_edge(edge))
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        let id = edge.instance.id.clone();
+        self.invalidate_after(&id, self.inner.update_edge_if_revision(edge))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.clear_after(self.inner.remove_edge(id))
+    }
+}
+
+//
+// StoreCacheStatistics
+//
+
+/// [CachingStore] statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StoreCacheStatistics {
+    /// Reads served from the cache.
+    pub hits: u64,
+
+    /// Reads served from the inner store.
+    pub misses: u64,
+
+    /// Entities evicted to make room for others.
+    pub evictions: u64,
+
+    /// Entities removed from the cache because of writes.
+    pub invalidations: u64,
+
+    /// Current number of cached entities.
+    pub entries: usize,
+
+    /// Maximum number of cached entities.
+    pub capacity: usize,
+}
+
+impl StoreCacheStatistics {
+    /// Ratio of hits to all reads.
+    ///
+    /// Will be 0 if there were no reads.
+    pub fn hit_ratio(&self) -> f64 {
+        let reads = self.hits + self.misses;
+        if reads == 0 { 0. } else { self.hits as f64 / reads as f64 }
+    }
+}
+
+impl fmt::Display for StoreCacheStatistics {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "hits: {}, misses: {}, hit ratio: {:.2}, evictions: {}, invalidations: {}, entries: {}/{}",
+            self.hits,
+            self.misses,
+            self.hit_ratio(),
+            self.evictions,
+            self.invalidations,
+            self.entries,
+            self.capacity
+        )
+    }
+}
+
+//
+// StoreCache
+//
+
+#[derive(Debug)]
+struct StoreCache {
+    capacity: usize,
+
+    // The value is the entity and its last use
+    entries: HashMap<ID, (Entity, u64)>,
+
+    // Last use to ID, so that the first is the least recently used
+    uses: BTreeMap<u64, ID>,
+
+    use_counter: u64,
+
+    // Incremented on every invalidation, so that we don't cache entities read before it
+    generation: u64,
+
+    statistics: StoreCacheStatistics,
+}
+
+impl StoreCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+            uses: Default::default(),
+            use_counter: 0,
+            generation: 0,
+            statistics: Default::default(),
+        }
+    }
+
+    fn get(&mut self, id: &ID) -> Option<Entity> {
+        let use_ = self.next_use();
+        let (entity, last_use) = self.entries.get_mut(id)?;
+        self.uses.remove(last_use);
+        self.uses.insert(use_, id.clone());
+        *last_use = use_;
+        Some(entity.clone())
+    }
+
+    fn put(&mut self, id: ID, entity: Entity, generation: u64) {
+        if (self.capacity == 0) || (generation != self.generation) {
+            return;
+        }
+
+        let use_ = self.next_use();
+        if let Some((_, last_use)) = self.entries.insert(id.clone(), (entity, use_)) {
+            self.uses.remove(&last_use);
+        }
+        self.uses.insert(use_, id);
+
+        while self.entries.len() > self.capacity {
+            let Some((_, id)) = self.uses.pop_first() else {
+                break;
+            };
+            self.entries.remove(&id);
+            self.statistics.evictions += 1;
+        }
+    }
+
+    fn invalidate(&mut self, id: &ID) {
+        self.generation += 1;
+        if let Some((_, last_use)) = self.entries.remove(id) {
+            self.uses.remove(&last_use);
+            self.statistics.invalidations += 1;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.statistics.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+        self.uses.clear();
+    }
+
+    fn next_use(&mut self) -> u64 {
+        self.use_counter += 1;
+        self.use_counter
+    }
+}
@@ -0,0 +1,73 @@
+use compris::{annotate::*, normal::*};
+
+// Encode CBOR.
+//
+// Compris's CBOR serializer miscounts empty collections that are followed by other items (which is
+// common for us, e.g. empty metadata), so we encode ourselves. Decoding is left to Compris.
+pub(crate) fn encode_cbor(variant: &Variant<WithoutAnnotations>, bytes: &mut Vec<u8>) {
+    match variant {
+        Variant::Undefined => bytes.push(0xf7),
+        Variant::Null(_) => bytes.push(0xf6),
+        Variant::Boolean(boolean) => bytes.push(if boolean.inner { 0xf5 } else { 0xf4 }),
+
+        Variant::Integer(integer) => {
+            if integer.inner < 0 {
+                encode_cbor_head(1, (-1 - integer.inner) as u64, bytes);
+            } else {
+                encode_cbor_head(0, integer.inner as u64, bytes);
+            }
+        }
+
+        Variant::UnsignedInteger(unsigned_integer) => encode_cbor_head(0, unsigned_integer.inner, bytes),
+
+        Variant::Float(float) => {
+            bytes.push(0xfb);
+            bytes.extend_from_slice(&float.inner.to_be_bytes());
+        }
+
+        Variant::Text(text) => {
+            encode_cbor_head(3, text.inner.len() as u64, bytes);
+            bytes.extend_from_slice(text.inner.as_bytes());
+        }
+
+        Variant::Blob(blob) => {
+            encode_cbor_head(2, blob.inner.len() as u64, bytes);
+            bytes.extend_from_slice(&blob.inner);
+        }
+
+        Variant::List(list) => {
+            encode_cbor_head(4, list.inner.len() as u64, bytes);
+            for item in &list.inner {
+                encode_cbor(item, bytes);
+            }
+        }
+
+        Variant::Map(map) => {
+            encode_cbor_head(5, map.inner.len() as u64, bytes);
+            for (key, value) in &map.inner {
+                encode_cbor(key, bytes);
+                encode_cbor(value, bytes);
+            }
+        }
+    }
+}
+
+// Encode a CBOR head: the major type and its argument.
+fn encode_cbor_head(major: u8, argument: u64, bytes: &mut Vec<u8>) {
+    let major = major << 5;
+    if argument < 24 {
+        bytes.push(major | argument as u8);
+    } else if argument <= u8::MAX as u64 {
+        bytes.push(major | 24);
+        bytes.push(argument as u8);
+    } else if argument <= u16::MAX as u64 {
+        bytes.push(major | 25);
+        bytes.extend_from_slice(&(argument as u16).to_be_bytes());
+    } else if argument <= u32::MAX as u64 {
+        bytes.push(major | 26);
+        bytes.extend_from_slice(&(argument as u32).to_be_bytes());
+    } else {
+        bytes.push(major | 27);
+        bytes.extend_from_slice(&argument.to_be_bytes());
+    }
+}
@@ -0,0 +1,293 @@
+use super::{super::data::*, errors::*, store::*};
+
+use std::collections::*;
+
+//
+// StoreClassHierarchyUtilities
+//
+
+/// Class hierarchy utilities for [Store].
+///
+/// Classes inherit via [Class::parent_class_ids], with [Class::child_class_ids] as the back links.
+/// An entity that is a member of a class is also considered to be a member of all its ancestor
+/// classes.
+pub trait StoreClassHierarchyUtilities {
+    /// Add a class, linking it into the class hierarchy.
+    ///
+    /// The class's parent and child class IDs are authoritative: the parent and child classes are
+    /// updated to link back to it, and classes it no longer links to (if it's replacing an existing
+    /// class) are unlinked. The linked classes must exist and the links may not create a cycle.
+    ///
+    /// Done in a single transaction. Fails with [StoreError::ClassCycle] if the links would create
+    /// a cycle and with [StoreError::Conflict] if any of the classes is changed concurrently.
+    fn add_class_to_hierarchy(&self, class: Class) -> Result<(), StoreError>;
+
+    /// Add a parent class to a class, linking both.
+    fn add_parent_class(&self, class_id: &ID, parent_class_id: &ID) -> Result<(), StoreError>;
+
+    /// Remove a parent class from a class, unlinking both.
+    fn remove_parent_class(&self, class_id: &ID, parent_class_id: &ID) -> Result<(), StoreError>;
+
+    /// Ancestor class IDs, nearest first.
+    fn get_ancestor_class_ids(&self, class_id: &ID) -> Result<Vec<ID>, StoreError>;
+
+    /// Descendant class IDs, nearest first.
+    fn get_descendant_class_ids(&self, class_id: &ID) -> Result<Vec<ID>, StoreError>;
+
+    /// The classes and all their ancestor class IDs, nearest first and without duplicates.
+    ///
+    /// This is the order in which class behavior is inherited.
+    fn get_inherited_class_ids(&self, class_ids: &[ID]) -> Result<Vec<ID>, StoreError>;
+
+    /// True if the class is the other class or one of its descendants.
+    fn is_subclass_of(&self, class_id: &ID, ancestor_class_id: &ID) -> Result<bool, StoreError>;
+
+    /// True if any of the classes is the other class or one of its descendants.
+    fn is_any_subclass_of(&self, class_ids: &[ID], ancestor_class_id: &ID) -> Result<bool, StoreError>;
+
+    /// True if the entity is a member of the class, directly or via inheritance.
+    ///
+    /// For a class entity this is the same as [is_subclass_of](Self::is_subclass_of).
+    fn is_a(&self, id: &ID, class_id: &ID) -> Result<bool, StoreError>;
+}
+
+impl<StoreT> StoreClassHierarchyUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn add_class_to_hierarchy(&self, mut class: Class) -> Result<(), StoreError> {
+        self.transaction(|| {
+            let id = class.id.clone();
+            check_cycle(self, &class)?;
+
+            let (previous_revision, previous_parent_class_ids, previous_child_class_ids) = match self.get_class(&id)? {
+                Some(previous) => (previous.revision, previous.parent_class_ids, previous.child_class_ids),
+                None => Default::default(),
+            };
+
+            for parent_class_id in &class.parent_class_ids {
+                update_class(self, parent_class_id, |parent_class| add_id(&mut parent_class.child_class_ids, &id))?;
+            }
+
+            for child_class_id in &class.child_class_ids {
+                update_class(self, child_class_id, |child_class| add_id(&mut child_class.parent_class_ids, &id))?;
+            }
+
+            for previous_parent_class_id in previous_parent_class_ids {
+                if !class.parent_class_ids.contains(&previous_parent_class_id) {
+                    unlink_class(self, &previous_parent_class_id, |parent_class| {
+                        parent_class.child_class_ids.retain(|child_class_id| *child_class_id != id)
+                    })?;
+                }
+            }
+
+            for previous_child_class_id in previous_child_class_ids {
+                if !class.child_class_ids.contains(&previous_child_class_id) {
+                    unlink_class(self, &previous_child_class_id, |child_class| {
+                        child_class.parent_class_ids.retain(|parent_class_id| *parent_class_id != id)
+                    })?;
+                }
+            }
+
+            // Replace the class we read (if any)
+            class.revision = previous_revision;
+            self.update_class_if_revision(class)?;
+            Ok(())
+        })
+    }
+
+    fn add_parent_class(&self, class_id: &ID, parent_class_id: &ID) -> Result<(), StoreError> {
+        let mut class = get_class(self, class_id)?;
+        if add_id(&mut class.parent_class_ids, parent_class_id) {
+            self.add_class_to_hierarchy(class)?;
+        }
+        Ok(())
+    }
+
+    fn remove_parent_class(&self, class_id: &ID, parent_class_id: &ID) -> Result<(), StoreError> {
+        let mut class = get_class(self, class_id)?;
+        let length = class.parent_class_ids.len();
+        class.parent_class_ids.retain(|parent_class_id_| parent_class_id_ != parent_class_id);
+        if class.parent_class_ids.len() != length {
+            self.add_class_to_hierarchy(class)?;
+        }
+        Ok(())
+    }
+
+    fn get_ancestor_class_ids(&self, class_id: &ID) -> Result<Vec<ID>, StoreError> {
+        traverse(self, class_id, |class| &class.parent_class_ids)
+    }
+
+    fn get_descendant_class_ids(&self, class_id: &ID) -> Result<Vec<ID>, StoreError> {
+        traverse(self, class_id, |class| &class.child_class_ids)
+    }
+
+    fn get_inherited_class_ids(&self, class_ids: &[ID]) -> Result<Vec<ID>, StoreError> {
+        let mut inherited_class_ids = Vec::default();
+        let mut visited: HashSet<_> = class_ids.iter().cloned().collect();
+        let mut pending: VecDeque<_> = class_ids.iter().cloned().collect();
+
+        while let Some(class_id) = pending.pop_front() {
+            if let Some(class) = self.get_class(&class_id)? {
+                for parent_class_id in class.parent_class_ids {
+                    if visited.insert(parent_class_id.clone()) {
+                        pending.push_back(parent_class_id);
+                    }
+                }
+            }
+            inherited_class_ids.push(class_id);
+        }
+
+        Ok(inherited_class_ids)
+    }
+
+    fn is_subclass_of(&self, class_id: &ID, ancestor_class_id: &ID) -> Result<bool, StoreError> {
+        self.is_any_subclass_of(std::slice::from_ref(class_id), ancestor_class_id)
+    }
+
+    fn is_any_subclass_of(&self, class_ids: &[ID], ancestor_class_id: &ID) -> Result<bool, StoreError> {
+        if class_ids.contains(ancestor_class_id) {
+            return Ok(true);
+        }
+
+        for class_id in class_ids {
+            if self.get_ancestor_class_ids(class_id)?.contains(ancestor_class_id) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn is_a(&self, id: &ID, class_id: &ID) -> Result<bool, StoreError> {
+        let class_ids = match id.kind {
+            Kind::Class => Some(vec![id.clone()]),
+            Kind::VertexTemplate => self.get_vertex_template(id)?.map(|entity| entity.template.class_ids),
+            Kind::EdgeTemplate => self.get_edge_template(id)?.map(|entity| entity.template.class_ids),
+            Kind::Vertex => self.get_vertex(id)?.map(|entity| entity.instance.class_ids),
+            Kind::Edge => self.get_edge(id)?.map(|entity| entity.instance.class_ids),
+        }
+        .ok_or_else(|| StoreError::ID(format!("not found: {}", id)))?;
+
+        self.is_any_subclass_of(&class_ids, class_id)
+    }
+}
+
+// Utils
+
+// Breadth-first traversal, nearest first. Tolerates cycles.
+fn traverse<StoreT, NextT>(store: &StoreT, class_id: &ID, next: NextT) -> Result<Vec<ID>, StoreError>
+where
+    StoreT: Store,
+    NextT: Fn(&Class) -> &Vec<ID>,
+{
+    let mut class_ids = Vec::default();
+    let mut visited = HashSet::from([class_id.clone()]);
+    let mut pending = VecDeque::from([class_id.clone()]);
+
+    while let Some(class_id) = pending.pop_front() {
+        if let Some(class) = store.get_class(&class_id)? {
+            for next_class_id in next(&class) {
+                if visited.insert(next_class_id.clone()) {
+                    class_ids.push(next_class_id.clone());
+                    pending.push_back(next_class_id.clone());
+                }
+            }
+        }
+    }
+
+    Ok(class_ids)
+}
+
+// Make sure that the class's links would not create a cycle, i.e. that none of its ancestors is
+// also one of its descendants (or the class itself).
+fn check_cycle<StoreT>(store: &StoreT, class: &Class) -> Result<(), StoreError>
+where
+    StoreT: Store,
+{
+    let ancestor_class_ids = traverse_links(store, class, &class.parent_class_ids, |class| &class.parent_class_ids)?;
+    let descendant_class_ids = traverse_links(store, class, &class.child_class_ids, |class| &class.child_class_ids)?;
+
+    for ancestor_class_id in &ancestor_class_ids {
+        if (*ancestor_class_id == class.id) || descendant_class_ids.contains(ancestor_class_id) {
+            return Err(StoreError::ClassCycle(format!(
+                "{} would be both an ancestor and a descendant of {}",
+                ancestor_class_id, class.id
+            )));
+        }
+    }
+
+    if descendant_class_ids.contains(&class.id) {
+        return Err(StoreError::ClassCycle(format!("{} would be its own descendant", class.id)));
+    }
+
+    Ok(())
+}
+
+// The linked classes and their transitive links. Stops at the class itself, because its stored
+// links are about to be replaced. The linked classes must exist.
+fn traverse_links<StoreT, NextT>(
+    store: &StoreT,
+    class: &Class,
+    class_ids: &[ID],
+    next: NextT,
+) -> Result<HashSet<ID>, StoreError>
+where
+    StoreT: Store,
+    NextT: Fn(&Class) -> &Vec<ID>,
+{
+    let mut visited = HashSet::default();
+
+    for class_id in class_ids {
+        get_class(store, class_id)?;
+        if visited.insert(class_id.clone()) && (*class_id != class.id) {
+            visited.extend(traverse(store, class_id, &next)?);
+        }
+    }
+
+    Ok(visited)
+}
+
+fn get_class<StoreT>(store: &StoreT, class_id: &ID) -> Result<Class, StoreError>
+where
+    StoreT: Store,
+{
+    store.get_class(class_id)?.ok_or_else(|| StoreError::ID(format!("class not found: {}", class_id)))
+}
+
+// Update a class that must exist. Only writes if changed, and only if the class was not changed
+// since we read it.
+fn update_class<StoreT, UpdateT>(store: &StoreT, class_id: &ID, update: UpdateT) -> Result<(), StoreError>
+where
+    StoreT: Store,
+    UpdateT: FnOnce(&mut Class) -> bool,
+{
+    let mut class = get_class(store, class_id)?;
+    if update(&mut class) {
+        store.update_class_if_revision(class)?;
+    }
+    Ok(())
+}
+
+// Update a class if it exists, and only if the class was not changed since we read it.
+fn unlink_class<StoreT, UpdateT>(store: &StoreT, class_id: &ID, update: UpdateT) -> Result<(), StoreError>
+where
+    StoreT: Store,
+    UpdateT: FnOnce(&mut Class),
+{
+    if let Some(mut class) = store.get_class(class_id)? {
+        update(&mut class);
+        store.update_class_if_revision(class)?;
+    }
+    Ok(())
+}
+
+// Add an ID if it's not already there. Returns true if added.
+fn add_id(ids: &mut Vec<ID>, id: &ID) -> bool {
+    if ids.contains(id) {
+        false
+    } else {
+        ids.push(id.clone());
+        true
+    }
+}
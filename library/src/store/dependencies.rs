@@ -0,0 +1,334 @@
+use super::{super::data::*, errors::*, query::*, store::*};
+
+use {
+    kutil::cli::depict::*,
+    std::{collections::*, fmt, io},
+    thiserror::*,
+};
+
+//
+// EdgeDependency
+//
+
+/// Which end of an edge depends on the other.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EdgeDependency {
+    /// The source vertex depends on the target vertex.
+    #[default]
+    SourceDependsOnTarget,
+
+    /// The target vertex depends on the source vertex.
+    TargetDependsOnSource,
+}
+
+//
+// ContainmentDependency
+//
+
+/// Whether and how containment implies dependency.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ContainmentDependency {
+    /// Containment does not imply dependency.
+    #[default]
+    None,
+
+    /// Contained vertexes depend on their containing vertex.
+    ContainedDependsOnContaining,
+
+    /// Containing vertexes depend on their contained vertexes.
+    ContainingDependsOnContained,
+}
+
+//
+// DependencyOptions
+//
+
+/// Options for [StoreDependencyUtilities::dependency_graph].
+#[derive(Clone, Debug, Default)]
+pub struct DependencyOptions {
+    /// Edge class IDs. Edges that are members of any of them imply dependency. If empty then all
+    /// edges do.
+    pub edge_class_ids: Vec<ID>,
+
+    /// Edge dependency.
+    pub edge_dependency: EdgeDependency,
+
+    /// Containment dependency.
+    pub containment_dependency: ContainmentDependency,
+}
+
+impl DependencyOptions {
+    /// With edge class ID.
+    pub fn with_edge_class_id(mut self, edge_class_id: ID) -> Self {
+        self.edge_class_ids.push(edge_class_id);
+        self
+    }
+
+    /// With edge dependency.
+    pub fn with_edge_dependency(mut self, edge_dependency: EdgeDependency) -> Self {
+        self.edge_dependency = edge_dependency;
+        self
+    }
+
+    /// With containment dependency.
+    pub fn with_containment_dependency(mut self, containment_dependency: ContainmentDependency) -> Self {
+        self.containment_dependency = containment_dependency;
+        self
+    }
+
+    // True if the edge implies dependency.
+    fn includes(&self, edge: &Edge) -> bool {
+        self.edge_class_ids.is_empty()
+            || self.edge_class_ids.iter().any(|edge_class_id| edge.instance.class_ids.contains(edge_class_id))
+    }
+}
+
+//
+// StoreDependencyUtilities
+//
+
+/// Dependency utilities for [Store].
+pub trait StoreDependencyUtilities {
+    /// Dependency graph of the vertexes that match a query.
+    ///
+    /// Dependencies on vertexes that do not match the query are ignored.
+    fn dependency_graph(&self, query: &StoreQuery, options: &DependencyOptions) -> Result<DependencyGraph, StoreError>;
+}
+
+impl<StoreT> StoreDependencyUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn dependency_graph(&self, query: &StoreQuery, options: &DependencyOptions) -> Result<DependencyGraph, StoreError> {
+        let mut vertexes: Vec<Vertex> = Vec::default();
+        for entity in self.get_entities(Kind::Vertex, query)? {
+            if let Entity::Vertex(vertex) = entity {
+                vertexes.push(vertex);
+            }
+        }
+
+        // Sorted so that orders are deterministic
+        vertexes.sort_by_cached_key(|vertex| vertex.instance.id.to_string());
+
+        let mut graph = DependencyGraph::default();
+        for vertex in &vertexes {
+            graph.indexes.insert(vertex.instance.id.clone(), graph.vertex_ids.len());
+            graph.vertex_ids.push(vertex.instance.id.clone());
+        }
+        graph.dependencies.resize_with(vertexes.len(), Default::default);
+        graph.dependents.resize_with(vertexes.len(), Default::default);
+
+        for (index, vertex) in vertexes.iter().enumerate() {
+            // Vertexes keep track only of their outgoing edges, so we record both directions
+            // from the source
+            for edge_id in &vertex.outgoing_edge_ids {
+                if let Some(edge) = self.get_edge(edge_id)?
+                    && options.includes(&edge)
+                {
+                    match options.edge_dependency {
+                        EdgeDependency::SourceDependsOnTarget => {
+                            graph.add_dependency(index, &edge.target_vertex_id);
+                        }
+
+                        EdgeDependency::TargetDependsOnSource => {
+                            if let Some(target_index) = graph.indexes.get(&edge.target_vertex_id).copied() {
+                                graph.add_dependency(target_index, &edge.source_vertex_id);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match options.containment_dependency {
+                ContainmentDependency::None => {}
+
+                ContainmentDependency::ContainedDependsOnContaining => {
+                    if let Some(containing_vertex_id) = &vertex.containing_vertex_id {
+                        graph.add_dependency(index, containing_vertex_id);
+                    }
+                }
+
+                ContainmentDependency::ContainingDependsOnContained => {
+                    for contained_vertex_id in &vertex.contained_vertex_ids {
+                        graph.add_dependency(index, contained_vertex_id);
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+//
+// DependencyGraph
+//
+
+/// Dependency graph of vertexes.
+///
+/// Orders are deterministic: vertexes that could go in any order are sorted by ID.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    vertex_ids: Vec<ID>,
+    indexes: HashMap<ID, usize>,
+
+    // By index
+    dependencies: Vec<BTreeSet<usize>>,
+    dependents: Vec<BTreeSet<usize>>,
+}
+
+impl DependencyGraph {
+    /// Vertex IDs, sorted.
+    pub fn vertex_ids(&self) -> &[ID] {
+        &self.vertex_ids
+    }
+
+    /// Vertexes that a vertex depends on.
+    pub fn dependencies(&self, vertex_id: &ID) -> Vec<ID> {
+        self.lookup(vertex_id, &self.dependencies)
+    }
+
+    /// Vertexes that depend on a vertex.
+    pub fn dependents(&self, vertex_id: &ID) -> Vec<ID> {
+        self.lookup(vertex_id, &self.dependents)
+    }
+
+    /// Order in which every vertex comes after its dependencies, e.g. for deployment.
+    pub fn order(&self) -> Result<Vec<ID>, DependencyCycleError> {
+        Ok(self.levels()?.into_iter().flatten().collect())
+    }
+
+    /// Order in which every vertex comes before its dependencies, e.g. for teardown.
+    pub fn reverse_order(&self) -> Result<Vec<ID>, DependencyCycleError> {
+        Ok(self.reverse_levels()?.into_iter().flatten().collect())
+    }
+
+    /// Vertexes grouped into levels, in which every vertex depends only on vertexes in previous
+    /// levels. The vertexes in a level can thus be handled in parallel.
+    pub fn levels(&self) -> Result<Vec<Vec<ID>>, DependencyCycleError> {
+        self.kahn(&self.dependencies, &self.dependents)
+    }
+
+    /// Vertexes grouped into levels, in which every vertex is depended on only by vertexes in
+    /// previous levels.
+    pub fn reverse_levels(&self) -> Result<Vec<Vec<ID>>, DependencyCycleError> {
+        self.kahn(&self.dependents, &self.dependencies)
+    }
+
+    /// Find a dependency cycle.
+    ///
+    /// Each vertex in the returned cycle depends on the next one, and the last on the first.
+    pub fn find_cycle(&self) -> Option<Vec<ID>> {
+        self.levels().err().map(|error| error.cycle)
+    }
+
+    fn add_dependency(&mut self, index: usize, dependency_id: &ID) {
+        if let Some(dependency_index) = self.indexes.get(dependency_id) {
+            self.dependencies[index].insert(*dependency_index);
+            self.dependents[*dependency_index].insert(index);
+        }
+    }
+
+    fn lookup(&self, vertex_id: &ID, table: &[BTreeSet<usize>]) -> Vec<ID> {
+        match self.indexes.get(vertex_id) {
+            Some(index) => table[*index].iter().map(|index| self.vertex_ids[*index].clone()).collect(),
+            None => Default::default(),
+        }
+    }
+
+    // Kahn's algorithm, one level at a time.
+    fn kahn(
+        &self,
+        dependencies: &[BTreeSet<usize>],
+        dependents: &[BTreeSet<usize>],
+    ) -> Result<Vec<Vec<ID>>, DependencyCycleError> {
+        let mut remaining: Vec<usize> = dependencies.iter().map(|dependencies| dependencies.len()).collect();
+        let mut level: Vec<usize> = (0..remaining.len()).filter(|index| remaining[*index] == 0).collect();
+
+        let mut levels = Vec::default();
+        let mut count = 0;
+        while !level.is_empty() {
+            count += level.len();
+
+            let mut next_level = Vec::default();
+            for index in &level {
+                for dependent in &dependents[*index] {
+                    remaining[*dependent] -= 1;
+                    if remaining[*dependent] == 0 {
+                        next_level.push(*dependent);
+                    }
+                }
+            }
+            next_level.sort();
+
+            levels.push(level.iter().map(|index| self.vertex_ids[*index].clone()).collect());
+            level = next_level;
+        }
+
+        if count < remaining.len() {
+            return Err(DependencyCycleError::new(self.cycle(dependencies, &remaining)));
+        }
+
+        Ok(levels)
+    }
+
+    // Every vertex that Kahn's algorithm could not reach is waiting on another such vertex, so
+    // following them from any one of them must eventually repeat.
+    fn cycle(&self, dependencies: &[BTreeSet<usize>], remaining: &[usize]) -> Vec<ID> {
+        let mut path = Vec::default();
+        let mut positions = HashMap::new();
+
+        let mut index = remaining.iter().position(|remaining| *remaining != 0).expect("cycle");
+        loop {
+            if let Some(position) = positions.get(&index) {
+                return path[*position..].iter().map(|index: &usize| self.vertex_ids[*index].clone()).collect();
+            }
+
+            positions.insert(index, path.len());
+            path.push(index);
+
+            index = *dependencies[index].iter().find(|dependency| remaining[**dependency] != 0).expect("cycle");
+        }
+    }
+}
+
+//
+// DependencyCycleError
+//
+
+/// Dependency cycle error.
+#[derive(Debug, Error)]
+pub struct DependencyCycleError {
+    /// Cycle. Each vertex depends on the next one, and the last on the first.
+    pub cycle: Vec<ID>,
+}
+
+impl DependencyCycleError {
+    /// Constructor.
+    pub fn new(cycle: Vec<ID>) -> Self {
+        Self { cycle }
+    }
+}
+
+impl Depict for DependencyCycleError {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        write!(writer, "dependency cycle: {}", context.theme.error(self.path()))
+    }
+}
+
+impl fmt::Display for DependencyCycleError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "dependency cycle: {}", self.path())
+    }
+}
+
+impl DependencyCycleError {
+    // Cycle as text, ending with its start.
+    fn path(&self) -> String {
+        let ids: Vec<_> = self.cycle.iter().chain(self.cycle.first()).map(|id| id.to_string()).collect();
+        ids.join(" -> ")
+    }
+}
@@ -0,0 +1,151 @@
+use {
+    compris::{annotate::*, normal::*},
+    kutil::cli::depict::*,
+    std::{collections::*, fmt, io},
+};
+
+//
+// DiffField
+//
+
+/// Field of an entity that is compared as a unit.
+///
+/// Metadata and properties are compared per key, other fields as a whole.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DiffField {
+    /// Field, e.g. "class_ids".
+    Field(Variant<WithoutAnnotations>),
+
+    /// Metadata key.
+    Metadata(Variant<WithoutAnnotations>),
+
+    /// Property name.
+    Property(Variant<WithoutAnnotations>),
+}
+
+impl DiffField {
+    /// To Compris variant.
+    ///
+    /// A list of the path to the field, e.g. ["properties", "port"].
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        match self {
+            Self::Field(name) => vec![name.clone()].into_iter().collect::<List<_>>().into(),
+            Self::Metadata(key) => vec![METADATA.into(), key.clone()].into_iter().collect::<List<_>>().into(),
+            Self::Property(name) => vec![PROPERTIES.into(), name.clone()].into_iter().collect::<List<_>>().into(),
+        }
+    }
+}
+
+impl Depict for DiffField {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        match self {
+            Self::Field(name) => context.theme.write_meta(writer, name),
+            Self::Metadata(key) => {
+                context.theme.write_meta(writer, METADATA)?;
+                context.theme.write_delimiter(writer, '.')?;
+                context.theme.write_meta(writer, key)
+            }
+            Self::Property(name) => {
+                context.theme.write_meta(writer, PROPERTIES)?;
+                context.theme.write_delimiter(writer, '.')?;
+                context.theme.write_meta(writer, name)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DiffField {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(name) => fmt::Display::fmt(name, formatter),
+            Self::Metadata(key) => write!(formatter, "{}.{}", METADATA, key),
+            Self::Property(name) => write!(formatter, "{}.{}", PROPERTIES, name),
+        }
+    }
+}
+
+// Utils
+
+const METADATA: &str = "metadata";
+const PROPERTIES: &str = "properties";
+
+// Revisions are store bookkeeping and are not compared.
+const REVISION: &str = "revision";
+
+/// Diff fields of an entity.
+pub(crate) type DiffFields = BTreeMap<DiffField, Variant<WithoutAnnotations>>;
+
+// Split an entity variant into diff fields.
+pub(crate) fn to_diff_fields(entity: &Map<WithoutAnnotations>) -> DiffFields {
+    let mut fields = DiffFields::default();
+
+    for (key, value) in &entity.inner {
+        match (key, value) {
+            (Variant::Text(text), _) if text.inner == REVISION => {}
+
+            (Variant::Text(text), Variant::Map(metadata)) if text.inner == METADATA => {
+                for (key, value) in &metadata.inner {
+                    fields.insert(DiffField::Metadata(key.clone()), value.clone());
+                }
+            }
+
+            (Variant::Text(text), Variant::Map(properties)) if text.inner == PROPERTIES => {
+                for (name, property) in &properties.inner {
+                    fields.insert(DiffField::Property(name.clone()), property.clone());
+                }
+            }
+
+            _ => {
+                fields.insert(DiffField::Field(key.clone()), value.clone());
+            }
+        }
+    }
+
+    fields
+}
+
+// Join diff fields back into an entity variant.
+pub(crate) fn from_diff_fields(
+    fields: DiffFields,
+    revision: Option<Variant<WithoutAnnotations>>,
+) -> Map<WithoutAnnotations> {
+    let mut entity = Map::default();
+    let mut metadata = Map::default();
+    let mut properties = Map::default();
+
+    for (field, value) in fields {
+        match field {
+            DiffField::Field(name) => {
+                entity.inner.insert(name, value);
+            }
+
+            DiffField::Metadata(key) => {
+                metadata.inner.insert(key, value);
+            }
+
+            DiffField::Property(name) => {
+                properties.inner.insert(name, value);
+            }
+        }
+    }
+
+    if let Some(revision) = revision {
+        entity.into_insert(REVISION, revision);
+    }
+
+    entity.into_insert(METADATA, metadata);
+
+    if !properties.inner.is_empty() {
+        entity.into_insert(PROPERTIES, properties);
+    }
+
+    entity
+}
+
+// The revision of an entity variant.
+pub(crate) fn get_diff_revision(entity: &Map<WithoutAnnotations>) -> Option<Variant<WithoutAnnotations>> {
+    entity.into_get(REVISION).cloned()
+}
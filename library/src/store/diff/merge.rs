@@ -0,0 +1,356 @@
+use super::{
+    super::{
+        super::data::{from_variant::*, *},
+        errors::*,
+        store::*,
+    },
+    field::*,
+    snapshot::*,
+    store_diff::*,
+};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::{cli::depict::*, std::iter::*},
+    std::{collections::*, io},
+};
+
+//
+// StoreMerge
+//
+
+/// Three-way store merge.
+///
+/// Conflicts are resolved in favor of the local store. A merge with conflicts is only applied when
+/// explicitly asked to keep the local values.
+#[derive(Clone, Debug, Default)]
+pub struct StoreMerge {
+    /// Changes to the local store.
+    pub changes: StoreDiff,
+
+    /// Conflicts.
+    pub conflicts: Vec<MergeConflict>,
+
+    // Merged entities that differ from the local store.
+    merged: Vec<(ID, MergedEntity)>,
+}
+
+impl StoreMerge {
+    /// True if there are no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// Apply the changes to the local store.
+    ///
+    /// Fails with [StoreError::Conflict] if there are conflicts, unless `keep_local` is true, in
+    /// which case the local values are kept. Also fails with [StoreError::Conflict] if an entity
+    /// was changed in the local store since the merge.
+    ///
+    /// Done in a single transaction.
+    pub fn apply<StoreT>(&self, store: &StoreT, keep_local: bool) -> Result<(), StoreError>
+    where
+        StoreT: Store,
+    {
+        if !keep_local && !self.is_clean() {
+            return Err(StoreError::Conflict(format!("merge has {} conflicts", self.conflicts.len())));
+        }
+
+        store.transaction(|| {
+            for (id, entity) in &self.merged {
+                match entity {
+                    MergedEntity::Changed(entity) => {
+                        let entity = Entity::from_variant(&Variant::from(entity.clone()))
+                            .map_err(|error| StoreError::Backend(error.to_string()))?;
+
+                        // The entity has the revision of the local entity (or none if added)
+                        match entity {
+                            Entity::Class(class) => store.update_class_if_revision(class)?,
+                            Entity::VertexTemplate(vertex_template) => {
+                                store.update_vertex_template_if_revision(vertex_template)?
+                            }
+                            Entity::EdgeTemplate(edge_template) => {
+                                store.update_edge_template_if_revision(edge_template)?
+                            }
+                            Entity::Vertex(vertex) => store.update_vertex_if_revision(vertex)?,
+                            Entity::Edge(edge) => store.update_edge_if_revision(edge)?,
+                        };
+                    }
+
+                    MergedEntity::Removed(local) => {
+                        let revision = get_revision(local).map_err(|error| StoreError::Backend(error.to_string()))?;
+                        let stored_revision = match id.kind {
+                            Kind::Class => store.get_class(id)?.map(|class| class.get_revision()),
+                            Kind::VertexTemplate => {
+                                store.get_vertex_template(id)?.map(|vertex_template| vertex_template.get_revision())
+                            }
+                            Kind::EdgeTemplate => {
+                                store.get_edge_template(id)?.map(|edge_template| edge_template.get_revision())
+                            }
+                            Kind::Vertex => store.get_vertex(id)?.map(|vertex| vertex.get_revision()),
+                            Kind::Edge => store.get_edge(id)?.map(|edge| edge.get_revision()),
+                        };
+                        check_revision(id, Some(revision), stored_revision.unwrap_or_default())?;
+
+                        match id.kind {
+                            Kind::Class => store.remove_class(id)?.map(|_| ()),
+                            Kind::VertexTemplate => store.remove_vertex_template(id)?.map(|_| ()),
+                            Kind::EdgeTemplate => store.remove_edge_template(id)?.map(|_| ()),
+                            Kind::Vertex => store.remove_vertex(id)?.map(|_| ()),
+                            Kind::Edge => store.remove_edge(id)?.map(|_| ()),
+                        };
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// To Compris variant.
+    ///
+    /// A map with "changes" (see [StoreDiff::to_variant]) and "conflicts" keys. The latter is a
+    /// list of maps with "kind", "id", "field", "base", "local", and "remote" keys. Missing values
+    /// are omitted.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        let mut map = Map::default();
+        map.into_insert("changes", self.changes.to_variant());
+        map.into_insert("conflicts", self.conflicts.iter().map(|conflict| conflict.to_variant()).collect::<List<_>>());
+        map.into()
+    }
+
+    // Merge of snapshots.
+    pub(crate) fn new(base: &Snapshot, local: &Snapshot, remote: &Snapshot) -> Self {
+        let mut merge = Self::default();
+
+        for key in Snapshot::keys(&[base, local, remote]) {
+            let (base, local, remote) = (base.entities.get(key), local.entities.get(key), remote.entities.get(key));
+            let Some((id, _)) = base.or(local).or(remote) else {
+                continue;
+            };
+
+            let base_fields = base.map(|(_, base)| to_diff_fields(base));
+            let local_fields = local.map(|(_, local)| to_diff_fields(local));
+            let remote_fields = remote.map(|(_, remote)| to_diff_fields(remote));
+
+            let merged_fields = match (&local_fields, &remote_fields) {
+                (Some(local_fields), Some(remote_fields)) => {
+                    Some(merge.merge_fields(id, &base_fields.clone().unwrap_or_default(), local_fields, remote_fields))
+                }
+
+                // Added or changed locally, and remote did not have it or removed it
+                (Some(local_fields), None) => match &base_fields {
+                    Some(base_fields) if base_fields == local_fields => None,
+                    Some(_) => {
+                        merge.conflict(id, None, base, local, remote);
+                        Some(local_fields.clone())
+                    }
+                    None => Some(local_fields.clone()),
+                },
+
+                // Added or changed remotely, and local did not have it or removed it
+                (None, Some(remote_fields)) => match &base_fields {
+                    Some(base_fields) if base_fields == remote_fields => None,
+                    Some(_) => {
+                        merge.conflict(id, None, base, local, remote);
+                        None
+                    }
+                    None => Some(remote_fields.clone()),
+                },
+
+                (None, None) => None,
+            };
+
+            let change = match (local_fields, merged_fields) {
+                (Some(local_fields), Some(merged_fields)) => {
+                    let fields = diff_fields(&local_fields, &merged_fields);
+                    if fields.is_empty() {
+                        continue;
+                    }
+
+                    let revision = local.and_then(|(_, local)| get_diff_revision(local));
+                    merge.merged.push((id.clone(), MergedEntity::Changed(from_diff_fields(merged_fields, revision))));
+                    EntityChange::Changed(fields)
+                }
+
+                (Some(_), None) => {
+                    let local = local.map(|(_, local)| local.clone()).unwrap_or_default();
+                    merge.merged.push((id.clone(), MergedEntity::Removed(local.clone())));
+                    EntityChange::Removed(local.into())
+                }
+
+                (None, Some(merged_fields)) => {
+                    let entity = from_diff_fields(merged_fields, None);
+                    merge.merged.push((id.clone(), MergedEntity::Changed(entity.clone())));
+                    EntityChange::Added(entity.into())
+                }
+
+                (None, None) => continue,
+            };
+
+            merge.changes.entities.push(EntityDiff::new(id.clone(), change));
+        }
+
+        merge
+    }
+
+    fn merge_fields(&mut self, id: &ID, base: &DiffFields, local: &DiffFields, remote: &DiffFields) -> DiffFields {
+        let mut merged = DiffFields::default();
+
+        let fields: BTreeSet<_> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+
+        for field in fields {
+            let (base, local, remote) = (base.get(field), local.get(field), remote.get(field));
+
+            let value = if (local == remote) || (remote == base) {
+                local
+            } else if local == base {
+                remote
+            } else {
+                self.conflicts.push(MergeConflict::new(
+                    id.clone(),
+                    Some(field.clone()),
+                    base.cloned(),
+                    local.cloned(),
+                    remote.cloned(),
+                ));
+                local
+            };
+
+            if let Some(value) = value {
+                merged.insert(field.clone(), value.clone());
+            }
+        }
+
+        merged
+    }
+
+    fn conflict(
+        &mut self,
+        id: &ID,
+        field: Option<DiffField>,
+        base: Option<&(ID, Map<WithoutAnnotations>)>,
+        local: Option<&(ID, Map<WithoutAnnotations>)>,
+        remote: Option<&(ID, Map<WithoutAnnotations>)>,
+    ) {
+        let entity = |entity: Option<&(ID, Map<WithoutAnnotations>)>| entity.map(|(_, entity)| entity.clone().into());
+        self.conflicts.push(MergeConflict::new(id.clone(), field, entity(base), entity(local), entity(remote)));
+    }
+}
+
+impl Depict for StoreMerge {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        self.changes.depict(writer, context)?;
+        for (conflict, first) in IterateWithFirst::new(&self.conflicts) {
+            context.separate_or_indent(writer, first && self.changes.is_empty())?;
+            conflict.depict(writer, context)?;
+        }
+        Ok(())
+    }
+}
+
+//
+// MergedEntity
+//
+
+// Merged entity that differs from the local store.
+#[derive(Clone, Debug)]
+enum MergedEntity {
+    // Added or changed, with the revision of the local entity (if any).
+    Changed(Map<WithoutAnnotations>),
+
+    // Removed, with the local entity.
+    Removed(Map<WithoutAnnotations>),
+}
+
+//
+// MergeConflict
+//
+
+/// Merge conflict.
+///
+/// Values are [None] if missing.
+#[derive(Clone, Debug)]
+pub struct MergeConflict {
+    /// Entity ID.
+    pub id: ID,
+
+    /// Field. [None] if the conflict is about the entire entity, i.e. it was removed on one side
+    /// and changed on the other. The values are then entities.
+    pub field: Option<DiffField>,
+
+    /// Base value.
+    pub base: Option<Variant<WithoutAnnotations>>,
+
+    /// Local value.
+    pub local: Option<Variant<WithoutAnnotations>>,
+
+    /// Remote value.
+    pub remote: Option<Variant<WithoutAnnotations>>,
+}
+
+impl MergeConflict {
+    /// Constructor.
+    pub fn new(
+        id: ID,
+        field: Option<DiffField>,
+        base: Option<Variant<WithoutAnnotations>>,
+        local: Option<Variant<WithoutAnnotations>>,
+        remote: Option<Variant<WithoutAnnotations>>,
+    ) -> Self {
+        Self { id, field, base, local, remote }
+    }
+
+    /// To Compris variant.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        let mut map = Map::default();
+        map.into_insert("kind", self.id.kind.to_string());
+        map.into_insert("id", self.id.to_string());
+        if let Some(field) = &self.field {
+            map.into_insert("field", field.to_variant());
+        }
+        for (key, value) in [("base", &self.base), ("local", &self.local), ("remote", &self.remote)] {
+            if let Some(value) = value {
+                map.into_insert(key, value.clone());
+            }
+        }
+        map.into()
+    }
+}
+
+impl Depict for MergeConflict {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        self.id.kind.depict(writer, context)?;
+        write!(writer, " ")?;
+        self.id.depict(writer, context)?;
+        if let Some(field) = &self.field {
+            write!(writer, " ")?;
+            field.depict(writer, context)?;
+        }
+        context.theme.write_delimiter(writer, ':')?;
+        write!(writer, " {}", context.theme.error("conflict"))?;
+
+        for ((name, value), last) in
+            IterateWithLast::new([("base", &self.base), ("local", &self.local), ("remote", &self.remote)])
+        {
+            context.indent_into_branch(writer, last)?;
+            context.theme.write_meta(writer, name)?;
+            context.theme.write_delimiter(writer, ':')?;
+            match &self.field {
+                Some(_) => depict_diff_value(value.as_ref(), writer, context)?,
+                None => {
+                    let state = if value.is_some() { "present" } else { "missing" };
+                    write!(writer, " {}", context.theme.symbol(state))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+mod field;
+mod merge;
+mod snapshot;
+mod store_diff;
+mod utilities;
+
+#[allow(unused_imports)]
+pub use {field::*, merge::*, store_diff::*, utilities::*};
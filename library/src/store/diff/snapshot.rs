@@ -0,0 +1,46 @@
+use super::super::{super::data::*, errors::*, query::*, store::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    std::collections::*,
+};
+
+const SNAPSHOT_KINDS: [Kind; 5] = [Kind::Class, Kind::VertexTemplate, Kind::EdgeTemplate, Kind::Vertex, Kind::Edge];
+
+//
+// Snapshot
+//
+
+// Entities as variants, ordered by kind and then by ID.
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    pub(crate) entities: BTreeMap<SnapshotKey, (ID, Map<WithoutAnnotations>)>,
+}
+
+pub(crate) type SnapshotKey = (usize, String);
+
+impl Snapshot {
+    // Snapshot of the entities that match a query.
+    pub(crate) fn new<StoreT>(store: &StoreT, query: &StoreQuery) -> Result<Self, StoreError>
+    where
+        StoreT: Store,
+    {
+        let mut snapshot = Self::default();
+
+        for (index, kind) in SNAPSHOT_KINDS.into_iter().enumerate() {
+            for entity in store.get_entities(kind, query)? {
+                if let Variant::Map(map) = entity.to_variant(false, store)? {
+                    let id = entity.get_id();
+                    snapshot.entities.insert((index, id.to_string()), (id.clone(), map));
+                }
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    // Union of the keys of several snapshots.
+    pub(crate) fn keys<'own>(snapshots: &[&'own Self]) -> BTreeSet<&'own SnapshotKey> {
+        snapshots.iter().flat_map(|snapshot| snapshot.entities.keys()).collect()
+    }
+}
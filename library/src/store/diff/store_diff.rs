@@ -0,0 +1,247 @@
+use super::{super::super::data::*, field::*, snapshot::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::{cli::depict::*, std::iter::*},
+    std::{collections::*, io},
+};
+
+//
+// StoreDiff
+//
+
+/// Store diff.
+#[derive(Clone, Debug, Default)]
+pub struct StoreDiff {
+    /// Entity diffs, ordered by kind and then by ID.
+    pub entities: Vec<EntityDiff>,
+}
+
+impl StoreDiff {
+    /// True if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// To Compris variant.
+    ///
+    /// A list of maps with "kind", "id", and "change" keys. Added and removed entities also have
+    /// an "entity" key, changed entities a "fields" key with a list of maps with "field", "before",
+    /// and "after" keys. Missing values are omitted.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        self.entities.iter().map(|entity| entity.to_variant()).collect::<List<_>>().into()
+    }
+
+    // Diff of two snapshots.
+    pub(crate) fn new(before: &Snapshot, after: &Snapshot) -> Self {
+        let mut diff = Self::default();
+
+        for key in Snapshot::keys(&[before, after]) {
+            let entity = match (before.entities.get(key), after.entities.get(key)) {
+                (Some((id, before)), Some((_, after))) => {
+                    let fields = diff_fields(&to_diff_fields(before), &to_diff_fields(after));
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    EntityDiff::new(id.clone(), EntityChange::Changed(fields))
+                }
+
+                (Some((id, before)), None) => EntityDiff::new(id.clone(), EntityChange::Removed(before.clone().into())),
+                (None, Some((id, after))) => EntityDiff::new(id.clone(), EntityChange::Added(after.clone().into())),
+                (None, None) => continue,
+            };
+
+            diff.entities.push(entity);
+        }
+
+        diff
+    }
+}
+
+impl Depict for StoreDiff {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        for (entity, first) in IterateWithFirst::new(&self.entities) {
+            context.separate_or_indent(writer, first)?;
+            entity.depict(writer, context)?;
+        }
+        Ok(())
+    }
+}
+
+//
+// EntityDiff
+//
+
+/// Entity diff.
+#[derive(Clone, Debug)]
+pub struct EntityDiff {
+    /// Entity ID.
+    pub id: ID,
+
+    /// Change.
+    pub change: EntityChange,
+}
+
+impl EntityDiff {
+    /// Constructor.
+    pub fn new(id: ID, change: EntityChange) -> Self {
+        Self { id, change }
+    }
+
+    /// To Compris variant.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        let mut map = Map::default();
+        map.into_insert("kind", self.id.kind.to_string());
+        map.into_insert("id", self.id.to_string());
+
+        match &self.change {
+            EntityChange::Added(entity) => {
+                map.into_insert("change", "added");
+                map.into_insert("entity", entity.clone());
+            }
+
+            EntityChange::Removed(entity) => {
+                map.into_insert("change", "removed");
+                map.into_insert("entity", entity.clone());
+            }
+
+            EntityChange::Changed(fields) => {
+                map.into_insert("change", "changed");
+                map.into_insert("fields", fields.iter().map(|field| field.to_variant()).collect::<List<_>>());
+            }
+        }
+
+        map.into()
+    }
+}
+
+impl Depict for EntityDiff {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        self.id.kind.depict(writer, context)?;
+        write!(writer, " ")?;
+        self.id.depict(writer, context)?;
+        context.theme.write_delimiter(writer, ':')?;
+
+        match &self.change {
+            EntityChange::Added(_) => write!(writer, " {}", context.theme.meta("added")),
+            EntityChange::Removed(_) => write!(writer, " {}", context.theme.meta("removed")),
+            EntityChange::Changed(fields) => {
+                write!(writer, " {}", context.theme.meta("changed"))?;
+                for (field, last) in IterateWithLast::new(fields) {
+                    context.indent_into_branch(writer, last)?;
+                    field.depict(writer, context)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+//
+// EntityChange
+//
+
+/// Entity change.
+#[derive(Clone, Debug)]
+pub enum EntityChange {
+    /// Added. Contains the entity.
+    Added(Variant<WithoutAnnotations>),
+
+    /// Removed. Contains the entity.
+    Removed(Variant<WithoutAnnotations>),
+
+    /// Changed.
+    Changed(Vec<FieldDiff>),
+}
+
+//
+// FieldDiff
+//
+
+/// Field diff.
+#[derive(Clone, Debug)]
+pub struct FieldDiff {
+    /// Field.
+    pub field: DiffField,
+
+    /// Value before. [None] if missing.
+    pub before: Option<Variant<WithoutAnnotations>>,
+
+    /// Value after. [None] if missing.
+    pub after: Option<Variant<WithoutAnnotations>>,
+}
+
+impl FieldDiff {
+    /// Constructor.
+    pub fn new(
+        field: DiffField,
+        before: Option<Variant<WithoutAnnotations>>,
+        after: Option<Variant<WithoutAnnotations>>,
+    ) -> Self {
+        Self { field, before, after }
+    }
+
+    /// To Compris variant.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        let mut map = Map::default();
+        map.into_insert("field", self.field.to_variant());
+        if let Some(before) = &self.before {
+            map.into_insert("before", before.clone());
+        }
+        if let Some(after) = &self.after {
+            map.into_insert("after", after.clone());
+        }
+        map.into()
+    }
+}
+
+impl Depict for FieldDiff {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        self.field.depict(writer, context)?;
+        context.theme.write_delimiter(writer, ':')?;
+        depict_diff_value(self.before.as_ref(), writer, context)?;
+        write!(writer, " {}", context.theme.delimiter("→"))?;
+        depict_diff_value(self.after.as_ref(), writer, context)
+    }
+}
+
+// Utils
+
+// Diffs of the fields that differ.
+pub(crate) fn diff_fields(before: &DiffFields, after: &DiffFields) -> Vec<FieldDiff> {
+    let fields: BTreeSet<_> = before.keys().chain(after.keys()).collect();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let (before, after) = (before.get(field), after.get(field));
+            (before != after).then(|| FieldDiff::new(field.clone(), before.cloned(), after.cloned()))
+        })
+        .collect()
+}
+
+// Depict a value on a single line.
+pub(crate) fn depict_diff_value<WriteT>(
+    value: Option<&Variant<WithoutAnnotations>>,
+    writer: &mut WriteT,
+    context: &DepictionContext,
+) -> io::Result<()>
+where
+    WriteT: io::Write,
+{
+    match value {
+        Some(value) => {
+            value.depict(writer, &context.child().with_format(DepictionFormat::Compact).with_separator(true))
+        }
+        None => write!(writer, " {}", context.theme.symbol("None")),
+    }
+}
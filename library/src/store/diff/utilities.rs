@@ -0,0 +1,65 @@
+use super::{
+    super::{errors::*, query::*, store::*},
+    merge::*,
+    snapshot::*,
+    store_diff::*,
+};
+
+//
+// StoreDiffUtilities
+//
+
+/// Diff utilities for [Store].
+///
+/// Entities are compared via their Compris variants. Metadata and properties are compared per key,
+/// other fields as a whole. Revisions are ignored.
+///
+/// To compare snapshots of a store, import [bundles](super::super::StoreBundleUtilities) into
+/// in-memory stores.
+pub trait StoreDiffUtilities {
+    /// Diff of the entities that match a query in this store (before) and in another store
+    /// (after).
+    fn diff<OtherStoreT>(&self, other: &OtherStoreT, query: &StoreQuery) -> Result<StoreDiff, StoreError>
+    where
+        OtherStoreT: Store;
+
+    /// Three-way merge of the entities that match a query, with this store as the local one.
+    ///
+    /// The base is the common ancestor of the local and remote stores. Changes made on only one
+    /// side are taken. Changes made on both sides conflict unless they are the same. Use
+    /// [StoreMerge::apply] to apply the result to this store.
+    fn merge<BaseStoreT, RemoteStoreT>(
+        &self,
+        base: &BaseStoreT,
+        remote: &RemoteStoreT,
+        query: &StoreQuery,
+    ) -> Result<StoreMerge, StoreError>
+    where
+        BaseStoreT: Store,
+        RemoteStoreT: Store;
+}
+
+impl<StoreT> StoreDiffUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn diff<OtherStoreT>(&self, other: &OtherStoreT, query: &StoreQuery) -> Result<StoreDiff, StoreError>
+    where
+        OtherStoreT: Store,
+    {
+        Ok(StoreDiff::new(&Snapshot::new(self, query)?, &Snapshot::new(other, query)?))
+    }
+
+    fn merge<BaseStoreT, RemoteStoreT>(
+        &self,
+        base: &BaseStoreT,
+        remote: &RemoteStoreT,
+        query: &StoreQuery,
+    ) -> Result<StoreMerge, StoreError>
+    where
+        BaseStoreT: Store,
+        RemoteStoreT: Store,
+    {
+        Ok(StoreMerge::new(&Snapshot::new(base, query)?, &Snapshot::new(self, query)?, &Snapshot::new(remote, query)?))
+    }
+}
@@ -1,3 +1,5 @@
+use super::super::data::*;
+
 use {
     kutil::cli::depict::*,
     std::{io, sync::*},
@@ -18,6 +20,26 @@ pub enum StoreError {
     /// Concurrency.
     #[error("concurrency: {0}")]
     Concurrency(String),
+
+    /// Conflict.
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    /// Class cycle.
+    #[error("class cycle: {0}")]
+    ClassCycle(String),
+
+    /// Invalid lifecycle transition.
+    #[error("invalid lifecycle transition: {0}")]
+    InvalidTransition(String),
+
+    /// Transaction.
+    #[error("transaction: {0}")]
+    Transaction(String),
+
+    /// Backend.
+    #[error("backend: {0}")]
+    Backend(String),
 }
 
 impl Depict for StoreError {
@@ -28,6 +50,13 @@ impl Depict for StoreError {
         match self {
             Self::ID(id) => write!(writer, "ID: {}", context.theme.error(id)),
             Self::Concurrency(concurrency) => write!(writer, "concurrency: {}", context.theme.error(concurrency)),
+            Self::Conflict(conflict) => write!(writer, "conflict: {}", context.theme.error(conflict)),
+            Self::ClassCycle(class_cycle) => write!(writer, "class cycle: {}", context.theme.error(class_cycle)),
+            Self::InvalidTransition(transition) => {
+                write!(writer, "invalid lifecycle transition: {}", context.theme.error(transition))
+            }
+            Self::Transaction(transaction) => write!(writer, "transaction: {}", context.theme.error(transaction)),
+            Self::Backend(backend) => write!(writer, "backend: {}", context.theme.error(backend)),
         }
     }
 }
@@ -37,3 +66,35 @@ impl<GuardT> From<PoisonError<GuardT>> for StoreError {
         Self::Concurrency(error.to_string())
     }
 }
+
+impl From<io::Error> for StoreError {
+    fn from(error: io::Error) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for StoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::Backend(error.to_string())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::task::JoinError> for StoreError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        Self::Concurrency(error.to_string())
+    }
+}
+
+// Utils
+
+// Check that the revision is the expected one (if provided).
+pub(crate) fn check_revision(id: &ID, expected_revision: Option<u64>, revision: u64) -> Result<(), StoreError> {
+    match expected_revision {
+        Some(expected_revision) if expected_revision != revision => {
+            Err(StoreError::Conflict(format!("{}: expected revision {}, found {}", id, expected_revision, revision)))
+        }
+        _ => Ok(()),
+    }
+}
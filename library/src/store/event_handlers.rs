@@ -0,0 +1,95 @@
+use super::{super::data::*, class_hierarchy::*, errors::*, store::*};
+
+use {kutil::std::immutable::*, std::collections::*};
+
+//
+// StoreEventHandlerUtilities
+//
+
+/// Event handler utilities for [Store].
+///
+/// A vertex's or edge's own event handlers come first, then those inherited from its origin
+/// template, then those inherited from its classes nearest first (see
+/// [get_inherited_class_ids](StoreClassHierarchyUtilities::get_inherited_class_ids)). The first
+/// of these that has handlers for an event ID overrides all later ones for that event ID.
+pub trait StoreEventHandlerUtilities {
+    /// Event handlers of classes, including those inherited from their ancestors.
+    fn get_class_event_handlers(&self, class_ids: &[ID]) -> Result<Vec<EventHandler>, StoreError>;
+
+    /// Event handlers of a vertex or an edge, including those inherited from its origin template
+    /// and its classes.
+    ///
+    /// Note that instances copy the event handlers of their origin templates when instantiated.
+    fn get_event_handlers(&self, id: &ID) -> Result<Vec<EventHandler>, StoreError>;
+}
+
+impl<StoreT> StoreEventHandlerUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn get_class_event_handlers(&self, class_ids: &[ID]) -> Result<Vec<EventHandler>, StoreError> {
+        let mut inherited = InheritedEventHandlers::default();
+
+        for class_id in self.get_inherited_class_ids(class_ids)? {
+            if let Some(class) = self.get_class(&class_id)? {
+                inherited.inherit(class.event_handlers);
+            }
+        }
+
+        Ok(inherited.event_handlers)
+    }
+
+    fn get_event_handlers(&self, id: &ID) -> Result<Vec<EventHandler>, StoreError> {
+        let instance = match id.kind {
+            Kind::Vertex => self.get_vertex(id)?.map(|vertex| vertex.instance),
+            Kind::Edge => self.get_edge(id)?.map(|edge| edge.instance),
+            kind => return Err(StoreError::ID(format!("not a vertex or an edge: {}", kind))),
+        }
+        .ok_or_else(|| StoreError::ID(format!("not found: {}", id)))?;
+
+        let mut inherited = InheritedEventHandlers::default();
+        inherited.inherit(instance.event_handlers);
+
+        if let Some(origin_template_id) = &instance.origin_template_id {
+            let template = match origin_template_id.kind {
+                Kind::VertexTemplate => {
+                    self.get_vertex_template(origin_template_id)?.map(|vertex_template| vertex_template.template)
+                }
+                _ => self.get_edge_template(origin_template_id)?.map(|edge_template| edge_template.template),
+            };
+
+            if let Some(template) = template {
+                inherited.inherit(template.event_handlers);
+            }
+        }
+
+        inherited.inherit(self.get_class_event_handlers(&instance.class_ids)?);
+
+        Ok(inherited.event_handlers)
+    }
+}
+
+//
+// InheritedEventHandlers
+//
+
+#[derive(Default)]
+struct InheritedEventHandlers {
+    event_handlers: Vec<EventHandler>,
+    event_ids: HashSet<ByteString>,
+}
+
+impl InheritedEventHandlers {
+    // Adds the event handlers for event IDs that we don't have yet.
+    fn inherit(&mut self, event_handlers: Vec<EventHandler>) {
+        let event_ids: HashSet<_> = event_handlers.iter().map(|event_handler| event_handler.event_id.clone()).collect();
+
+        for event_handler in event_handlers {
+            if !self.event_ids.contains(&event_handler.event_id) {
+                self.event_handlers.push(event_handler);
+            }
+        }
+
+        self.event_ids.extend(event_ids);
+    }
+}
@@ -0,0 +1,157 @@
+use super::{super::data::*, errors::*, query::*};
+
+use {
+    kutil::std::*,
+    std::sync::{mpsc::*, *},
+};
+
+//
+// StoreChange
+//
+
+/// Store change.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+pub enum StoreChange {
+    /// Added.
+    Added,
+
+    /// Updated.
+    Updated,
+
+    /// Removed.
+    Removed,
+}
+
+//
+// StoreEvent
+//
+
+/// Store event.
+#[derive(Clone, Debug)]
+pub struct StoreEvent {
+    /// Change.
+    pub change: StoreChange,
+
+    /// Entity ID.
+    pub id: ID,
+
+    /// Entity before the change. Will be [None] if [StoreChange::Added].
+    pub before: Option<Entity>,
+
+    /// Entity after the change. Will be [None] if [StoreChange::Removed].
+    pub after: Option<Entity>,
+}
+
+impl StoreEvent {
+    /// Constructor.
+    ///
+    /// The change is determined by the presence of the before and after entities. Returns [None] if
+    /// both are missing.
+    pub fn new(id: ID, before: Option<Entity>, after: Option<Entity>) -> Option<Self> {
+        let change = match (&before, &after) {
+            (None, Some(_)) => StoreChange::Added,
+            (Some(_), Some(_)) => StoreChange::Updated,
+            (Some(_), None) => StoreChange::Removed,
+            (None, None) => return None,
+        };
+
+        Some(Self { change, id, before, after })
+    }
+
+    /// The entity after the change, or before the change if it was removed.
+    pub fn get_entity(&self) -> Option<&Entity> {
+        self.after.as_ref().or(self.before.as_ref())
+    }
+}
+
+//
+// StoreEventFilter
+//
+
+/// Store event filter.
+#[derive(Clone, Debug, Default)]
+pub struct StoreEventFilter {
+    /// Optional kinds. The entity must be of one of them.
+    pub kinds: Option<Vec<Kind>>,
+
+    /// Query. The entity must match it.
+    ///
+    /// For removals the entity before the change is matched, otherwise the entity after the
+    /// change.
+    pub query: StoreQuery,
+}
+
+impl StoreEventFilter {
+    /// With kind.
+    pub fn with_kind(mut self, kind: Kind) -> Self {
+        self.kinds.get_or_insert_default().push(kind);
+        self
+    }
+
+    /// With query.
+    pub fn with_query(mut self, query: StoreQuery) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// True if the event matches.
+    pub fn matches(&self, event: &StoreEvent) -> bool {
+        if let Some(kinds) = &self.kinds
+            && !kinds.contains(&event.id.kind)
+        {
+            return false;
+        }
+
+        match event.get_entity() {
+            Some(entity) => self.query.matches(entity),
+            None => false,
+        }
+    }
+}
+
+//
+// StoreEventReceiver
+//
+
+/// Receiver of [StoreEvent].
+///
+/// Dropping it unsubscribes.
+pub type StoreEventReceiver = Receiver<StoreEvent>;
+
+//
+// StoreEventPublisher
+//
+
+/// Publishes [StoreEvent] to subscribers.
+///
+/// Meant to be used by [Store](super::store::Store) implementations.
+#[derive(Debug, Default)]
+pub struct StoreEventPublisher {
+    subscribers: Mutex<Vec<(StoreEventFilter, Sender<StoreEvent>)>>,
+}
+
+impl StoreEventPublisher {
+    /// Subscribe.
+    pub fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        let (sender, receiver) = channel();
+        self.subscribers.lock()?.push((filter, sender));
+        Ok(receiver)
+    }
+
+    /// True if there are subscribers.
+    ///
+    /// Can be used to avoid the work of constructing events.
+    pub fn has_subscribers(&self) -> Result<bool, StoreError> {
+        Ok(!self.subscribers.lock()?.is_empty())
+    }
+
+    /// Publish an event to all subscribers whose filter matches it.
+    ///
+    /// Subscribers whose receivers have been dropped are removed.
+    pub fn publish(&self, event: StoreEvent) -> Result<(), StoreError> {
+        self.subscribers
+            .lock()?
+            .retain(|(filter, sender)| if filter.matches(&event) { sender.send(event.clone()).is_ok() } else { true });
+        Ok(())
+    }
+}
@@ -0,0 +1,480 @@
+use super::super::{super::data::*, errors::*, events::*, formats::*, id_strategy::*, query::*, transaction_lock::*};
+
+use {
+    compris::{annotate::*, normal::*, *},
+    kutil::std::{collections::*, immutable::*},
+    std::{collections::*, fs, io, path::*, sync::*, time::*},
+};
+
+// The previous states of the entities written in the transaction
+pub(crate) type FilesystemTransaction = Vec<(ID, Option<Entity>)>;
+
+//
+// FilesystemStoreBackend
+//
+
+/// Filesystem store backend.
+///
+/// Directory segments are mapped to filesystem directories under the root, and each entity is
+/// stored in its own file, named "{id}.{kind}.{extension}". Files and directories starting with
+/// "." are reserved for the backend.
+///
+/// Entities are cached in memory. A cached entity is reloaded whenever the modification time or
+/// size of its file changes, so that edits made by other processes (or by hand) are picked up.
+pub struct FilesystemStoreBackend {
+    /// Root directory.
+    pub root: PathBuf,
+
+    /// Format.
+    pub format: Format,
+
+    /// ID strategy.
+    pub id_strategy: Box<dyn IDStrategy>,
+
+    pub(crate) cache: FastConcurrentHashMap<ID, CachedEntity>,
+
+    // Serializes file access so that the cache would be consistent with the files
+    pub(crate) file_lock: Mutex<()>,
+
+    pub(crate) next_ids: Mutex<HashMap<Kind, u64>>,
+    pub(crate) publisher: StoreEventPublisher,
+
+    // Serializes writes so that revisions can be compared and set atomically
+    pub(crate) write_lock: Mutex<()>,
+
+    // Held by transactions and writes so that they would not interleave with other owners'
+    pub(crate) transaction_lock: TransactionLock,
+
+    // A stack of nested transactions, all belonging to the owner of the transaction lock
+    pub(crate) transactions: Mutex<Vec<FilesystemTransaction>>,
+}
+
+impl FilesystemStoreBackend {
+    /// Constructor.
+    ///
+    /// Creates the root directory if it doesn't exist.
+    ///
+    /// Supported formats are YAML, JSON, XJSON, CBOR, and MessagePack. Note that YAML and JSON do
+    /// not fully preserve the types of variants (e.g. unsigned integers and blobs).
+    pub fn new<PathT>(root: PathT, format: Format) -> Result<Self, StoreError>
+    where
+        PathT: Into<PathBuf>,
+    {
+        if matches!(format, Format::XML) {
+            return Err(StoreError::Backend(format!("unsupported format: {}", format)));
+        }
+
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+
+        Ok(Self {
+            root,
+            format,
+            id_strategy: Default::default(),
+            cache: Default::default(),
+            next_ids: Default::default(),
+            file_lock: Default::default(),
+            publisher: Default::default(),
+            write_lock: Default::default(),
+            transaction_lock: Default::default(),
+            transactions: Default::default(),
+        })
+    }
+
+    /// With ID strategy.
+    pub fn with_id_strategy<IDStrategyT>(mut self, id_strategy: IDStrategyT) -> Self
+    where
+        IDStrategyT: 'static + IDStrategy,
+    {
+        self.id_strategy = Box::new(id_strategy);
+        self
+    }
+
+    /// Next ID.
+    ///
+    /// The counters are persisted in a file in the root directory. They are also tracked in memory
+    /// so that they would never go back, even if the file is edited or deleted.
+    pub fn get_next_id(&self, kind: Kind) -> Result<u64, StoreError> {
+        self.update_next_id(kind, |next_id| next_id + 1)
+    }
+
+    /// Next IDs.
+    pub fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        let mut next_ids = self.next_ids.lock()?.clone();
+        for (kind, next_id) in self.read_next_ids()? {
+            let next_id_ = next_ids.entry(kind).or_default();
+            *next_id_ = (*next_id_).max(next_id);
+        }
+        Ok(next_ids)
+    }
+
+    /// Advance next ID.
+    ///
+    /// Does nothing if the provided value is not higher.
+    pub fn advance_next_id(&self, kind: Kind, next_id: u64) -> Result<(), StoreError> {
+        self.update_next_id(kind, |next_id_| next_id_.max(next_id))?;
+        Ok(())
+    }
+
+    /// True if we are in a transaction.
+    pub fn in_transaction(&self) -> Result<bool, StoreError> {
+        Ok(!self.transactions.lock()?.is_empty())
+    }
+
+    /// Reload all cached entities that have been changed by others, and load any new ones.
+    ///
+    /// Publishes the changes to the cached entities. Note that these changes are also detected
+    /// (and published) whenever an entity is read.
+    pub fn reload(&self) -> Result<(), StoreError> {
+        let mut ids = HashSet::new();
+
+        for kind in KINDS {
+            ids.extend(self.scan(kind, &Default::default())?);
+        }
+
+        ids.extend(self.cache.pin().keys().cloned());
+
+        for id in ids {
+            self.read(&id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Publish a change to subscribers.
+    pub(crate) fn publish(&self, id: ID, before: Option<Entity>, after: Option<Entity>) -> Result<(), StoreError> {
+        match StoreEvent::new(id, before, after) {
+            Some(event) => self.publisher.publish(event),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the previous state of an entity in the current transaction.
+    ///
+    /// Does nothing if we are not in a transaction.
+    pub(crate) fn record(&self, id: ID, previous: Option<Entity>) -> Result<(), StoreError> {
+        if let Some(transaction) = self.transactions.lock()?.last_mut() {
+            transaction.push((id, previous));
+        }
+        Ok(())
+    }
+
+    /// Read an entity.
+    ///
+    /// If the file was changed by others since it was cached then the change is published.
+    pub(crate) fn read(&self, id: &ID) -> Result<Option<Entity>, StoreError> {
+        let path = self.entity_path(id)?;
+
+        let _file_lock = self.file_lock.lock()?;
+        let cached = self.cache.pin().get(id).cloned();
+
+        let stamp = match fs::metadata(&path) {
+            Ok(metadata) => FileStamp::new(&metadata)?,
+
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                if let Some(cached) = cached {
+                    self.cache.pin().remove(id);
+                    self.publish(id.clone(), Some(cached.entity), None)?;
+                }
+                return Ok(None);
+            }
+
+            Err(error) => return Err(error.into()),
+        };
+
+        if let Some(cached) = &cached
+            && cached.stamp == stamp
+        {
+            return Ok(Some(cached.entity.clone()));
+        }
+
+        let Some(variant) = self.read_variant(&path)? else {
+            return Ok(None);
+        };
+
+        let entity = Entity::from_variant(&variant)
+            .map_err(|error| StoreError::Backend(format!("decode {}: {}", path.display(), error)))?;
+        if entity.get_id() != id {
+            return Err(StoreError::Backend(format!("file {} has wrong ID: {}", path.display(), entity.get_id())));
+        }
+
+        self.cache.pin().insert(id.clone(), CachedEntity::new(stamp, entity.clone()));
+
+        // Loading an entity for the first time is not a change
+        if let Some(cached) = cached {
+            self.publish(id.clone(), Some(cached.entity), Some(entity.clone()))?;
+        }
+
+        Ok(Some(entity))
+    }
+
+    /// Write an entity.
+    pub(crate) fn write(&self, entity: Entity, variant: &Variant<WithoutAnnotations>) -> Result<(), StoreError> {
+        let id = entity.get_id().clone();
+        let path = self.entity_path(&id)?;
+
+        let _file_lock = self.file_lock.lock()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        self.write_variant(&path, variant)?;
+
+        let stamp = FileStamp::new(&fs::metadata(&path)?)?;
+        self.cache.pin().insert(id, CachedEntity::new(stamp, entity));
+
+        Ok(())
+    }
+
+    /// Delete an entity.
+    ///
+    /// Empty directories are left in place.
+    pub(crate) fn delete(&self, id: &ID) -> Result<(), StoreError> {
+        let _file_lock = self.file_lock.lock()?;
+        self.cache.pin().remove(id);
+        match fs::remove_file(self.entity_path(id)?) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Scan the query's directories for the IDs of a kind.
+    ///
+    /// Only the query's directories are considered, not its other criteria.
+    pub(crate) fn scan(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<ID>, StoreError> {
+        let mut ids = Vec::default();
+
+        match &query.directories {
+            Some(directories) => {
+                for directory in directories {
+                    self.scan_directory(kind, directory.clone(), query.recursive, &mut ids)?;
+                }
+
+                // Overlapping directories may select the same IDs more than once
+                let mut unique_ids = HashSet::new();
+                ids.retain(|id| unique_ids.insert(id.clone()));
+            }
+
+            None => self.scan_directory(kind, Default::default(), true, &mut ids)?,
+        }
+
+        Ok(ids)
+    }
+
+    fn scan_directory(
+        &self,
+        kind: Kind,
+        directory: Directory,
+        recursive: bool,
+        ids: &mut Vec<ID>,
+    ) -> Result<(), StoreError> {
+        let entries = match fs::read_dir(self.directory_path(&directory)?) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let suffix = format!(".{}.{}", kind_name(kind), self.extension());
+
+        for entry in entries {
+            let entry = entry?;
+
+            let Ok(file_name) = entry.file_name().into_string() else {
+                continue;
+            };
+
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if recursive {
+                    let mut subdirectory = directory.clone();
+                    subdirectory.add_last_segment(ByteString::from(file_name));
+                    self.scan_directory(kind, subdirectory, recursive, ids)?;
+                }
+            } else if file_type.is_file()
+                && let Some(id) = file_name.strip_suffix(&suffix)
+            {
+                ids.push(ID::new_for(kind, directory.clone(), id.into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_next_id<UpdateT>(&self, kind: Kind, update: UpdateT) -> Result<u64, StoreError>
+    where
+        UpdateT: FnOnce(u64) -> u64,
+    {
+        let mut next_ids = self.next_ids.lock()?;
+        let mut persisted_next_ids = self.read_next_ids_map()?;
+
+        let kind_name = kind_name(kind);
+        let persisted = match persisted_next_ids.into_get(kind_name) {
+            Some(next_id) => next_id_from_variant(next_id)?,
+            None => 0,
+        };
+
+        let next_id = update(persisted.max(next_ids.get(&kind).copied().unwrap_or(0)));
+        next_ids.insert(kind, next_id);
+
+        persisted_next_ids.into_insert(kind_name, next_id);
+        self.write_variant(&self.next_ids_path(), &persisted_next_ids.into())?;
+
+        Ok(next_id)
+    }
+
+    fn read_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        let persisted_next_ids = self.read_next_ids_map()?;
+        let mut next_ids = HashMap::default();
+        for kind in KINDS {
+            if let Some(next_id) = persisted_next_ids.into_get(kind_name(kind)) {
+                next_ids.insert(kind, next_id_from_variant(next_id)?);
+            }
+        }
+        Ok(next_ids)
+    }
+
+    fn read_next_ids_map(&self) -> Result<Map<WithoutAnnotations>, StoreError> {
+        match self.read_variant(&self.next_ids_path())? {
+            Some(Variant::Map(map)) => Ok(map),
+            Some(variant) => Err(StoreError::Backend(format!("malformed next IDs: {}", variant.type_name()))),
+            None => Ok(Map::default()),
+        }
+    }
+
+    fn next_ids_path(&self) -> PathBuf {
+        self.root.join(format!(".next_ids.{}", self.extension()))
+    }
+
+    fn entity_path(&self, id: &ID) -> Result<PathBuf, StoreError> {
+        let id_: &str = id.id.as_ref();
+        validate_segment(id_)?;
+        Ok(self.directory_path(&id.directory)?.join(format!("{}.{}.{}", id_, kind_name(id.kind), self.extension())))
+    }
+
+    fn directory_path(&self, directory: &Directory) -> Result<PathBuf, StoreError> {
+        let mut path = self.root.clone();
+        for segment in directory {
+            let segment: &str = segment.as_ref();
+            validate_segment(segment)?;
+            path.push(segment);
+        }
+        Ok(path)
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.format {
+            Format::JSON => "json",
+            Format::XJSON => "xjson",
+            Format::CBOR => "cbor",
+            Format::MessagePack => "msgpack",
+            _ => "yaml",
+        }
+    }
+
+    fn read_variant(&self, path: &Path) -> Result<Option<Variant<WithoutAnnotations>>, StoreError> {
+        let mut file = match fs::File::open(path) {
+            Ok(file) => io::BufReader::new(file),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        read_variant(&mut file, self.format)
+            .map(Some)
+            .map_err(|error| StoreError::Backend(format!("parse {}: {}", path.display(), error)))
+    }
+
+    // Writes to a temporary file and then renames it, so that readers never see a partial file.
+    fn write_variant(&self, path: &Path, variant: &Variant<WithoutAnnotations>) -> Result<(), StoreError> {
+        let file_name = path.file_name().and_then(|file_name| file_name.to_str()).unwrap_or_default();
+        let temporary_path = path.with_file_name(format!(".{}.tmp", file_name));
+
+        {
+            let mut file = io::BufWriter::new(fs::File::create(&temporary_path)?);
+
+            write_variant(variant, self.format, &mut file)
+                .map_err(|error| StoreError::Backend(format!("serialize {}: {}", path.display(), error)))?;
+            io::Write::flush(&mut file)?;
+        }
+
+        fs::rename(&temporary_path, path)?;
+        Ok(())
+    }
+}
+
+//
+// CachedEntity
+//
+
+/// Cached entity.
+#[derive(Clone, Debug)]
+pub struct CachedEntity {
+    /// File stamp.
+    pub stamp: FileStamp,
+
+    /// Entity.
+    pub entity: Entity,
+}
+
+impl CachedEntity {
+    /// Constructor.
+    pub fn new(stamp: FileStamp, entity: Entity) -> Self {
+        Self { stamp, entity }
+    }
+}
+
+//
+// FileStamp
+//
+
+/// Identifies a version of a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileStamp {
+    /// Modification time.
+    pub modified: SystemTime,
+
+    /// Size.
+    pub size: u64,
+}
+
+impl FileStamp {
+    /// Constructor.
+    pub fn new(metadata: &fs::Metadata) -> Result<Self, StoreError> {
+        Ok(Self { modified: metadata.modified()?, size: metadata.len() })
+    }
+}
+
+// Utils
+
+const KINDS: [Kind; 5] = [Kind::Class, Kind::VertexTemplate, Kind::EdgeTemplate, Kind::Vertex, Kind::Edge];
+
+fn kind_name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Class => "class",
+        Kind::VertexTemplate => "vertex_template",
+        Kind::EdgeTemplate => "edge_template",
+        Kind::Vertex => "vertex",
+        Kind::Edge => "edge",
+    }
+}
+
+fn next_id_from_variant(next_id: &Variant<WithoutAnnotations>) -> Result<u64, StoreError> {
+    match next_id {
+        Variant::Integer(next_id) => Ok(next_id.inner.max(0) as u64),
+        Variant::UnsignedInteger(next_id) => Ok(next_id.inner),
+        _ => Err(StoreError::Backend(format!("malformed next ID: {}", next_id.type_name()))),
+    }
+}
+
+fn validate_segment(segment: &str) -> Result<(), StoreError> {
+    if segment.is_empty() || segment.starts_with('.') || segment.contains(['/', '\\']) {
+        Err(StoreError::ID(format!("cannot be used as a file name: {:?}", segment)))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod backend;
+mod store;
+
+#[allow(unused_imports)]
+pub use {backend::*, store::*};
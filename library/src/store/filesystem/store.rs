@@ -0,0 +1,452 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, query::*, store::*, wrapper::*},
+    backend::*,
+};
+
+use {
+    compris::annotate::*,
+    std::{collections::*, sync::*},
+};
+
+//
+// FilesystemStore
+//
+
+/// Filesystem store.
+///
+/// All clones share the same backend.
+#[derive(Clone)]
+pub struct FilesystemStore {
+    /// Backend.
+    pub backend: Arc<FilesystemStoreBackend>,
+}
+
+impl FilesystemStore {
+    /// Constructor.
+    pub fn new(backend: Arc<FilesystemStoreBackend>) -> StoreWrapper<Self> {
+        StoreWrapper::new(Self { backend })
+    }
+
+    // Read an entity.
+    fn read<EntityT>(&self, id: &ID) -> Result<Option<EntityT>, StoreError>
+    where
+        EntityT: TryFrom<Entity>,
+    {
+        Ok(self.backend.read(id)?.and_then(|entity| entity.try_into().ok()))
+    }
+
+    // Write an entity.
+    fn write(&self, entity: Entity) -> Result<(), StoreError> {
+        let variant = entity.to_variant::<_, WithoutAnnotations>(false, self)?;
+        self.backend.write(entity, &variant)
+    }
+
+    // Insert an entity, recording the previous state and publishing the change.
+    fn insert_entity<EntityT>(&self, entity: EntityT) -> Result<(), StoreError>
+    where
+        EntityT: Into<Entity>,
+    {
+        self.insert_entity_if_revision(entity, None)?;
+        Ok(())
+    }
+
+    // Insert an entity if the revision matches (when provided), recording the previous state and
+    // publishing the change. Returns the new revision.
+    fn insert_entity_if_revision<EntityT>(
+        &self,
+        entity: EntityT,
+        expected_revision: Option<u64>,
+    ) -> Result<u64, StoreError>
+    where
+        EntityT: Into<Entity>,
+    {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let _write_lock = self.backend.write_lock.lock()?;
+
+        let mut entity: Entity = entity.into();
+        let id = entity.get_id().clone();
+        let previous = self.backend.read(&id)?;
+
+        let revision = previous.as_ref().map(|previous| previous.get_revision()).unwrap_or(0);
+        check_revision(&id, expected_revision, revision)?;
+        entity.set_revision(revision + 1);
+
+        let after = if self.backend.publisher.has_subscribers()? { Some(entity.clone()) } else { None };
+        self.write(entity)?;
+
+        if after.is_some() {
+            self.backend.publish(id.clone(), previous.clone(), after)?;
+        }
+
+        self.backend.record(id, previous)?;
+        Ok(revision + 1)
+    }
+
+    // Remove an entity, recording the previous state and publishing the change.
+    fn remove_entity<EntityT>(&self, id: &ID) -> Result<Option<EntityT>, StoreError>
+    where
+        EntityT: TryFrom<Entity>,
+    {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let _write_lock = self.backend.write_lock.lock()?;
+
+        let Some(previous) = self.backend.read(id)? else {
+            return Ok(None);
+        };
+
+        self.backend.delete(id)?;
+        self.backend.publish(id.clone(), Some(previous.clone()), None)?;
+        self.backend.record(id.clone(), Some(previous.clone()))?;
+
+        Ok(previous.try_into().ok())
+    }
+
+    // Update an entity (if it exists), recording the previous state and publishing the change.
+    fn update_entity<EntityT, UpdateT>(&self, id: &ID, update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: Into<Entity> + Revisioned + TryFrom<Entity>,
+        UpdateT: Fn(&mut EntityT),
+    {
+        self.update_entity_if(id, |entity: &mut EntityT| {
+            update(entity);
+            true
+        })
+    }
+
+    // Update an entity (if it exists) if the update function returns true, recording the previous
+    // state and publishing the change.
+    //
+    // The entity is written only if it has not changed since we read it. Otherwise we read it
+    // again and retry, so that concurrent changes would not be lost.
+    fn update_entity_if<EntityT, UpdateT>(&self, id: &ID, update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: Into<Entity> + Revisioned + TryFrom<Entity>,
+        UpdateT: Fn(&mut EntityT) -> bool,
+    {
+        while let Some(mut entity) = self.read::<EntityT>(id)? {
+            let revision = entity.get_revision();
+            if !update(&mut entity) {
+                break;
+            }
+
+            match self.insert_entity_if_revision(entity, Some(revision)) {
+                Err(StoreError::Conflict(_)) => {}
+                result => return result.map(|_| ()),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Update all entities of a kind for which the update function returns true, recording the
+    // previous states and publishing the changes.
+    fn update_entities<EntityT, UpdateT>(&self, kind: Kind, update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: Into<Entity> + Revisioned + TryFrom<Entity>,
+        UpdateT: Fn(&mut EntityT) -> bool,
+    {
+        for id in self.backend.scan(kind, &Default::default())? {
+            self.update_entity_if(&id, &update)?;
+        }
+        Ok(())
+    }
+
+    // Restore the previous state of an entity (without recording it), publishing the change.
+    fn restore(&self, id: ID, previous: Option<Entity>) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let _write_lock = self.backend.write_lock.lock()?;
+        let before = self.backend.read(&id)?;
+
+        match &previous {
+            Some(previous) => self.write(previous.clone())?,
+            None => self.backend.delete(&id)?,
+        }
+
+        self.backend.publish(id, before, previous)
+    }
+}
+
+impl Store for FilesystemStore {
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.backend.transaction_lock.begin()?;
+        self.backend.transactions.lock()?.push(Default::default());
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let mut transactions = self.backend.transactions.lock()?;
+        let transaction = transactions.pop().ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        // Merge into the enclosing transaction so that it can still be rolled back
+        if let Some(enclosing_transaction) = transactions.last_mut() {
+            enclosing_transaction.extend(transaction);
+        }
+
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let transaction = self
+            .backend
+            .transactions
+            .lock()?
+            .pop()
+            .ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        for (id, previous) in transaction.into_iter().rev() {
+            self.restore(id, previous)?;
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.backend.publisher.subscribe(filter)
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        let kind = id.kind;
+        self.backend.id_strategy.create_id(id, &mut || self.backend.get_next_id(kind))
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.backend.get_next_ids()
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        for (kind, next_id) in next_ids {
+            self.backend.advance_next_id(*kind, *next_id)?;
+        }
+        Ok(())
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.insert_entity(class)
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        let revision = class.get_revision();
+        self.insert_entity_if_revision(class, Some(revision))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.transaction(|| {
+            let Some(class) = self.remove_entity::<Class>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entities(Kind::Class, |class: &mut Class| class.remove_class_id(id))?;
+            self.update_entities(Kind::VertexTemplate, |vertex_template: &mut VertexTemplate| {
+                vertex_template.template.remove_class_id(id)
+            })?;
+            self.update_entities(Kind::EdgeTemplate, |edge_template: &mut EdgeTemplate| {
+                edge_template.template.remove_class_id(id)
+            })?;
+            self.update_entities(Kind::Vertex, |vertex: &mut Vertex| vertex.instance.remove_class_id(id))?;
+            self.update_entities(Kind::Edge, |edge: &mut Edge| edge.instance.remove_class_id(id))?;
+
+            Ok(Some(class))
+        })
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.insert_entity(vertex_template)
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        let revision = vertex_template.get_revision();
+        self.insert_entity_if_revision(vertex_template, Some(revision))
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex_template) = self.remove_entity::<VertexTemplate>(id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_template_id in &vertex_template.contained_vertex_template_ids {
+                self.remove_vertex_template(contained_vertex_template_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_template_ids
+            let mut outgoing_edge_template_ids = Vec::default();
+            for edge_template_id in self.backend.scan(Kind::EdgeTemplate, &Default::default())? {
+                if let Some(edge_template) = self.read::<EdgeTemplate>(&edge_template_id)?
+                    && (edge_template.containing_source_vertex_template_id == *id)
+                {
+                    outgoing_edge_template_ids.push(edge_template_id);
+                }
+            }
+            outgoing_edge_template_ids.extend(vertex_template.outgoing_edge_template_ids.iter().cloned());
+
+            for outgoing_edge_template_id in &outgoing_edge_template_ids {
+                self.remove_edge_template(outgoing_edge_template_id)?;
+            }
+
+            if let Some(containing_vertex_template_id) = &vertex_template.containing_vertex_template_id {
+                self.update_entity(
+                    containing_vertex_template_id,
+                    |containing_vertex_template: &mut VertexTemplate| {
+                        containing_vertex_template
+                            .contained_vertex_template_ids
+                            .retain(|contained_vertex_template_id| contained_vertex_template_id != id);
+                    },
+                )?;
+            }
+
+            self.update_entities(Kind::Vertex, |vertex: &mut Vertex| vertex.instance.remove_origin_template_id(id))?;
+
+            Ok(Some(vertex_template))
+        })
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.insert_entity(edge_template)
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        let revision = edge_template.get_revision();
+        self.insert_entity_if_revision(edge_template, Some(revision))
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(edge_template) = self.remove_entity::<EdgeTemplate>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entity(
+                &edge_template.containing_source_vertex_template_id,
+                |vertex_template: &mut VertexTemplate| {
+                    vertex_template
+                        .outgoing_edge_template_ids
+                        .retain(|outgoing_edge_template_id| outgoing_edge_template_id != id);
+                },
+            )?;
+
+            self.update_entities(Kind::Edge, |edge: &mut Edge| edge.instance.remove_origin_template_id(id))?;
+
+            Ok(Some(edge_template))
+        })
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.read(id)
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        let mut entities = Vec::default();
+        for id in self.backend.scan(kind, query)? {
+            if let Some(entity) = self.backend.read(&id)?
+                && query.matches(&entity)
+            {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        let query = StoreQuery::default().with_directories(directories);
+        let mut vertexes = Vec::default();
+        for id in self.backend.scan(Kind::Vertex, &query)? {
+            if let Some(vertex) = self.read(&id)? {
+                vertexes.push(vertex);
+            }
+        }
+        Ok(vertexes)
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.insert_entity(vertex)
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        let revision = vertex.get_revision();
+        self.insert_entity_if_revision(vertex, Some(revision))
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex) = self.remove_entity::<Vertex>(id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_id in &vertex.contained_vertex_ids {
+                self.remove_vertex(contained_vertex_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_ids and incoming_edge_ids
+            let mut edge_ids = Vec::default();
+            for edge_id in self.backend.scan(Kind::Edge, &Default::default())? {
+                if let Some(edge) = self.read::<Edge>(&edge_id)?
+                    && ((edge.source_vertex_id == *id) || (edge.target_vertex_id == *id))
+                {
+                    edge_ids.push(edge_id);
+                }
+            }
+            edge_ids.extend(vertex.outgoing_edge_ids.iter().chain(&vertex.incoming_edge_ids).cloned());
+
+            for edge_id in &edge_ids {
+                self.remove_edge(edge_id)?;
+            }
+
+            if let Some(containing_vertex_id) = &vertex.containing_vertex_id {
+                self.update_entity(containing_vertex_id, |containing_vertex: &mut Vertex| {
+                    containing_vertex.contained_vertex_ids.retain(|contained_vertex_id| contained_vertex_id != id);
+                })?;
+            }
+
+            Ok(Some(vertex))
+        })
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.insert_entity(edge)
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        let revision = edge.get_revision();
+        self.insert_entity_if_revision(edge, Some(revision))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.transaction(|| {
+            let Some(edge) = self.remove_entity::<Edge>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entity(&edge.source_vertex_id, |source_vertex: &mut Vertex| {
+                source_vertex.outgoing_edge_ids.retain(|outgoing_edge_id| outgoing_edge_id != id);
+            })?;
+
+            self.update_entity(&edge.target_vertex_id, |target_vertex: &mut Vertex| {
+                target_vertex.incoming_edge_ids.retain(|incoming_edge_id| incoming_edge_id != id);
+            })?;
+
+            Ok(Some(edge))
+        })
+    }
+}
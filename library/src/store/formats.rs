@@ -0,0 +1,51 @@
+use super::{cbor::*, message_pack::*};
+
+use {
+    compris::{annotate::*, normal::*, parse::*, ser::*, *},
+    std::io,
+};
+
+// Read a variant in a format.
+//
+// Integers are parsed from text formats, too.
+pub(crate) fn read_variant<ReadT>(reader: &mut ReadT, format: Format) -> Result<Variant<WithoutAnnotations>, String>
+where
+    ReadT: io::Read,
+{
+    match format {
+        Format::MessagePack => {
+            let mut bytes = Vec::default();
+            reader.read_to_end(&mut bytes).map_err(|error| error.to_string())?;
+            decode_message_pack(&bytes)
+        }
+
+        format => Parser::new(format).with_try_integers(true).parse_reader(reader).map_err(|error| error.to_string()),
+    }
+}
+
+// Write a variant in a format.
+//
+// Text formats are written pretty.
+pub(crate) fn write_variant<WriteT>(
+    variant: &Variant<WithoutAnnotations>,
+    format: Format,
+    writer: &mut WriteT,
+) -> Result<(), String>
+where
+    WriteT: io::Write,
+{
+    match format {
+        Format::CBOR => {
+            let mut bytes = Vec::default();
+            encode_cbor(variant, &mut bytes);
+            writer.write_all(&bytes).map_err(|error| error.to_string())
+        }
+
+        format => {
+            // Pretty output would append a newline to binary formats
+            let serializer = Serializer::new(format).with_pretty(!format.is_binary());
+            let mode = SerializationMode::for_format(&format).unwrap_or_default();
+            serializer.write_modal(variant, &mode, writer).map_err(|error| error.to_string())
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use super::{super::data::*, errors::*, query::*, store::*};
+
+use {
+    kutil::{cli::depict::*, std::iter::*},
+    std::{collections::*, io},
+};
+
+const GARBAGE_COLLECTION_KINDS: [Kind; 5] =
+    [Kind::Edge, Kind::Vertex, Kind::EdgeTemplate, Kind::VertexTemplate, Kind::Class];
+
+//
+// GarbageCollectionRoots
+//
+
+/// Roots for [StoreGarbageCollectionUtilities::collect_garbage].
+#[derive(Clone, Debug, Default)]
+pub struct GarbageCollectionRoots {
+    /// Directories. All the entities in them are roots.
+    pub directories: Vec<Directory>,
+
+    /// Whether to also include subdirectories of the directories.
+    pub recursive: bool,
+
+    /// Vertex template and edge template IDs. The templates and all the vertexes and edges that
+    /// originated from them are roots.
+    pub template_ids: Vec<ID>,
+}
+
+impl GarbageCollectionRoots {
+    /// True if there are no roots.
+    pub fn is_empty(&self) -> bool {
+        self.directories.is_empty() && self.template_ids.is_empty()
+    }
+
+    /// With directory.
+    pub fn with_directory(mut self, directory: Directory) -> Self {
+        self.directories.push(directory);
+        self
+    }
+
+    /// With recursive.
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// With template ID.
+    pub fn with_template_id(mut self, template_id: ID) -> Self {
+        self.template_ids.push(template_id);
+        self
+    }
+}
+
+//
+// GarbageCollectionReport
+//
+
+/// Garbage collection report.
+#[derive(Clone, Debug, Default)]
+pub struct GarbageCollectionReport {
+    /// Garbage entity IDs, ordered by kind (edges first, classes last) and then by ID.
+    pub garbage: Vec<ID>,
+
+    /// Whether the garbage was removed.
+    pub removed: bool,
+}
+
+impl GarbageCollectionReport {
+    /// True if there is no garbage.
+    pub fn is_empty(&self) -> bool {
+        self.garbage.is_empty()
+    }
+}
+
+impl Depict for GarbageCollectionReport {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        for (id, first) in IterateWithFirst::new(&self.garbage) {
+            context.separate_or_indent(writer, first)?;
+            id.kind.depict(writer, context)?;
+            write!(writer, " ")?;
+            id.depict(writer, context)?;
+            if self.removed {
+                write!(writer, " {}", context.theme.meta("(removed)"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//
+// StoreGarbageCollectionUtilities
+//
+
+/// Garbage collection utilities for [Store].
+pub trait StoreGarbageCollectionUtilities {
+    /// Mark-and-sweep garbage collection.
+    ///
+    /// Entities are live if they are roots or are reachable from live entities. Entities reach
+    /// their classes (and those classes' parent classes) and origin templates. Vertexes and vertex
+    /// templates reach their containers and contained entities. Vertexes reach the edges going
+    /// out of them, and edges reach both their vertexes. Vertex templates reach their outgoing
+    /// edge templates, and edge templates their containing source vertex templates. References to
+    /// missing entities are ignored, except that edges with a missing vertex are always garbage.
+    /// All other entities are garbage.
+    ///
+    /// Thus garbage includes vertexes without a container whose origin template is gone, edges
+    /// with a missing vertex, and classes that are not referenced by live entities, as long as they
+    /// are not roots.
+    ///
+    /// Fails with [StoreError::ID] if there are no roots, because the entire store would be
+    /// garbage.
+    ///
+    /// Marking, sweeping, and (unless it's a dry run) removing the garbage are done in a single
+    /// transaction, so that entities added concurrently are not mistaken for garbage.
+    fn collect_garbage(
+        &self,
+        roots: &GarbageCollectionRoots,
+        dry_run: bool,
+    ) -> Result<GarbageCollectionReport, StoreError>;
+}
+
+impl<StoreT> StoreGarbageCollectionUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn collect_garbage(
+        &self,
+        roots: &GarbageCollectionRoots,
+        dry_run: bool,
+    ) -> Result<GarbageCollectionReport, StoreError> {
+        if roots.is_empty() {
+            return Err(StoreError::ID("no garbage collection roots".into()));
+        }
+
+        self.transaction(|| {
+            let mut collector = GarbageCollector {
+                store: self,
+                live: Default::default(),
+                broken: Default::default(),
+                pending: Default::default(),
+            };
+
+            collector.mark_roots(roots)?;
+            collector.mark()?;
+
+            let mut report = GarbageCollectionReport { garbage: collector.sweep()?, removed: false };
+
+            if !dry_run && !report.is_empty() {
+                for id in &report.garbage {
+                    // Removing may cascade, so some might already be gone
+                    match id.kind {
+                        Kind::Class => self.remove_class(id)?.map(|_| ()),
+                        Kind::VertexTemplate => self.remove_vertex_template(id)?.map(|_| ()),
+                        Kind::EdgeTemplate => self.remove_edge_template(id)?.map(|_| ()),
+                        Kind::Vertex => self.remove_vertex(id)?.map(|_| ()),
+                        Kind::Edge => self.remove_edge(id)?.map(|_| ()),
+                    };
+                }
+                report.removed = true;
+            }
+
+            Ok(report)
+        })
+    }
+}
+
+//
+// GarbageCollector
+//
+
+struct GarbageCollector<'own, StoreT> {
+    store: &'own StoreT,
+    live: HashSet<ID>,
+    broken: HashSet<ID>,
+    pending: Vec<ID>,
+}
+
+impl<'own, StoreT> GarbageCollector<'own, StoreT>
+where
+    StoreT: Store,
+{
+    fn mark_roots(&mut self, roots: &GarbageCollectionRoots) -> Result<(), StoreError> {
+        if !roots.directories.is_empty() {
+            let query =
+                StoreQuery::default().with_directories(Some(roots.directories.clone())).with_recursive(roots.recursive);
+
+            for kind in GARBAGE_COLLECTION_KINDS {
+                for entity in self.store.get_entities(kind, &query)? {
+                    self.reach(entity.get_id());
+                }
+            }
+        }
+
+        for template_id in &roots.template_ids {
+            self.reach(template_id);
+
+            let query = StoreQuery::default().with_origin_template_id(template_id.clone());
+            for kind in [Kind::Vertex, Kind::Edge] {
+                for entity in self.store.get_entities(kind, &query)? {
+                    self.reach(entity.get_id());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mark(&mut self) -> Result<(), StoreError> {
+        while let Some(id) = self.pending.pop() {
+            match id.kind {
+                Kind::Class => {
+                    if let Some(class) = self.store.get_class(&id)? {
+                        self.reach_all(&class.parent_class_ids);
+                    }
+                }
+
+                Kind::VertexTemplate => {
+                    if let Some(vertex_template) = self.store.get_vertex_template(&id)? {
+                        self.reach_all(&vertex_template.template.class_ids);
+                        if let Some(containing_vertex_template_id) = &vertex_template.containing_vertex_template_id {
+                            self.reach(containing_vertex_template_id);
+                        }
+                        self.reach_all(&vertex_template.contained_vertex_template_ids);
+                        self.reach_all(&vertex_template.outgoing_edge_template_ids);
+                    }
+                }
+
+                Kind::EdgeTemplate => {
+                    if let Some(edge_template) = self.store.get_edge_template(&id)? {
+                        self.reach_all(&edge_template.template.class_ids);
+                        self.reach(&edge_template.containing_source_vertex_template_id);
+                    }
+                }
+
+                Kind::Vertex => {
+                    if let Some(vertex) = self.store.get_vertex(&id)? {
+                        self.reach_instance(&vertex.instance);
+                        if let Some(containing_vertex_id) = &vertex.containing_vertex_id {
+                            self.reach(containing_vertex_id);
+                        }
+                        self.reach_all(&vertex.contained_vertex_ids);
+                        self.reach_all(&vertex.outgoing_edge_ids);
+                    }
+                }
+
+                Kind::Edge => {
+                    if let Some(edge) = self.store.get_edge(&id)? {
+                        // Edges with a missing vertex are garbage even if reached
+                        if self.store.get_vertex(&edge.source_vertex_id)?.is_none()
+                            || self.store.get_vertex(&edge.target_vertex_id)?.is_none()
+                        {
+                            self.broken.insert(id);
+                            continue;
+                        }
+
+                        self.reach_instance(&edge.instance);
+                        self.reach(&edge.source_vertex_id);
+                        self.reach(&edge.target_vertex_id);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sweep(&self) -> Result<Vec<ID>, StoreError> {
+        let mut garbage = Vec::default();
+
+        for kind in GARBAGE_COLLECTION_KINDS {
+            let mut ids: Vec<_> = self
+                .store
+                .get_entities(kind, &Default::default())?
+                .iter()
+                .map(|entity| entity.get_id().clone())
+                .filter(|id| !self.live.contains(id) || self.broken.contains(id))
+                .collect();
+            ids.sort_by_cached_key(|id| id.to_string());
+            garbage.extend(ids);
+        }
+
+        Ok(garbage)
+    }
+
+    fn reach_instance(&mut self, instance: &Instance) {
+        self.reach_all(&instance.class_ids);
+        if let Some(origin_template_id) = &instance.origin_template_id {
+            self.reach(origin_template_id);
+        }
+    }
+
+    fn reach_all(&mut self, ids: &[ID]) {
+        for id in ids {
+            self.reach(id);
+        }
+    }
+
+    fn reach(&mut self, id: &ID) {
+        if self.live.insert(id.clone()) {
+            self.pending.push(id.clone());
+        }
+    }
+}
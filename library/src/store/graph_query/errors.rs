@@ -0,0 +1,38 @@
+use super::super::errors::*;
+
+use {kutil::cli::depict::*, std::io, thiserror::*};
+
+//
+// GraphQueryError
+//
+
+/// Graph query error.
+#[derive(Debug, Error)]
+pub enum GraphQueryError {
+    /// Syntax.
+    #[error("syntax: {0}")]
+    Syntax(String),
+
+    /// Store.
+    #[error("store: {0}")]
+    Store(#[from] StoreError),
+}
+
+impl GraphQueryError {
+    /// Syntax error at a position in the source.
+    pub fn syntax_at(position: usize, message: &str) -> Self {
+        Self::Syntax(format!("at {}: {}", position, message))
+    }
+}
+
+impl Depict for GraphQueryError {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        match self {
+            Self::Syntax(syntax) => write!(writer, "syntax: {}", context.theme.error(syntax)),
+            Self::Store(store) => store.depict(writer, context),
+        }
+    }
+}
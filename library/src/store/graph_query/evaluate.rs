@@ -0,0 +1,467 @@
+use super::{
+    super::{super::data::*, errors::*, query::*, store::*},
+    errors::*,
+    query::*,
+    results::*,
+};
+
+use {
+    compris::{annotate::*, normal::*},
+    std::{cell::*, cmp::*, collections::*},
+};
+
+//
+// StoreGraphQueryUtilities
+//
+
+/// Graph query utilities for [Store].
+pub trait StoreGraphQueryUtilities {
+    /// Find the matches of a [GraphQuery].
+    fn query_graph(&self, query: &GraphQuery) -> Result<Vec<GraphQueryMatch>, GraphQueryError>;
+
+    /// Find the IDs bound to the variable of the first return of a [GraphQuery].
+    ///
+    /// Each ID appears once, in the order of its first match.
+    fn query_graph_ids(&self, query: &GraphQuery) -> Result<Vec<ID>, GraphQueryError>;
+
+    /// Find the returns of a [GraphQuery] as rows.
+    fn query_graph_rows(&self, query: &GraphQuery) -> Result<GraphQueryRows, GraphQueryError>;
+}
+
+impl<StoreT> StoreGraphQueryUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn query_graph(&self, query: &GraphQuery) -> Result<Vec<GraphQueryMatch>, GraphQueryError> {
+        if query.limit == Some(0) {
+            return Ok(Default::default());
+        }
+
+        let mut evaluator =
+            GraphQueryEvaluator { query, store: self, matches: Default::default(), incoming_edges: Default::default() };
+        evaluator.match_pattern(0, &mut Default::default())?;
+        Ok(evaluator.matches)
+    }
+
+    fn query_graph_ids(&self, query: &GraphQuery) -> Result<Vec<ID>, GraphQueryError> {
+        let mut ids = Vec::default();
+
+        if let Some(return_) = query.returns.first() {
+            let mut unique = HashSet::new();
+            for match_ in self.query_graph(query)? {
+                if let Some(id) = match_.get_id(&return_.accessor.variable)
+                    && unique.insert(id.clone())
+                {
+                    ids.push(id.clone());
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    fn query_graph_rows(&self, query: &GraphQuery) -> Result<GraphQueryRows, GraphQueryError> {
+        let columns = query.returns.iter().map(|return_| return_.column()).collect();
+
+        let rows = self
+            .query_graph(query)?
+            .iter()
+            .map(|match_| query.returns.iter().map(|return_| match_.get(&return_.accessor)).collect())
+            .collect();
+
+        Ok(GraphQueryRows { columns, rows })
+    }
+}
+
+//
+// GraphQueryEvaluator
+//
+
+// Backtracking matcher: binds the nodes of each pattern in order, then the next pattern.
+struct GraphQueryEvaluator<'own, StoreT> {
+    query: &'own GraphQuery,
+    store: &'own StoreT,
+    matches: Vec<GraphQueryMatch>,
+
+    // Edges by target vertex ID, built on first use
+    incoming_edges: OnceCell<HashMap<ID, Vec<Edge>>>,
+}
+
+impl<'own, StoreT> GraphQueryEvaluator<'own, StoreT>
+where
+    StoreT: Store,
+{
+    // The match functions return false when the limit has been reached.
+
+    fn match_pattern(&mut self, pattern_index: usize, bindings: &mut GraphQueryMatch) -> Result<bool, StoreError> {
+        let query = self.query;
+
+        let Some(pattern) = query.patterns.get(pattern_index) else {
+            if let Some(condition) = &query.condition
+                && !is_true(&evaluate(condition, bindings))
+            {
+                return Ok(true);
+            }
+
+            if query.limit.is_some_and(|limit| self.matches.len() >= limit) {
+                return Ok(false);
+            }
+
+            self.matches.push(bindings.clone());
+            return Ok(query.limit.is_none_or(|limit| self.matches.len() < limit));
+        };
+
+        for entity in self.start(&pattern.start, bindings)? {
+            if !self.match_node(pattern_index, 0, &pattern.start, entity, bindings)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn match_node(
+        &mut self,
+        pattern_index: usize,
+        step_index: usize,
+        node: &GraphNode,
+        entity: Entity,
+        bindings: &mut GraphQueryMatch,
+    ) -> Result<bool, StoreError> {
+        if !node_matches(node, &entity) {
+            return Ok(true);
+        }
+
+        let Some(variable) = &node.variable else {
+            return self.match_step(pattern_index, step_index, &entity, bindings);
+        };
+
+        if let Some(bound) = bindings.bindings.get(variable) {
+            return if bound.get_id() == entity.get_id() {
+                self.match_step(pattern_index, step_index, &entity, bindings)
+            } else {
+                Ok(true)
+            };
+        }
+
+        bindings.bindings.insert(variable.clone(), entity.clone());
+        let result = self.match_step(pattern_index, step_index, &entity, bindings);
+        bindings.bindings.remove(variable);
+        result
+    }
+
+    fn match_step(
+        &mut self,
+        pattern_index: usize,
+        step_index: usize,
+        entity: &Entity,
+        bindings: &mut GraphQueryMatch,
+    ) -> Result<bool, StoreError> {
+        let query = self.query;
+
+        let Some((relationship, node)) = query.patterns[pattern_index].steps.get(step_index) else {
+            return self.match_pattern(pattern_index + 1, bindings);
+        };
+
+        for (edge, neighbor) in self.traverse(entity, relationship)? {
+            let variable = match (&relationship.variable, edge) {
+                (Some(variable), Some(edge)) => {
+                    bindings.bindings.insert(variable.clone(), edge);
+                    Some(variable)
+                }
+
+                _ => None,
+            };
+
+            let result = self.match_node(pattern_index, step_index + 1, node, neighbor, bindings);
+
+            if let Some(variable) = variable {
+                bindings.bindings.remove(variable);
+            }
+
+            if !result? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Candidates for the start node of a pattern.
+    fn start(&self, node: &GraphNode, bindings: &GraphQueryMatch) -> Result<Vec<Entity>, StoreError> {
+        if let Some(variable) = &node.variable
+            && let Some(bound) = bindings.bindings.get(variable)
+        {
+            return Ok(vec![bound.clone()]);
+        }
+
+        let kind = node.kind.unwrap_or(Kind::Vertex);
+
+        for (field, value) in &node.fields {
+            if (field == &GraphField::ID)
+                && let Variant::Text(id) = value
+            {
+                return Ok(self.get(&ID::parse(kind, id.as_ref()))?.into_iter().collect());
+            }
+        }
+
+        self.store.get_entities(kind, &store_query(node, kind))
+    }
+
+    // Neighbors via a relationship, with the edge if there was one.
+    fn traverse(
+        &self,
+        entity: &Entity,
+        relationship: &GraphRelationship,
+    ) -> Result<Vec<(Option<Entity>, Entity)>, StoreError> {
+        if !relationship.transitive {
+            return self.neighbors(entity, relationship);
+        }
+
+        let mut neighbors = Vec::default();
+        let mut visited = HashSet::new();
+        let mut frontier = vec![entity.clone()];
+
+        while let Some(entity) = frontier.pop() {
+            for (_, neighbor) in self.neighbors(&entity, relationship)? {
+                if visited.insert(neighbor.get_id().clone()) {
+                    frontier.push(neighbor.clone());
+                    neighbors.push((None, neighbor));
+                }
+            }
+        }
+
+        Ok(neighbors)
+    }
+
+    fn neighbors(
+        &self,
+        entity: &Entity,
+        relationship: &GraphRelationship,
+    ) -> Result<Vec<(Option<Entity>, Entity)>, StoreError> {
+        let outgoing = relationship.direction != GraphDirection::Incoming;
+        let incoming = relationship.direction != GraphDirection::Outgoing;
+
+        let mut neighbors = Vec::default();
+
+        match relationship.relationship {
+            GraphRelationshipKind::Edge => {
+                let Entity::Vertex(vertex) = entity else {
+                    return Ok(neighbors);
+                };
+
+                let mut edges = Vec::default();
+                if outgoing {
+                    for edge_id in &vertex.outgoing_edge_ids {
+                        if let Some(edge) = self.store.get_edge(edge_id)? {
+                            edges.push((edge, true));
+                        }
+                    }
+                }
+                if incoming {
+                    // Vertexes do not keep track of their incoming edges, so we use the index
+                    if let Some(incoming_edges) = self.incoming_edges()?.get(&vertex.instance.id) {
+                        edges.extend(incoming_edges.iter().map(|edge| (edge.clone(), false)));
+                    }
+                }
+
+                for (edge, outgoing) in edges {
+                    let edge = Entity::Edge(edge);
+                    if !fields_match(&relationship.fields, &edge) {
+                        continue;
+                    }
+
+                    let Entity::Edge(edge_) = &edge else {
+                        continue;
+                    };
+
+                    let vertex_id = if outgoing { &edge_.target_vertex_id } else { &edge_.source_vertex_id };
+                    if let Some(vertex) = self.store.get_vertex(vertex_id)? {
+                        neighbors.push((Some(edge), vertex.into()));
+                    }
+                }
+            }
+
+            GraphRelationshipKind::Contains => {
+                let mut ids = Vec::default();
+
+                match entity {
+                    Entity::Vertex(vertex) => {
+                        if outgoing {
+                            ids.extend(vertex.contained_vertex_ids.iter());
+                        }
+                        if incoming {
+                            ids.extend(vertex.containing_vertex_id.iter());
+                        }
+                    }
+
+                    Entity::VertexTemplate(vertex_template) => {
+                        if outgoing {
+                            ids.extend(vertex_template.contained_vertex_template_ids.iter());
+                        }
+                        if incoming {
+                            ids.extend(vertex_template.containing_vertex_template_id.iter());
+                        }
+                    }
+
+                    _ => {}
+                }
+
+                for id in ids {
+                    if let Some(neighbor) = self.get(id)? {
+                        neighbors.push((None, neighbor));
+                    }
+                }
+            }
+        }
+
+        Ok(neighbors)
+    }
+
+    // Edges by target vertex ID.
+    fn incoming_edges(&self) -> Result<&HashMap<ID, Vec<Edge>>, StoreError> {
+        if let Some(incoming_edges) = self.incoming_edges.get() {
+            return Ok(incoming_edges);
+        }
+
+        let mut incoming_edges: HashMap<_, Vec<_>> = HashMap::default();
+        for edge in self.store.get_edges(&Default::default())? {
+            incoming_edges.entry(edge.target_vertex_id.clone()).or_default().push(edge);
+        }
+
+        Ok(self.incoming_edges.get_or_init(|| incoming_edges))
+    }
+
+    fn get(&self, id: &ID) -> Result<Option<Entity>, StoreError> {
+        Ok(match id.kind {
+            Kind::Class => self.store.get_class(id)?.map(|class| class.into()),
+            Kind::VertexTemplate => self.store.get_vertex_template(id)?.map(|vertex_template| vertex_template.into()),
+            Kind::EdgeTemplate => self.store.get_edge_template(id)?.map(|edge_template| edge_template.into()),
+            Kind::Vertex => self.store.get_vertex(id)?.map(|vertex| vertex.into()),
+            Kind::Edge => self.store.get_edge(id)?.map(|edge| edge.into()),
+        })
+    }
+}
+
+// Matching
+
+// The store query for the fields that it supports, so that the store can do the filtering.
+fn store_query(node: &GraphNode, kind: Kind) -> StoreQuery {
+    let mut query = StoreQuery::default();
+
+    for (field, value) in &node.fields {
+        match (field, value) {
+            (GraphField::Directory, Variant::Text(directory)) => {
+                query = query.with_directory(ID::parse_directory(directory.as_ref()));
+            }
+
+            (GraphField::Class, Variant::Text(class_id)) => {
+                query = query.with_class_id(ID::parse(Kind::Class, class_id.as_ref()));
+            }
+
+            (GraphField::Class, Variant::List(class_ids)) => {
+                for class_id in class_ids {
+                    if let Variant::Text(class_id) = class_id {
+                        query = query.with_class_id(ID::parse(Kind::Class, class_id.as_ref()));
+                    }
+                }
+            }
+
+            (GraphField::Origin, Variant::Text(origin_template_id)) if kind == Kind::Vertex => {
+                query = query.with_origin_template_id(ID::parse(Kind::VertexTemplate, origin_template_id.as_ref()));
+            }
+
+            (GraphField::Metadata(Some(key)), value) => {
+                query = query.with_metadata(key.clone(), value.clone());
+            }
+
+            _ => {}
+        }
+    }
+
+    query
+}
+
+fn node_matches(node: &GraphNode, entity: &Entity) -> bool {
+    let kind = entity.get_id().kind;
+    match node.kind {
+        Some(kind_) if kind_ != kind => return false,
+        None if !matches!(kind, Kind::Vertex | Kind::VertexTemplate) => return false,
+        _ => {}
+    }
+
+    fields_match(&node.fields, entity)
+}
+
+fn fields_match(fields: &[(GraphField, Variant<WithoutAnnotations>)], entity: &Entity) -> bool {
+    fields.iter().all(|(field, value)| {
+        let actual = get_field(entity, field);
+        match (field, value) {
+            (GraphField::Class, Variant::List(class_ids)) => {
+                class_ids.inner.iter().all(|class_id| contains(&actual, class_id))
+            }
+            (GraphField::Class, class_id) => contains(&actual, class_id),
+            _ => equals(&actual, value),
+        }
+    })
+}
+
+// Expressions
+
+fn evaluate(expression: &GraphExpression, bindings: &GraphQueryMatch) -> Variant<WithoutAnnotations> {
+    match expression {
+        GraphExpression::Literal(literal) => literal.clone(),
+        GraphExpression::Accessor(accessor) => bindings.get(accessor),
+
+        GraphExpression::Compare(left, operator, right) => {
+            let left = evaluate(left, bindings);
+            let right = evaluate(right, bindings);
+            match operator {
+                GraphOperator::Equal => equals(&left, &right),
+                GraphOperator::NotEqual => !equals(&left, &right),
+                GraphOperator::Less => compare_variants(&left, &right) == Some(Ordering::Less),
+                GraphOperator::LessOrEqual => {
+                    matches!(compare_variants(&left, &right), Some(Ordering::Less | Ordering::Equal))
+                }
+                GraphOperator::Greater => compare_variants(&left, &right) == Some(Ordering::Greater),
+                GraphOperator::GreaterOrEqual => {
+                    matches!(compare_variants(&left, &right), Some(Ordering::Greater | Ordering::Equal))
+                }
+                GraphOperator::In => contains(&right, &left),
+                GraphOperator::Contains => contains(&left, &right),
+            }
+            .into()
+        }
+
+        GraphExpression::IsNull(expression, negated) => {
+            (matches!(evaluate(expression, bindings), Variant::Null(_) | Variant::Undefined) != *negated).into()
+        }
+
+        GraphExpression::Not(expression) => (!is_true(&evaluate(expression, bindings))).into(),
+
+        GraphExpression::And(left, right) => {
+            (is_true(&evaluate(left, bindings)) && is_true(&evaluate(right, bindings))).into()
+        }
+
+        GraphExpression::Or(left, right) => {
+            (is_true(&evaluate(left, bindings)) || is_true(&evaluate(right, bindings))).into()
+        }
+    }
+}
+
+fn is_true(value: &Variant<WithoutAnnotations>) -> bool {
+    matches!(value, Variant::Boolean(boolean) if boolean.inner)
+}
+
+fn equals(left: &Variant<WithoutAnnotations>, right: &Variant<WithoutAnnotations>) -> bool {
+    compare_variants(left, right) == Some(Ordering::Equal)
+}
+
+// A list item or a substring.
+fn contains(container: &Variant<WithoutAnnotations>, item: &Variant<WithoutAnnotations>) -> bool {
+    match (container, item) {
+        (Variant::List(list), _) => list.inner.iter().any(|item_| equals(item_, item)),
+        (Variant::Text(text), Variant::Text(substring)) => text.inner.contains(substring.inner.as_ref() as &str),
+        _ => false,
+    }
+}
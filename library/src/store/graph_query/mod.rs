@@ -0,0 +1,8 @@
+mod errors;
+mod evaluate;
+mod parser;
+mod query;
+mod results;
+
+#[allow(unused_imports)]
+pub use {errors::*, evaluate::*, query::*, results::*};
@@ -0,0 +1,592 @@
+use super::{super::super::data::*, errors::*, query::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::std::immutable::*,
+};
+
+//
+// Token
+//
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(ByteString),
+    Text(ByteString),
+    UnsignedInteger(u64),
+    Float(f64),
+    LeftParenthesis,
+    RightParenthesis,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Colon,
+    Comma,
+    Dot,
+    Star,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    End,
+}
+
+//
+// Parser
+//
+
+// Recursive-descent parser. Keywords are identifiers, matched case-insensitively.
+pub(crate) struct Parser {
+    // Tokens with their position in the source
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(source: &str) -> Result<Self, GraphQueryError> {
+        Ok(Self { tokens: tokenize(source)?, index: 0 })
+    }
+
+    pub(crate) fn parse_query(&mut self) -> Result<GraphQuery, GraphQueryError> {
+        self.expect_keyword("MATCH")?;
+
+        let mut patterns = vec![self.parse_pattern()?];
+        while self.accept(&Token::Comma) {
+            patterns.push(self.parse_pattern()?);
+        }
+
+        let condition = if self.accept_keyword("WHERE") { Some(self.parse_or()?) } else { None };
+
+        self.expect_keyword("RETURN")?;
+        let mut returns = vec![self.parse_return()?];
+        while self.accept(&Token::Comma) {
+            returns.push(self.parse_return()?);
+        }
+
+        let limit = if self.accept_keyword("LIMIT") {
+            match self.next() {
+                Token::UnsignedInteger(limit) => Some(limit as usize),
+                _ => return Err(self.error_before("expected limit")),
+            }
+        } else {
+            None
+        };
+
+        if self.peek() != &Token::End {
+            return Err(self.error("unexpected token"));
+        }
+
+        let query = GraphQuery { patterns, condition, returns, limit };
+        self.check_variables(&query)?;
+        Ok(query)
+    }
+
+    // Patterns
+
+    fn parse_pattern(&mut self) -> Result<GraphPattern, GraphQueryError> {
+        let start = self.parse_node()?;
+
+        let mut steps = Vec::default();
+        while matches!(self.peek(), Token::Minus | Token::Less) {
+            let relationship = self.parse_relationship()?;
+            steps.push((relationship, self.parse_node()?));
+        }
+
+        Ok(GraphPattern { start, steps })
+    }
+
+    fn parse_node(&mut self) -> Result<GraphNode, GraphQueryError> {
+        self.expect(&Token::LeftParenthesis, "expected \"(\"")?;
+
+        let mut node = GraphNode { variable: self.accept_identifier(), ..Default::default() };
+
+        if self.accept(&Token::Colon) {
+            let position = self.position();
+            let label = self.expect_identifier()?;
+            node.kind = match label.as_ref() {
+                "Vertex" => Some(Kind::Vertex),
+                "VertexTemplate" => Some(Kind::VertexTemplate),
+                _ => return Err(GraphQueryError::syntax_at(position, "node label must be Vertex or VertexTemplate")),
+            };
+        }
+
+        if self.peek() == &Token::LeftBrace {
+            node.fields = self.parse_fields()?;
+        }
+
+        self.expect(&Token::RightParenthesis, "expected \")\"")?;
+        Ok(node)
+    }
+
+    fn parse_relationship(&mut self) -> Result<GraphRelationship, GraphQueryError> {
+        let incoming = self.accept(&Token::Less);
+        self.expect(&Token::Minus, "expected \"-\"")?;
+
+        let mut relationship = GraphRelationship {
+            variable: None,
+            relationship: GraphRelationshipKind::Edge,
+            direction: GraphDirection::Both,
+            transitive: false,
+            fields: Default::default(),
+        };
+
+        if self.accept(&Token::LeftBracket) {
+            relationship.variable = self.accept_identifier();
+
+            if self.accept(&Token::Colon) {
+                let position = self.position();
+                let label = self.expect_identifier()?;
+                relationship.relationship = match label.as_ref() {
+                    "Edge" => GraphRelationshipKind::Edge,
+                    "Contains" => GraphRelationshipKind::Contains,
+                    _ => {
+                        return Err(GraphQueryError::syntax_at(
+                            position,
+                            "relationship label must be Edge or Contains",
+                        ));
+                    }
+                };
+            }
+
+            if relationship.variable.is_some() && (relationship.relationship == GraphRelationshipKind::Contains) {
+                return Err(self.error_before("containment cannot be bound to a variable"));
+            }
+
+            if self.accept(&Token::Star) {
+                relationship.transitive = true;
+                if relationship.variable.is_some() {
+                    return Err(self.error_before("transitive relationships cannot be bound to a variable"));
+                }
+            }
+
+            if self.peek() == &Token::LeftBrace {
+                let position = self.position();
+                relationship.fields = self.parse_fields()?;
+                if relationship.relationship == GraphRelationshipKind::Contains {
+                    return Err(GraphQueryError::syntax_at(position, "containment does not have fields"));
+                }
+            }
+
+            self.expect(&Token::RightBracket, "expected \"]\"")?;
+        }
+
+        self.expect(&Token::Minus, "expected \"-\"")?;
+        let outgoing = self.accept(&Token::Greater);
+
+        relationship.direction = match (incoming, outgoing) {
+            (false, true) => GraphDirection::Outgoing,
+            (true, false) => GraphDirection::Incoming,
+            (false, false) => GraphDirection::Both,
+            (true, true) => return Err(self.error_before("relationship cannot have two directions")),
+        };
+
+        Ok(relationship)
+    }
+
+    fn parse_fields(&mut self) -> Result<Vec<(GraphField, Variant<WithoutAnnotations>)>, GraphQueryError> {
+        self.expect(&Token::LeftBrace, "expected \"{\"")?;
+
+        let mut fields = Vec::default();
+        if !self.accept(&Token::RightBrace) {
+            loop {
+                let field = self.parse_field()?;
+                self.expect(&Token::Colon, "expected \":\"")?;
+                fields.push((field, self.parse_literal()?));
+
+                if !self.accept(&Token::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(&Token::RightBrace, "expected \"}\"")?;
+        }
+
+        Ok(fields)
+    }
+
+    fn parse_field(&mut self) -> Result<GraphField, GraphQueryError> {
+        let position = self.position();
+        let name = self.expect_identifier()?;
+
+        Ok(match name.as_ref() {
+            "id" => GraphField::ID,
+            "kind" => GraphField::Kind,
+            "directory" => GraphField::Directory,
+            "class" => GraphField::Class,
+            "origin" => GraphField::Origin,
+            "revision" => GraphField::Revision,
+            "container" => GraphField::Container,
+            "source" => GraphField::Source,
+            "target" => GraphField::Target,
+            "metadata" => GraphField::Metadata(self.parse_subfield()?),
+            "property" => GraphField::Property(self.parse_subfield()?),
+            _ => return Err(GraphQueryError::syntax_at(position, &format!("unknown field: {}", name))),
+        })
+    }
+
+    fn parse_subfield(&mut self) -> Result<Option<ByteString>, GraphQueryError> {
+        if self.accept(&Token::Dot) { self.expect_identifier().map(Some) } else { Ok(None) }
+    }
+
+    fn parse_accessor(&mut self) -> Result<GraphAccessor, GraphQueryError> {
+        let variable = self.expect_identifier()?;
+        let field = if self.accept(&Token::Dot) { Some(self.parse_field()?) } else { None };
+        Ok(GraphAccessor { variable, field })
+    }
+
+    fn parse_return(&mut self) -> Result<GraphReturn, GraphQueryError> {
+        let accessor = self.parse_accessor()?;
+        let alias = if self.accept_keyword("AS") { Some(self.expect_identifier()?) } else { None };
+        Ok(GraphReturn { accessor, alias })
+    }
+
+    // Expressions
+
+    fn parse_or(&mut self) -> Result<GraphExpression, GraphQueryError> {
+        let mut expression = self.parse_and()?;
+        while self.accept_keyword("OR") {
+            expression = GraphExpression::Or(expression.into(), self.parse_and()?.into());
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<GraphExpression, GraphQueryError> {
+        let mut expression = self.parse_not()?;
+        while self.accept_keyword("AND") {
+            expression = GraphExpression::And(expression.into(), self.parse_not()?.into());
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<GraphExpression, GraphQueryError> {
+        if self.accept_keyword("NOT") {
+            Ok(GraphExpression::Not(self.parse_not()?.into()))
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<GraphExpression, GraphQueryError> {
+        let left = self.parse_operand()?;
+
+        if self.accept_keyword("IS") {
+            let negated = self.accept_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(GraphExpression::IsNull(left.into(), negated));
+        }
+
+        let operator = match self.peek() {
+            Token::Equal => GraphOperator::Equal,
+            Token::NotEqual => GraphOperator::NotEqual,
+            Token::Less => GraphOperator::Less,
+            Token::LessOrEqual => GraphOperator::LessOrEqual,
+            Token::Greater => GraphOperator::Greater,
+            Token::GreaterOrEqual => GraphOperator::GreaterOrEqual,
+            _ if self.is_keyword("IN") => GraphOperator::In,
+            _ if self.is_keyword("CONTAINS") => GraphOperator::Contains,
+            _ => return Ok(left),
+        };
+        self.next();
+
+        Ok(GraphExpression::Compare(left.into(), operator, self.parse_operand()?.into()))
+    }
+
+    fn parse_operand(&mut self) -> Result<GraphExpression, GraphQueryError> {
+        if self.accept(&Token::LeftParenthesis) {
+            let expression = self.parse_or()?;
+            self.expect(&Token::RightParenthesis, "expected \")\"")?;
+            return Ok(expression);
+        }
+
+        if let Token::Identifier(identifier) = self.peek()
+            && !is_literal_keyword(identifier)
+        {
+            return Ok(GraphExpression::Accessor(self.parse_accessor()?));
+        }
+
+        Ok(GraphExpression::Literal(self.parse_literal()?))
+    }
+
+    fn parse_literal(&mut self) -> Result<Variant<WithoutAnnotations>, GraphQueryError> {
+        Ok(match self.next() {
+            Token::Text(text) => text.into(),
+            Token::UnsignedInteger(unsigned_integer) => unsigned_integer.into(),
+            Token::Float(float) => float.into(),
+
+            Token::Minus => match self.next() {
+                Token::UnsignedInteger(unsigned_integer) => match i64::try_from(unsigned_integer) {
+                    Ok(integer) => (-integer).into(),
+                    Err(_) => return Err(self.error_before("integer out of range")),
+                },
+                Token::Float(float) => (-float).into(),
+                _ => return Err(self.error_before("expected number")),
+            },
+
+            Token::LeftBracket => {
+                let mut list = List::default();
+                if !self.accept(&Token::RightBracket) {
+                    loop {
+                        list.inner.push(self.parse_literal()?);
+                        if !self.accept(&Token::Comma) {
+                            break;
+                        }
+                    }
+                    self.expect(&Token::RightBracket, "expected \"]\"")?;
+                }
+                list.into()
+            }
+
+            Token::Identifier(identifier) if identifier.eq_ignore_ascii_case("true") => true.into(),
+            Token::Identifier(identifier) if identifier.eq_ignore_ascii_case("false") => false.into(),
+            Token::Identifier(identifier) if identifier.eq_ignore_ascii_case("null") => ().into(),
+
+            _ => return Err(self.error_before("expected literal")),
+        })
+    }
+
+    // Variables
+
+    // Make sure that returned and tested variables are bound, and that a variable is not bound to
+    // both a node and a relationship.
+    fn check_variables(&self, query: &GraphQuery) -> Result<(), GraphQueryError> {
+        let mut nodes = Vec::default();
+        let mut relationships = Vec::default();
+        for pattern in &query.patterns {
+            nodes.extend(pattern.start.variable.iter());
+            for (relationship, node) in &pattern.steps {
+                relationships.extend(relationship.variable.iter());
+                nodes.extend(node.variable.iter());
+            }
+        }
+
+        for (index, variable) in relationships.iter().enumerate() {
+            if nodes.contains(variable) || relationships[..index].contains(variable) {
+                return Err(GraphQueryError::Syntax(format!("variable bound more than once: {}", variable)));
+            }
+        }
+
+        let mut accessors = Vec::default();
+        if let Some(condition) = &query.condition {
+            collect_accessors(condition, &mut accessors);
+        }
+        accessors.extend(query.returns.iter().map(|return_| &return_.accessor));
+
+        for accessor in accessors {
+            if !nodes.contains(&&accessor.variable) && !relationships.contains(&&accessor.variable) {
+                return Err(GraphQueryError::Syntax(format!("unbound variable: {}", accessor.variable)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Tokens
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.index].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].0.clone();
+        if token != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, message: &str) -> Result<(), GraphQueryError> {
+        if self.accept(token) { Ok(()) } else { Err(self.error(message)) }
+    }
+
+    fn accept_identifier(&mut self) -> Option<ByteString> {
+        match self.peek() {
+            Token::Identifier(identifier) => {
+                let identifier = identifier.clone();
+                self.next();
+                Some(identifier)
+            }
+
+            _ => None,
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<ByteString, GraphQueryError> {
+        self.accept_identifier().ok_or_else(|| self.error("expected identifier"))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Identifier(identifier) if identifier.eq_ignore_ascii_case(keyword))
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), GraphQueryError> {
+        if self.accept_keyword(keyword) { Ok(()) } else { Err(self.error(&format!("expected {}", keyword))) }
+    }
+
+    // Error at the current token.
+    fn error(&self, message: &str) -> GraphQueryError {
+        GraphQueryError::syntax_at(self.position(), message)
+    }
+
+    // Error at the previous token.
+    fn error_before(&self, message: &str) -> GraphQueryError {
+        GraphQueryError::syntax_at(self.tokens[self.index.saturating_sub(1)].1, message)
+    }
+}
+
+fn is_literal_keyword(identifier: &str) -> bool {
+    ["true", "false", "null"].iter().any(|keyword| identifier.eq_ignore_ascii_case(keyword))
+}
+
+fn collect_accessors<'own>(expression: &'own GraphExpression, accessors: &mut Vec<&'own GraphAccessor>) {
+    match expression {
+        GraphExpression::Literal(_) => {}
+        GraphExpression::Accessor(accessor) => accessors.push(accessor),
+        GraphExpression::IsNull(expression, _) | GraphExpression::Not(expression) => {
+            collect_accessors(expression, accessors)
+        }
+        GraphExpression::Compare(left, _, right)
+        | GraphExpression::And(left, right)
+        | GraphExpression::Or(left, right) => {
+            collect_accessors(left, accessors);
+            collect_accessors(right, accessors);
+        }
+    }
+}
+
+// Tokenize. Identifiers may be quoted in backticks, e.g. for metadata keys with special characters.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, GraphQueryError> {
+    let mut tokens = Vec::default();
+    let mut characters = source.char_indices().peekable();
+
+    while let Some((position, character)) = characters.next() {
+        let token = match character {
+            _ if character.is_whitespace() => continue,
+
+            '(' => Token::LeftParenthesis,
+            ')' => Token::RightParenthesis,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '.' => Token::Dot,
+            '*' => Token::Star,
+            '-' => Token::Minus,
+            '=' => Token::Equal,
+
+            '!' => match characters.next_if(|(_, character)| *character == '=') {
+                Some(_) => Token::NotEqual,
+                None => return Err(GraphQueryError::syntax_at(position, "expected \"!=\"")),
+            },
+
+            '<' => match characters.next_if(|(_, character)| matches!(character, '=' | '>')) {
+                Some((_, '=')) => Token::LessOrEqual,
+                Some(_) => Token::NotEqual,
+                None => Token::Less,
+            },
+
+            '>' => match characters.next_if(|(_, character)| *character == '=') {
+                Some(_) => Token::GreaterOrEqual,
+                None => Token::Greater,
+            },
+
+            '"' | '\'' => {
+                let mut text = String::default();
+                loop {
+                    match characters.next() {
+                        Some((_, '\\')) => match characters.next() {
+                            Some((_, 'n')) => text.push('\n'),
+                            Some((_, 't')) => text.push('\t'),
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err(GraphQueryError::syntax_at(position, "unterminated text")),
+                        },
+                        Some((_, quote)) if quote == character => break,
+                        Some((_, character)) => text.push(character),
+                        None => return Err(GraphQueryError::syntax_at(position, "unterminated text")),
+                    }
+                }
+                Token::Text(text.into())
+            }
+
+            '`' => {
+                let mut identifier = String::default();
+                loop {
+                    match characters.next() {
+                        Some((_, '`')) => break,
+                        Some((_, character)) => identifier.push(character),
+                        None => return Err(GraphQueryError::syntax_at(position, "unterminated identifier")),
+                    }
+                }
+                Token::Identifier(identifier.into())
+            }
+
+            _ if character.is_ascii_digit() => {
+                let mut end = position + 1;
+                let mut float = false;
+                while let Some((index, character)) = characters.next_if(|(index, character)| {
+                    character.is_ascii_digit()
+                        || ((*character == '.')
+                            && source[index + 1..].starts_with(|character: char| character.is_ascii_digit()))
+                        || matches!(character, 'e' | 'E')
+                        || (matches!(character, '+' | '-') && source[..*index].ends_with(['e', 'E']))
+                }) {
+                    float |= !character.is_ascii_digit();
+                    end = index + character.len_utf8();
+                }
+
+                let number = &source[position..end];
+                if float {
+                    Token::Float(number.parse().map_err(|_| GraphQueryError::syntax_at(position, "malformed number"))?)
+                } else {
+                    Token::UnsignedInteger(
+                        number.parse().map_err(|_| GraphQueryError::syntax_at(position, "malformed number"))?,
+                    )
+                }
+            }
+
+            _ if character.is_alphabetic() || (character == '_') => {
+                let mut end = position + character.len_utf8();
+                while let Some((index, character)) =
+                    characters.next_if(|(_, character)| character.is_alphanumeric() || (*character == '_'))
+                {
+                    end = index + character.len_utf8();
+                }
+                Token::Identifier(source[position..end].into())
+            }
+
+            _ => return Err(GraphQueryError::syntax_at(position, &format!("unexpected character: {}", character))),
+        };
+
+        tokens.push((token, position));
+    }
+
+    tokens.push((Token::End, source.len()));
+    Ok(tokens)
+}
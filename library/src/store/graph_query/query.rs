@@ -0,0 +1,336 @@
+use super::{super::super::data::*, errors::*, parser::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::std::immutable::*,
+    std::{fmt, str::*},
+};
+
+//
+// GraphQuery
+//
+
+/// Graph query.
+///
+/// A declarative pattern query over vertexes, vertex templates, edges, and containment. The syntax
+/// is a subset of Cypher:
+///
+/// ```text
+/// MATCH (server:Vertex {class: "infra:Server", directory: "prod"})-[link]->(database {class: "infra:Database"})
+/// WHERE link.property.port >= 5000 AND NOT database.metadata.deprecated = true
+/// RETURN server, database.property.name AS name
+/// LIMIT 10
+/// ```
+///
+/// Node patterns are in parentheses. Their label is a kind, either `Vertex` (the default) or
+/// `VertexTemplate`.
+///
+/// Relationship patterns are in brackets. Their label is either `Edge` (the default), which only
+/// connects vertexes, or `Contains`, which goes from a containing vertex (or vertex template) to
+/// its contained vertexes (or vertex templates). The direction is `-[]->`, `<-[]-`, or `-[]-` for
+/// either, with `-->`, `<--`, and `--` as shorthands for edges. A `*` after the label follows
+/// the relationship transitively, e.g. `-[:Contains*]->` for all nested vertexes. Only
+/// non-transitive edges can be bound to a variable.
+///
+/// Both nodes and relationships may have a map of required field values in braces. `class` requires
+/// membership in the class (or in all the classes if a list is provided). Other fields must equal
+/// the value.
+///
+/// Fields are `id`, `kind`, `directory`, `class`, `origin`, `revision`, `container` (vertexes and
+/// vertex templates), `source` and `target` (edges), `metadata.<key>`, and `property.<name>`. IDs
+/// and directories are compared as text, e.g. "prod:server1" and "prod". A bare variable is its ID.
+///
+/// `WHERE` supports `=`, `<>` (or `!=`), `<`, `<=`, `>`, `>=`, `IN` (a list), `CONTAINS` (a list
+/// item or a substring), `IS NULL`, `IS NOT NULL`, `AND`, `OR`, `NOT`, and parentheses. Numbers
+/// compare by value regardless of representation. Missing values are null.
+///
+/// Several comma-separated patterns may share variables, in which case they must match the same
+/// entities.
+///
+/// Matching starts from the first node of each pattern, which is fetched via
+/// [get_entities](super::super::Store::get_entities). Put the most selective node first.
+#[derive(Clone, Debug)]
+pub struct GraphQuery {
+    /// Patterns.
+    pub patterns: Vec<GraphPattern>,
+
+    /// Optional condition.
+    pub condition: Option<GraphExpression>,
+
+    /// Returns.
+    pub returns: Vec<GraphReturn>,
+
+    /// Optional limit on the number of matches.
+    pub limit: Option<usize>,
+}
+
+impl GraphQuery {
+    /// Parse.
+    pub fn parse(source: &str) -> Result<Self, GraphQueryError> {
+        Parser::new(source)?.parse_query()
+    }
+}
+
+impl FromStr for GraphQuery {
+    type Err = GraphQueryError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+//
+// GraphPattern
+//
+
+/// Graph pattern: a path of nodes connected by relationships.
+#[derive(Clone, Debug)]
+pub struct GraphPattern {
+    /// Start.
+    pub start: GraphNode,
+
+    /// Steps from the start.
+    pub steps: Vec<(GraphRelationship, GraphNode)>,
+}
+
+//
+// GraphNode
+//
+
+/// Graph node pattern.
+#[derive(Clone, Debug, Default)]
+pub struct GraphNode {
+    /// Optional variable.
+    pub variable: Option<ByteString>,
+
+    /// Optional kind. Either [Kind::Vertex] or [Kind::VertexTemplate].
+    pub kind: Option<Kind>,
+
+    /// Required field values.
+    pub fields: Vec<(GraphField, Variant<WithoutAnnotations>)>,
+}
+
+//
+// GraphRelationship
+//
+
+/// Graph relationship pattern.
+#[derive(Clone, Debug)]
+pub struct GraphRelationship {
+    /// Optional variable.
+    pub variable: Option<ByteString>,
+
+    /// Relationship.
+    pub relationship: GraphRelationshipKind,
+
+    /// Direction.
+    pub direction: GraphDirection,
+
+    /// Whether to follow the relationship transitively.
+    pub transitive: bool,
+
+    /// Required field values. Only supported for edges.
+    pub fields: Vec<(GraphField, Variant<WithoutAnnotations>)>,
+}
+
+//
+// GraphRelationshipKind
+//
+
+/// Graph relationship kind.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GraphRelationshipKind {
+    /// Edge.
+    Edge,
+
+    /// Containment.
+    Contains,
+}
+
+//
+// GraphDirection
+//
+
+/// Graph relationship direction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GraphDirection {
+    /// From the previous node to the next.
+    Outgoing,
+
+    /// From the next node to the previous.
+    Incoming,
+
+    /// Either.
+    Both,
+}
+
+//
+// GraphField
+//
+
+/// Graph field.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GraphField {
+    /// ID.
+    ID,
+
+    /// Kind.
+    Kind,
+
+    /// Directory.
+    Directory,
+
+    /// Class IDs.
+    Class,
+
+    /// Origin template ID.
+    Origin,
+
+    /// Revision.
+    Revision,
+
+    /// Containing vertex (or vertex template) ID.
+    Container,
+
+    /// Edge source vertex ID.
+    Source,
+
+    /// Edge target vertex ID.
+    Target,
+
+    /// Metadata, optionally a single key.
+    Metadata(Option<ByteString>),
+
+    /// Property values, optionally a single property.
+    Property(Option<ByteString>),
+}
+
+impl fmt::Display for GraphField {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ID => fmt::Display::fmt("id", formatter),
+            Self::Kind => fmt::Display::fmt("kind", formatter),
+            Self::Directory => fmt::Display::fmt("directory", formatter),
+            Self::Class => fmt::Display::fmt("class", formatter),
+            Self::Origin => fmt::Display::fmt("origin", formatter),
+            Self::Revision => fmt::Display::fmt("revision", formatter),
+            Self::Container => fmt::Display::fmt("container", formatter),
+            Self::Source => fmt::Display::fmt("source", formatter),
+            Self::Target => fmt::Display::fmt("target", formatter),
+            Self::Metadata(None) => fmt::Display::fmt("metadata", formatter),
+            Self::Metadata(Some(key)) => write!(formatter, "metadata.{}", key),
+            Self::Property(None) => fmt::Display::fmt("property", formatter),
+            Self::Property(Some(name)) => write!(formatter, "property.{}", name),
+        }
+    }
+}
+
+//
+// GraphAccessor
+//
+
+/// Graph accessor: a field of a variable.
+#[derive(Clone, Debug)]
+pub struct GraphAccessor {
+    /// Variable.
+    pub variable: ByteString,
+
+    /// Field. [None] means the ID.
+    pub field: Option<GraphField>,
+}
+
+impl fmt::Display for GraphAccessor {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(formatter, "{}.{}", self.variable, field),
+            None => fmt::Display::fmt(&self.variable, formatter),
+        }
+    }
+}
+
+//
+// GraphExpression
+//
+
+/// Graph expression.
+#[derive(Clone, Debug)]
+pub enum GraphExpression {
+    /// Literal.
+    Literal(Variant<WithoutAnnotations>),
+
+    /// Accessor.
+    Accessor(GraphAccessor),
+
+    /// Comparison.
+    Compare(Box<GraphExpression>, GraphOperator, Box<GraphExpression>),
+
+    /// Null test. True if the value is null, or if not null when negated.
+    IsNull(Box<GraphExpression>, bool),
+
+    /// Logical not.
+    Not(Box<GraphExpression>),
+
+    /// Logical and.
+    And(Box<GraphExpression>, Box<GraphExpression>),
+
+    /// Logical or.
+    Or(Box<GraphExpression>, Box<GraphExpression>),
+}
+
+//
+// GraphOperator
+//
+
+/// Graph comparison operator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GraphOperator {
+    /// Equal.
+    Equal,
+
+    /// Not equal.
+    NotEqual,
+
+    /// Less.
+    Less,
+
+    /// Less or equal.
+    LessOrEqual,
+
+    /// Greater.
+    Greater,
+
+    /// Greater or equal.
+    GreaterOrEqual,
+
+    /// In a list.
+    In,
+
+    /// Contains a list item or a substring.
+    Contains,
+}
+
+//
+// GraphReturn
+//
+
+/// Graph query return.
+#[derive(Clone, Debug)]
+pub struct GraphReturn {
+    /// Accessor.
+    pub accessor: GraphAccessor,
+
+    /// Optional alias.
+    pub alias: Option<ByteString>,
+}
+
+impl GraphReturn {
+    /// Column name.
+    ///
+    /// The alias if there is one, otherwise the accessor.
+    pub fn column(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.to_string(),
+            None => self.accessor.to_string(),
+        }
+    }
+}
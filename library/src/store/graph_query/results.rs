@@ -0,0 +1,153 @@
+use super::{super::super::data::*, query::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    kutil::std::immutable::*,
+    std::collections::*,
+};
+
+//
+// GraphQueryMatch
+//
+
+/// Graph query match.
+#[derive(Clone, Debug, Default)]
+pub struct GraphQueryMatch {
+    /// Entities bound to variables.
+    pub bindings: BTreeMap<ByteString, Entity>,
+}
+
+impl GraphQueryMatch {
+    /// ID of the entity bound to a variable.
+    pub fn get_id(&self, variable: &str) -> Option<&ID> {
+        self.bindings.get(variable).map(|entity| entity.get_id())
+    }
+
+    /// Value of an accessor.
+    ///
+    /// Will be null if the variable is not bound or the field is missing.
+    pub fn get(&self, accessor: &GraphAccessor) -> Variant<WithoutAnnotations> {
+        match self.bindings.get(&accessor.variable) {
+            Some(entity) => match &accessor.field {
+                Some(field) => get_field(entity, field),
+                None => entity.get_id().to_string().into(),
+            },
+
+            None => ().into(),
+        }
+    }
+}
+
+//
+// GraphQueryRows
+//
+
+/// Graph query rows.
+#[derive(Clone, Debug, Default)]
+pub struct GraphQueryRows {
+    /// Column names.
+    pub columns: Vec<String>,
+
+    /// Rows. Each has a value per column.
+    pub rows: Vec<Vec<Variant<WithoutAnnotations>>>,
+}
+
+impl GraphQueryRows {
+    /// To Compris variant.
+    ///
+    /// A list of maps of column names to values.
+    pub fn to_variant(&self) -> Variant<WithoutAnnotations> {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, value)| (column.clone().into(), value.clone()))
+                    .collect::<Variant<_>>()
+            })
+            .collect()
+    }
+}
+
+// Utils
+
+// Get the value of a field. Will be null if the field is missing or not supported by the kind.
+pub(crate) fn get_field(entity: &Entity, field: &GraphField) -> Variant<WithoutAnnotations> {
+    match field {
+        GraphField::ID => entity.get_id().to_string().into(),
+        GraphField::Kind => entity.get_id().kind.to_string().into(),
+        GraphField::Directory => entity.get_id().directory.to_string().into(),
+
+        GraphField::Class => {
+            entity.get_class_ids().iter().map(|class_id| Variant::from(class_id.to_string())).collect::<Variant<_>>()
+        }
+
+        GraphField::Origin => optional_id_to_variant(entity.get_origin_template_id()),
+
+        GraphField::Revision => match entity {
+            Entity::Class(class) => class.revision,
+            Entity::VertexTemplate(vertex_template) => vertex_template.template.revision,
+            Entity::EdgeTemplate(edge_template) => edge_template.template.revision,
+            Entity::Vertex(vertex) => vertex.instance.revision,
+            Entity::Edge(edge) => edge.instance.revision,
+        }
+        .into(),
+
+        GraphField::Container => match entity {
+            Entity::VertexTemplate(vertex_template) => {
+                optional_id_to_variant(vertex_template.containing_vertex_template_id.as_ref())
+            }
+            Entity::Vertex(vertex) => optional_id_to_variant(vertex.containing_vertex_id.as_ref()),
+            _ => ().into(),
+        },
+
+        GraphField::Source => match entity {
+            Entity::Edge(edge) => edge.source_vertex_id.to_string().into(),
+            _ => ().into(),
+        },
+
+        GraphField::Target => match entity {
+            Entity::Edge(edge) => edge.target_vertex_id.to_string().into(),
+            _ => ().into(),
+        },
+
+        GraphField::Metadata(key) => {
+            let metadata = entity.get_metadata();
+            match key {
+                Some(key) => metadata.into_get(key.clone()).cloned().unwrap_or_else(null),
+                None => metadata.clone().into(),
+            }
+        }
+
+        GraphField::Property(name) => {
+            let properties = match entity {
+                Entity::VertexTemplate(vertex_template) => &vertex_template.template.property_templates,
+                Entity::EdgeTemplate(edge_template) => &edge_template.template.property_templates,
+                Entity::Vertex(vertex) => &vertex.instance.properties,
+                Entity::Edge(edge) => &edge.instance.properties,
+                Entity::Class(_) => return ().into(),
+            };
+
+            match name {
+                Some(name) => properties.get(name).and_then(|property| property.value.clone()).unwrap_or_else(null),
+
+                None => properties
+                    .iter()
+                    .map(|(name, property)| (name.clone().into(), property.value.clone().unwrap_or_else(null)))
+                    .collect(),
+            }
+        }
+    }
+}
+
+fn optional_id_to_variant(id: Option<&ID>) -> Variant<WithoutAnnotations> {
+    match id {
+        Some(id) => id.to_string().into(),
+        None => null(),
+    }
+}
+
+fn null() -> Variant<WithoutAnnotations> {
+    ().into()
+}
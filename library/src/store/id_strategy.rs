@@ -0,0 +1,127 @@
+use super::{super::data::*, errors::*};
+
+use {std::sync::*, ulid::*, uuid::*};
+
+//
+// IDStrategy
+//
+
+/// Strategy for creating entity IDs.
+pub trait IDStrategy
+where
+    Self: Send + Sync,
+{
+    /// Create an ID by setting its `id` field.
+    ///
+    /// The next sequential ID for the kind can be acquired from the store by calling `next_id`.
+    /// Strategies that don't need it should not call it, as it advances the store's counter.
+    fn create_id(&self, id: &mut ID, next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError>;
+}
+
+impl Default for Box<dyn IDStrategy> {
+    fn default() -> Self {
+        Box::new(SequentialIDStrategy)
+    }
+}
+
+//
+// SequentialIDStrategy
+//
+
+/// [IDStrategy] that uses the store's sequential integer for the kind.
+///
+/// This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SequentialIDStrategy;
+
+impl IDStrategy for SequentialIDStrategy {
+    fn create_id(&self, id: &mut ID, next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError> {
+        id.id = next_id()?.to_string().into();
+        Ok(())
+    }
+}
+
+//
+// UUIDv4IDStrategy
+//
+
+/// [IDStrategy] that uses random UUIDv4.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UUIDv4IDStrategy;
+
+impl IDStrategy for UUIDv4IDStrategy {
+    fn create_id(&self, id: &mut ID, _next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError> {
+        id.id = Uuid::new_v4().to_string().into();
+        Ok(())
+    }
+}
+
+//
+// UUIDv7IDStrategy
+//
+
+/// [IDStrategy] that uses UUIDv7, which is sortable by creation time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UUIDv7IDStrategy;
+
+impl IDStrategy for UUIDv7IDStrategy {
+    fn create_id(&self, id: &mut ID, _next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError> {
+        id.id = Uuid::now_v7().to_string().into();
+        Ok(())
+    }
+}
+
+//
+// ULIDStrategy
+//
+
+/// [IDStrategy] that uses ULID, which is sortable by creation time.
+///
+/// IDs created by the same strategy in the same millisecond are still sorted by creation order.
+#[derive(Default)]
+pub struct ULIDStrategy {
+    generator: Mutex<Generator>,
+}
+
+impl IDStrategy for ULIDStrategy {
+    fn create_id(&self, id: &mut ID, _next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError> {
+        let ulid = self.generator.lock()?.generate().map_err(|error| StoreError::ID(error.to_string()))?;
+        id.id = ulid.to_string().into();
+        Ok(())
+    }
+}
+
+//
+// DerivedIDStrategy
+//
+
+/// [IDStrategy] that derives UUIDv5 from a seed, so that they can be reproduced.
+///
+/// If the ID's `id` field is not empty then its content, together with the kind and directory, is
+/// used to derive the ID. Otherwise the store's sequential integer for the kind is used instead, so
+/// that the same sequence of IDs would be created for the same seed in an empty store.
+///
+/// Different seeds derive different IDs, so topologies created with different seeds can be merged.
+#[derive(Clone, Copy, Debug)]
+pub struct DerivedIDStrategy {
+    namespace: Uuid,
+}
+
+impl DerivedIDStrategy {
+    /// Constructor.
+    pub fn new(seed: &str) -> Self {
+        Self { namespace: Uuid::new_v5(&Uuid::NAMESPACE_OID, seed.as_bytes()) }
+    }
+}
+
+impl IDStrategy for DerivedIDStrategy {
+    fn create_id(&self, id: &mut ID, next_id: &mut dyn FnMut() -> Result<u64, StoreError>) -> Result<(), StoreError> {
+        if id.id.is_empty() {
+            id.id = next_id()?.to_string().into();
+        }
+
+        let name = format!("{}:{}", id.kind, id);
+        id.id = Uuid::new_v5(&self.namespace, name.as_bytes()).to_string().into();
+        Ok(())
+    }
+}
@@ -1,6 +1,16 @@
-use super::super::super::data::*;
+use super::{
+    super::{super::data::*, errors::*, events::*, id_strategy::*, transaction_lock::*},
+    indexes::*,
+};
 
-use kutil::std::collections::*;
+use {
+    compris::{annotate::*, normal::*},
+    kutil::std::collections::*,
+    std::{collections::*, sync::*},
+};
+
+// The previous states of the entities written in the transaction
+pub(crate) type InMemoryTransaction = Vec<(ID, Option<Entity>)>;
 
 //
 // InMemoryStoreBackend
@@ -15,11 +25,113 @@ pub struct InMemoryStoreBackend {
     pub(crate) edge_templates: FastConcurrentHashMap<ID, EdgeTemplate>,
     pub(crate) vertexes: FastConcurrentHashMap<ID, Vertex>,
     pub(crate) edges: FastConcurrentHashMap<ID, Edge>,
+
+    // Updated together with the entities, while holding the write lock
+    pub(crate) indexes: RwLock<InMemoryStoreIndexes>,
+
+    /// ID strategy.
+    pub id_strategy: Box<dyn IDStrategy>,
+
+    pub(crate) publisher: StoreEventPublisher,
+
+    // Serializes writes so that revisions can be compared and set atomically
+    pub(crate) write_lock: Mutex<()>,
+
+    // Held by transactions and writes so that they would not interleave with other owners'
+    pub(crate) transaction_lock: TransactionLock,
+
+    // A stack of nested transactions, all belonging to the owner of the transaction lock
+    pub(crate) transactions: Mutex<Vec<InMemoryTransaction>>,
 }
 
 impl InMemoryStoreBackend {
+    /// With ID strategy.
+    pub fn with_id_strategy<IDStrategyT>(mut self, id_strategy: IDStrategyT) -> Self
+    where
+        IDStrategyT: 'static + IDStrategy,
+    {
+        self.id_strategy = Box::new(id_strategy);
+        self
+    }
+
+    /// With indexed metadata key.
+    ///
+    /// Queries with this metadata key will use an index.
+    pub fn with_indexed_metadata_key<KeyT>(self, key: KeyT) -> Self
+    where
+        KeyT: Into<Variant<WithoutAnnotations>>,
+    {
+        if let Ok(mut indexes) = self.indexes.write() {
+            indexes.metadata_keys.insert(key.into());
+        }
+        self
+    }
+
+    /// Add an indexed metadata key.
+    ///
+    /// Unlike [with_indexed_metadata_key](Self::with_indexed_metadata_key) this can be called
+    /// when there are already entities, which will all be reindexed.
+    pub fn add_indexed_metadata_key<KeyT>(&self, key: KeyT) -> Result<(), StoreError>
+    where
+        KeyT: Into<Variant<WithoutAnnotations>>,
+    {
+        let _write_lock = self.write_lock.lock()?;
+        let mut indexes = self.indexes.write()?;
+
+        if indexes.metadata_keys.insert(key.into()) {
+            indexes.clear();
+            indexes.rebuild(self.classes.pin().iter());
+            indexes.rebuild(self.vertex_templates.pin().iter());
+            indexes.rebuild(self.edge_templates.pin().iter());
+            indexes.rebuild(self.vertexes.pin().iter());
+            indexes.rebuild(self.edges.pin().iter());
+        }
+
+        Ok(())
+    }
+
+    /// Indexes.
+    pub fn get_indexes(&self) -> Result<RwLockReadGuard<'_, InMemoryStoreIndexes>, StoreError> {
+        Ok(self.indexes.read()?)
+    }
+
     /// Next ID.
     pub fn get_next_id(&self, kind: Kind) -> u64 {
         self.next_id.pin().update_or_insert(kind, |id| id + 1, 1).clone()
     }
+
+    /// Next IDs.
+    pub fn get_next_ids(&self) -> HashMap<Kind, u64> {
+        self.next_id.pin().iter().map(|(kind, next_id)| (*kind, *next_id)).collect()
+    }
+
+    /// Advance next ID.
+    ///
+    /// Does nothing if the provided value is not higher.
+    pub fn advance_next_id(&self, kind: Kind, next_id: u64) {
+        self.next_id.pin().update_or_insert(kind, |id| (*id).max(next_id), next_id);
+    }
+
+    /// True if we are in a transaction.
+    pub fn in_transaction(&self) -> Result<bool, StoreError> {
+        Ok(!self.transactions.lock()?.is_empty())
+    }
+
+    /// Publish a change to subscribers.
+    pub(crate) fn publish(&self, id: ID, before: Option<Entity>, after: Option<Entity>) -> Result<(), StoreError> {
+        match StoreEvent::new(id, before, after) {
+            Some(event) => self.publisher.publish(event),
+            None => Ok(()),
+        }
+    }
+
+    /// Record the previous state of an entity in the current transaction.
+    ///
+    /// Does nothing if we are not in a transaction.
+    pub(crate) fn record(&self, id: ID, previous: Option<Entity>) -> Result<(), StoreError> {
+        if let Some(transaction) = self.transactions.lock()?.last_mut() {
+            transaction.push((id, previous));
+        }
+        Ok(())
+    }
 }
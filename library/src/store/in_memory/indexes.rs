@@ -0,0 +1,304 @@
+use super::super::{super::data::*, query::*};
+
+use {
+    compris::{annotate::*, normal::*},
+    std::collections::*,
+};
+
+//
+// InMemoryStoreIndexes
+//
+
+/// Secondary indexes for [InMemoryStoreBackend](super::InMemoryStoreBackend).
+///
+/// Entities are indexed by directory, class IDs, origin template ID, and the values of selected
+/// metadata keys. They are used to narrow down the candidates for a [StoreQuery].
+#[derive(Debug, Default)]
+pub struct InMemoryStoreIndexes {
+    /// Indexed metadata keys.
+    pub metadata_keys: HashSet<Variant<WithoutAnnotations>>,
+
+    by_directory: HashMap<(Kind, Directory), HashSet<ID>>,
+    by_class_id: HashMap<(Kind, ID), HashSet<ID>>,
+    by_origin_template_id: HashMap<(Kind, ID), HashSet<ID>>,
+    by_metadata: HashMap<(Kind, Variant<WithoutAnnotations>, Variant<WithoutAnnotations>), HashSet<ID>>,
+}
+
+impl InMemoryStoreIndexes {
+    /// IDs of the entities of a kind in a directory.
+    pub fn get_by_directory(&self, kind: Kind, directory: &Directory) -> Vec<ID> {
+        get(&self.by_directory, &(kind, directory.clone()))
+    }
+
+    /// IDs of the entities of a kind that are members of a class.
+    pub fn get_by_class_id(&self, kind: Kind, class_id: &ID) -> Vec<ID> {
+        get(&self.by_class_id, &(kind, class_id.clone()))
+    }
+
+    /// IDs of the entities of a kind that originated from a template.
+    pub fn get_by_origin_template_id(&self, kind: Kind, origin_template_id: &ID) -> Vec<ID> {
+        get(&self.by_origin_template_id, &(kind, origin_template_id.clone()))
+    }
+
+    /// IDs of the entities of a kind with a metadata value.
+    ///
+    /// Returns [None] if the metadata key is not indexed.
+    pub fn get_by_metadata(
+        &self,
+        kind: Kind,
+        key: &Variant<WithoutAnnotations>,
+        value: &Variant<WithoutAnnotations>,
+    ) -> Option<Vec<ID>> {
+        self.metadata_keys.contains(key).then(|| get(&self.by_metadata, &(kind, key.clone(), value.clone())))
+    }
+
+    /// IDs of the entities of a kind that may match a query.
+    ///
+    /// Uses the most selective index that applies. Returns [None] if no index applies, in which
+    /// case all entities of the kind may match.
+    ///
+    /// Note that the candidates must still be checked with [StoreQuery::matches].
+    pub fn get_candidates(&self, kind: Kind, query: &StoreQuery) -> Option<Vec<ID>> {
+        let mut candidates = None;
+
+        if query.directories.is_some() {
+            narrow(
+                &mut candidates,
+                self.by_directory
+                    .iter()
+                    .filter(|((kind_, directory), _)| (*kind_ == kind) && query.matches_directory(directory))
+                    .map(|(_, ids)| ids)
+                    .collect(),
+            );
+        }
+
+        for class_id in &query.class_ids {
+            narrow(&mut candidates, self.by_class_id.get(&(kind, class_id.clone())).into_iter().collect());
+        }
+
+        if let Some(origin_template_id) = &query.origin_template_id {
+            narrow(
+                &mut candidates,
+                self.by_origin_template_id.get(&(kind, origin_template_id.clone())).into_iter().collect(),
+            );
+        }
+
+        for (key, value) in &query.metadata.inner {
+            if self.metadata_keys.contains(key) {
+                narrow(
+                    &mut candidates,
+                    self.by_metadata.get(&(kind, key.clone(), value.clone())).into_iter().collect(),
+                );
+            }
+        }
+
+        candidates.map(|sets| sets.into_iter().flatten().cloned().collect())
+    }
+
+    // Update the indexes for a change of an entity.
+    pub(crate) fn update<EntityT>(&mut self, id: &ID, before: Option<&EntityT>, after: Option<&EntityT>)
+    where
+        EntityT: Indexed,
+    {
+        if let Some(before) = before {
+            self.remove(id, before);
+        }
+
+        if let Some(after) = after {
+            self.add(id, after);
+        }
+    }
+
+    // Index entities.
+    pub(crate) fn rebuild<'own, EntityT, IteratorT>(&mut self, entities: IteratorT)
+    where
+        EntityT: 'own + Indexed,
+        IteratorT: Iterator<Item = (&'own ID, &'own EntityT)>,
+    {
+        for (id, entity) in entities {
+            self.add(id, entity);
+        }
+    }
+
+    // Clear the indexes (but not the indexed metadata keys).
+    pub(crate) fn clear(&mut self) {
+        self.by_directory.clear();
+        self.by_class_id.clear();
+        self.by_origin_template_id.clear();
+        self.by_metadata.clear();
+    }
+
+    fn add<EntityT>(&mut self, id: &ID, entity: &EntityT)
+    where
+        EntityT: Indexed,
+    {
+        let kind = id.kind;
+
+        self.by_directory.entry((kind, id.directory.clone())).or_default().insert(id.clone());
+
+        for class_id in entity.get_indexed_class_ids() {
+            self.by_class_id.entry((kind, class_id.clone())).or_default().insert(id.clone());
+        }
+
+        if let Some(origin_template_id) = entity.get_indexed_origin_template_id() {
+            self.by_origin_template_id.entry((kind, origin_template_id.clone())).or_default().insert(id.clone());
+        }
+
+        let metadata = entity.get_indexed_metadata();
+        for key in &self.metadata_keys {
+            if let Some(value) = metadata.inner.get(key) {
+                self.by_metadata.entry((kind, key.clone(), value.clone())).or_default().insert(id.clone());
+            }
+        }
+    }
+
+    fn remove<EntityT>(&mut self, id: &ID, entity: &EntityT)
+    where
+        EntityT: Indexed,
+    {
+        let kind = id.kind;
+
+        remove(&mut self.by_directory, (kind, id.directory.clone()), id);
+
+        for class_id in entity.get_indexed_class_ids() {
+            remove(&mut self.by_class_id, (kind, class_id.clone()), id);
+        }
+
+        if let Some(origin_template_id) = entity.get_indexed_origin_template_id() {
+            remove(&mut self.by_origin_template_id, (kind, origin_template_id.clone()), id);
+        }
+
+        let metadata = entity.get_indexed_metadata();
+        for key in &self.metadata_keys {
+            if let Some(value) = metadata.inner.get(key) {
+                remove(&mut self.by_metadata, (kind, key.clone(), value.clone()), id);
+            }
+        }
+    }
+}
+
+//
+// Indexed
+//
+
+// Provides the fields that are indexed by InMemoryStoreIndexes.
+pub(crate) trait Indexed {
+    fn get_indexed_class_ids(&self) -> &[ID];
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID>;
+
+    fn get_indexed_metadata(&self) -> &Metadata;
+}
+
+impl Indexed for Class {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        &[]
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        None
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+impl Indexed for VertexTemplate {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        &self.template.class_ids
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        None
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        &self.template.metadata
+    }
+}
+
+impl Indexed for EdgeTemplate {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        &self.template.class_ids
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        None
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        &self.template.metadata
+    }
+}
+
+impl Indexed for Vertex {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        &self.instance.class_ids
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        self.instance.origin_template_id.as_ref()
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        &self.instance.metadata
+    }
+}
+
+impl Indexed for Edge {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        &self.instance.class_ids
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        self.instance.origin_template_id.as_ref()
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        &self.instance.metadata
+    }
+}
+
+impl Indexed for Entity {
+    fn get_indexed_class_ids(&self) -> &[ID] {
+        self.get_class_ids()
+    }
+
+    fn get_indexed_origin_template_id(&self) -> Option<&ID> {
+        self.get_origin_template_id()
+    }
+
+    fn get_indexed_metadata(&self) -> &Metadata {
+        self.get_metadata()
+    }
+}
+
+// Utils
+
+// Keep the sets if they are smaller than the current candidates.
+fn narrow<'own>(candidates: &mut Option<Vec<&'own HashSet<ID>>>, sets: Vec<&'own HashSet<ID>>) {
+    let length: usize = sets.iter().map(|set| set.len()).sum();
+    if candidates.as_ref().is_none_or(|candidates| length < candidates.iter().map(|set| set.len()).sum()) {
+        *candidates = Some(sets);
+    }
+}
+
+fn get<KeyT>(index: &HashMap<KeyT, HashSet<ID>>, key: &KeyT) -> Vec<ID>
+where
+    KeyT: Eq + std::hash::Hash,
+{
+    index.get(key).map(|ids| ids.iter().cloned().collect()).unwrap_or_default()
+}
+
+fn remove<KeyT>(index: &mut HashMap<KeyT, HashSet<ID>>, key: KeyT, id: &ID)
+where
+    KeyT: Eq + std::hash::Hash,
+{
+    if let hash_map::Entry::Occupied(mut entry) = index.entry(key) {
+        entry.get_mut().remove(id);
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+    }
+}
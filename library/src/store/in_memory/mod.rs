@@ -1,5 +1,6 @@
 mod backend;
+mod indexes;
 mod store;
 
 #[allow(unused_imports)]
-pub use {backend::*, store::*};
+pub use {backend::*, indexes::*, store::*};
@@ -1,9 +1,13 @@
 use super::{
-    super::{super::data::*, errors::*, store::*, wrapper::*},
+    super::{super::data::*, errors::*, events::*, query::*, store::*, wrapper::*},
     backend::*,
+    indexes::*,
 };
 
-use std::sync::*;
+use {
+    kutil::std::collections::*,
+    std::{collections::*, sync::*},
+};
 
 //
 // InMemoryStore
@@ -23,10 +27,265 @@ impl InMemoryStore {
     }
 }
 
+// Entities that match the query. Uses the indexes to narrow down the candidates.
+fn query_entities<EntityT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    kind: Kind,
+    query: &StoreQuery,
+) -> Result<Vec<Entity>, StoreError>
+where
+    EntityT: Clone + Into<Entity>,
+{
+    let candidates = backend.indexes.read()?.get_candidates(kind, query);
+    let entities = entities.pin();
+
+    Ok(match candidates {
+        Some(ids) => ids
+            .iter()
+            .filter_map(|id| entities.get(id))
+            .map(|entity| entity.clone().into())
+            .filter(|entity| query.matches(entity))
+            .collect(),
+
+        None => entities
+            .iter()
+            .filter(|(id, _)| query.matches_directory(&id.directory))
+            .map(|(_, entity)| entity.clone().into())
+            .filter(|entity| query.matches(entity))
+            .collect(),
+    })
+}
+
+// Insert an entity, recording the previous state and publishing the change.
+fn insert_entity<EntityT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    id: ID,
+    entity: EntityT,
+) -> Result<(), StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity> + Revisioned,
+{
+    insert_entity_if_revision(backend, entities, id, entity, None)?;
+    Ok(())
+}
+
+// Insert an entity if the revision matches (when provided), recording the previous state and
+// publishing the change. Returns the new revision.
+fn insert_entity_if_revision<EntityT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    id: ID,
+    mut entity: EntityT,
+    expected_revision: Option<u64>,
+) -> Result<u64, StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity> + Revisioned,
+{
+    let _transaction_lock = backend.transaction_lock.hold()?;
+    let _write_lock = backend.write_lock.lock()?;
+    let entities = entities.pin();
+
+    let revision = entities.get(&id).map(|previous| previous.get_revision()).unwrap_or(0);
+    check_revision(&id, expected_revision, revision)?;
+    entity.set_revision(revision + 1);
+
+    let after = if backend.publisher.has_subscribers()? { Some(entity.clone().into()) } else { None };
+    let previous = entities.insert(id.clone(), entity).cloned();
+    backend.indexes.write()?.update(&id, previous.as_ref(), entities.get(&id));
+    let previous: Option<Entity> = previous.map(|previous| previous.into());
+
+    if after.is_some() {
+        backend.publish(id.clone(), previous.clone(), after)?;
+    }
+
+    backend.record(id, previous)?;
+    Ok(revision + 1)
+}
+
+// Remove an entity, recording the previous state and publishing the change.
+fn remove_entity<EntityT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    id: &ID,
+) -> Result<Option<EntityT>, StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity>,
+{
+    let _transaction_lock = backend.transaction_lock.hold()?;
+    let _write_lock = backend.write_lock.lock()?;
+    let previous = entities.pin().remove(id).cloned();
+    if let Some(previous) = &previous {
+        backend.indexes.write()?.update(id, Some(previous), None);
+        let previous: Entity = previous.clone().into();
+        backend.publish(id.clone(), Some(previous.clone()), None)?;
+        backend.record(id.clone(), Some(previous))?;
+    }
+    Ok(previous)
+}
+
+// Update an entity in place (if it exists), recording the previous state.
+fn update_entity<EntityT, UpdateT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    id: &ID,
+    update: UpdateT,
+) -> Result<(), StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity> + Revisioned,
+    UpdateT: Fn(&mut EntityT),
+{
+    update_entity_if(backend, entities, id, |entity| {
+        update(entity);
+        true
+    })
+}
+
+// Update an entity in place (if it exists) if the update function returns true, recording the
+// previous state.
+//
+// The entity is written only if it has not changed since we read it. Otherwise we read it again
+// and retry, so that concurrent changes would not be lost.
+fn update_entity_if<EntityT, UpdateT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    id: &ID,
+    update: UpdateT,
+) -> Result<(), StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity> + Revisioned,
+    UpdateT: Fn(&mut EntityT) -> bool,
+{
+    loop {
+        let Some(mut entity) = entities.pin().get(id).cloned() else {
+            return Ok(());
+        };
+
+        let revision = entity.get_revision();
+        if !update(&mut entity) {
+            return Ok(());
+        }
+
+        match insert_entity_if_revision(backend, entities, id.clone(), entity, Some(revision)) {
+            Err(StoreError::Conflict(_)) => {}
+            result => return result.map(|_| ()),
+        }
+    }
+}
+
+// Update all entities for which the update function returns true, recording the previous states.
+fn update_entities<EntityT, UpdateT>(
+    backend: &InMemoryStoreBackend,
+    entities: &FastConcurrentHashMap<ID, EntityT>,
+    update: UpdateT,
+) -> Result<(), StoreError>
+where
+    EntityT: Clone + Indexed + Into<Entity> + Revisioned,
+    UpdateT: Fn(&mut EntityT) -> bool,
+{
+    let ids: Vec<_> = entities.pin().keys().cloned().collect();
+    for id in ids {
+        update_entity_if(backend, entities, &id, &update)?;
+    }
+    Ok(())
+}
+
+impl InMemoryStore {
+    // Restore the previous state of an entity (without recording it), publishing the change.
+    fn restore(&self, id: ID, previous: Option<Entity>) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let _write_lock = self.backend.write_lock.lock()?;
+        let after = previous.clone();
+
+        let before: Option<Entity> = match previous {
+            Some(Entity::Class(class)) => self.backend.classes.pin().insert(id.clone(), class).cloned().map(Into::into),
+
+            Some(Entity::VertexTemplate(vertex_template)) => {
+                self.backend.vertex_templates.pin().insert(id.clone(), vertex_template).cloned().map(Into::into)
+            }
+
+            Some(Entity::EdgeTemplate(edge_template)) => {
+                self.backend.edge_templates.pin().insert(id.clone(), edge_template).cloned().map(Into::into)
+            }
+
+            Some(Entity::Vertex(vertex)) => {
+                self.backend.vertexes.pin().insert(id.clone(), vertex).cloned().map(Into::into)
+            }
+
+            Some(Entity::Edge(edge)) => self.backend.edges.pin().insert(id.clone(), edge).cloned().map(Into::into),
+
+            None => match id.kind {
+                Kind::Class => self.backend.classes.pin().remove(&id).cloned().map(Into::into),
+                Kind::VertexTemplate => self.backend.vertex_templates.pin().remove(&id).cloned().map(Into::into),
+                Kind::EdgeTemplate => self.backend.edge_templates.pin().remove(&id).cloned().map(Into::into),
+                Kind::Vertex => self.backend.vertexes.pin().remove(&id).cloned().map(Into::into),
+                Kind::Edge => self.backend.edges.pin().remove(&id).cloned().map(Into::into),
+            },
+        };
+
+        self.backend.indexes.write()?.update(&id, before.as_ref(), after.as_ref());
+        self.backend.publish(id, before, after)
+    }
+}
+
 impl Store for InMemoryStore {
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.backend.transaction_lock.begin()?;
+        self.backend.transactions.lock()?.push(Default::default());
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let mut transactions = self.backend.transactions.lock()?;
+        let transaction = transactions.pop().ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        // Merge into the enclosing transaction so that it can still be rolled back
+        if let Some(enclosing_transaction) = transactions.last_mut() {
+            enclosing_transaction.extend(transaction);
+        }
+
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let transaction = self
+            .backend
+            .transactions
+            .lock()?
+            .pop()
+            .ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        for (id, previous) in transaction.into_iter().rev() {
+            self.restore(id, previous)?;
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.backend.publisher.subscribe(filter)
+    }
+
     fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
-        let next_id = self.backend.get_next_id(id.kind.clone());
-        id.id = next_id.to_string().into();
+        let kind = id.kind;
+        self.backend.id_strategy.create_id(id, &mut || Ok(self.backend.get_next_id(kind)))
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        Ok(self.backend.get_next_ids())
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        for (kind, next_id) in next_ids {
+            self.backend.advance_next_id(*kind, *next_id);
+        }
         Ok(())
     }
 
@@ -35,8 +294,32 @@ impl Store for InMemoryStore {
     }
 
     fn add_class(&self, class: Class) -> Result<(), StoreError> {
-        self.backend.classes.pin().insert(class.id.clone(), class);
-        Ok(())
+        insert_entity(&self.backend, &self.backend.classes, class.id.clone(), class)
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        let revision = class.get_revision();
+        insert_entity_if_revision(&self.backend, &self.backend.classes, class.id.clone(), class, Some(revision))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.transaction(|| {
+            let Some(class) = remove_entity(&self.backend, &self.backend.classes, id)? else {
+                return Ok(None);
+            };
+
+            update_entities(&self.backend, &self.backend.classes, |class| class.remove_class_id(id))?;
+            update_entities(&self.backend, &self.backend.vertex_templates, |vertex_template| {
+                vertex_template.template.remove_class_id(id)
+            })?;
+            update_entities(&self.backend, &self.backend.edge_templates, |edge_template| {
+                edge_template.template.remove_class_id(id)
+            })?;
+            update_entities(&self.backend, &self.backend.vertexes, |vertex| vertex.instance.remove_class_id(id))?;
+            update_entities(&self.backend, &self.backend.edges, |edge| edge.instance.remove_class_id(id))?;
+
+            Ok(Some(class))
+        })
     }
 
     fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
@@ -44,8 +327,69 @@ impl Store for InMemoryStore {
     }
 
     fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
-        self.backend.vertex_templates.pin().insert(vertex_template.template.id.clone(), vertex_template);
-        Ok(())
+        insert_entity(
+            &self.backend,
+            &self.backend.vertex_templates,
+            vertex_template.template.id.clone(),
+            vertex_template,
+        )
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        let revision = vertex_template.get_revision();
+        insert_entity_if_revision(
+            &self.backend,
+            &self.backend.vertex_templates,
+            vertex_template.template.id.clone(),
+            vertex_template,
+            Some(revision),
+        )
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex_template) = remove_entity(&self.backend, &self.backend.vertex_templates, id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_template_id in &vertex_template.contained_vertex_template_ids {
+                self.remove_vertex_template(contained_vertex_template_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_template_ids
+            let outgoing_edge_template_ids: Vec<_> = self
+                .backend
+                .edge_templates
+                .pin()
+                .values()
+                .filter(|edge_template| edge_template.containing_source_vertex_template_id == *id)
+                .map(|edge_template| edge_template.template.id.clone())
+                .chain(vertex_template.outgoing_edge_template_ids.iter().cloned())
+                .collect();
+
+            for outgoing_edge_template_id in &outgoing_edge_template_ids {
+                self.remove_edge_template(outgoing_edge_template_id)?;
+            }
+
+            if let Some(containing_vertex_template_id) = &vertex_template.containing_vertex_template_id {
+                update_entity(
+                    &self.backend,
+                    &self.backend.vertex_templates,
+                    containing_vertex_template_id,
+                    |containing_vertex_template| {
+                        containing_vertex_template
+                            .contained_vertex_template_ids
+                            .retain(|contained_vertex_template_id| contained_vertex_template_id != id);
+                    },
+                )?;
+            }
+
+            update_entities(&self.backend, &self.backend.vertexes, |vertex| {
+                vertex.instance.remove_origin_template_id(id)
+            })?;
+
+            Ok(Some(vertex_template))
+        })
     }
 
     fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
@@ -53,21 +397,117 @@ impl Store for InMemoryStore {
     }
 
     fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
-        self.backend.edge_templates.pin().insert(edge_template.template.id.clone(), edge_template);
-        Ok(())
+        insert_entity(&self.backend, &self.backend.edge_templates, edge_template.template.id.clone(), edge_template)
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        let revision = edge_template.get_revision();
+        insert_entity_if_revision(
+            &self.backend,
+            &self.backend.edge_templates,
+            edge_template.template.id.clone(),
+            edge_template,
+            Some(revision),
+        )
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(edge_template) = remove_entity(&self.backend, &self.backend.edge_templates, id)? else {
+                return Ok(None);
+            };
+
+            update_entity(
+                &self.backend,
+                &self.backend.vertex_templates,
+                &edge_template.containing_source_vertex_template_id,
+                |vertex_template| {
+                    vertex_template
+                        .outgoing_edge_template_ids
+                        .retain(|outgoing_edge_template_id| outgoing_edge_template_id != id);
+                },
+            )?;
+
+            update_entities(&self.backend, &self.backend.edges, |edge| edge.instance.remove_origin_template_id(id))?;
+
+            Ok(Some(edge_template))
+        })
     }
 
     fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
         Ok(self.backend.vertexes.pin().get(id).cloned())
     }
 
-    fn get_vertexes(&self, _directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
-        Ok(self.backend.vertexes.pin().values().cloned().collect())
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        Ok(match kind {
+            Kind::Class => query_entities(&self.backend, &self.backend.classes, kind, query)?,
+            Kind::VertexTemplate => query_entities(&self.backend, &self.backend.vertex_templates, kind, query)?,
+            Kind::EdgeTemplate => query_entities(&self.backend, &self.backend.edge_templates, kind, query)?,
+            Kind::Vertex => query_entities(&self.backend, &self.backend.vertexes, kind, query)?,
+            Kind::Edge => query_entities(&self.backend, &self.backend.edges, kind, query)?,
+        })
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        let query = StoreQuery::default().with_directories(directories);
+        let candidates = self.backend.indexes.read()?.get_candidates(Kind::Vertex, &query);
+        let vertexes = self.backend.vertexes.pin();
+
+        Ok(match candidates {
+            Some(ids) => ids.iter().filter_map(|id| vertexes.get(id)).cloned().collect(),
+            None => vertexes.values().cloned().collect(),
+        })
     }
 
     fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
-        self.backend.vertexes.pin().insert(vertex.instance.id.clone(), vertex);
-        Ok(())
+        insert_entity(&self.backend, &self.backend.vertexes, vertex.instance.id.clone(), vertex)
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        let revision = vertex.get_revision();
+        insert_entity_if_revision(
+            &self.backend,
+            &self.backend.vertexes,
+            vertex.instance.id.clone(),
+            vertex,
+            Some(revision),
+        )
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex) = remove_entity(&self.backend, &self.backend.vertexes, id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_id in &vertex.contained_vertex_ids {
+                self.remove_vertex(contained_vertex_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_ids and incoming_edge_ids
+            let edge_ids: Vec<_> = self
+                .backend
+                .edges
+                .pin()
+                .values()
+                .filter(|edge| (edge.source_vertex_id == *id) || (edge.target_vertex_id == *id))
+                .map(|edge| edge.instance.id.clone())
+                .chain(vertex.outgoing_edge_ids.iter().cloned())
+                .chain(vertex.incoming_edge_ids.iter().cloned())
+                .collect();
+
+            for edge_id in &edge_ids {
+                self.remove_edge(edge_id)?;
+            }
+
+            if let Some(containing_vertex_id) = &vertex.containing_vertex_id {
+                update_entity(&self.backend, &self.backend.vertexes, containing_vertex_id, |containing_vertex| {
+                    containing_vertex.contained_vertex_ids.retain(|contained_vertex_id| contained_vertex_id != id);
+                })?;
+            }
+
+            Ok(Some(vertex))
+        })
     }
 
     fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
@@ -75,7 +515,29 @@ impl Store for InMemoryStore {
     }
 
     fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
-        self.backend.edges.pin().insert(edge.instance.id.clone(), edge);
-        Ok(())
+        insert_entity(&self.backend, &self.backend.edges, edge.instance.id.clone(), edge)
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        let revision = edge.get_revision();
+        insert_entity_if_revision(&self.backend, &self.backend.edges, edge.instance.id.clone(), edge, Some(revision))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.transaction(|| {
+            let Some(edge) = remove_entity(&self.backend, &self.backend.edges, id)? else {
+                return Ok(None);
+            };
+
+            update_entity(&self.backend, &self.backend.vertexes, &edge.source_vertex_id, |source_vertex| {
+                source_vertex.outgoing_edge_ids.retain(|outgoing_edge_id| outgoing_edge_id != id);
+            })?;
+
+            update_entity(&self.backend, &self.backend.vertexes, &edge.target_vertex_id, |target_vertex| {
+                target_vertex.incoming_edge_ids.retain(|incoming_edge_id| incoming_edge_id != id);
+            })?;
+
+            Ok(Some(edge))
+        })
     }
 }
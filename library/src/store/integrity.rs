@@ -0,0 +1,793 @@
+use super::{super::data::*, errors::*, query::*, store::*};
+
+use {
+    kutil::{
+        cli::depict::*,
+        std::{immutable::*, iter::*},
+    },
+    std::{collections::*, fmt, io},
+};
+
+//
+// IntegrityProblem
+//
+
+/// Integrity problem.
+#[derive(Clone, Debug)]
+pub struct IntegrityProblem {
+    /// ID of the entity with the problem.
+    pub id: ID,
+
+    /// Field with the problem.
+    pub field: String,
+
+    /// Kind of problem.
+    pub kind: IntegrityProblemKind,
+
+    /// Whether the problem was repaired.
+    pub repaired: bool,
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{} {} {}: {}", self.id.kind, self.id, self.field, self.kind)?;
+        if self.repaired {
+            write!(formatter, " (repaired)")?;
+        }
+        Ok(())
+    }
+}
+
+//
+// IntegrityProblemKind
+//
+
+/// Kind of [IntegrityProblem].
+#[derive(Clone, Debug)]
+pub enum IntegrityProblemKind {
+    /// Refers to an entity that does not exist.
+    Dangling(ID),
+
+    /// Refers to an entity of the wrong kind. The second value is the expected kind.
+    WrongKind(ID, Kind),
+
+    /// Refers to an entity that does not refer back to it.
+    Asymmetric(ID),
+
+    /// Containment cycle, starting with the entity itself.
+    Cycle(Vec<ID>),
+}
+
+impl fmt::Display for IntegrityProblemKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dangling(id) => write!(formatter, "dangling reference to {} {}", id.kind, id),
+            Self::WrongKind(id, kind) => write!(formatter, "reference to {} {} instead of {}", id.kind, id, kind),
+            Self::Asymmetric(id) => write!(formatter, "{} {} does not refer back", id.kind, id),
+            Self::Cycle(ids) => {
+                write!(formatter, "containment cycle: ")?;
+                for id in ids {
+                    write!(formatter, "{} -> ", id)?;
+                }
+                match ids.first() {
+                    Some(id) => write!(formatter, "{}", id),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+//
+// IntegrityReport
+//
+
+/// Integrity report.
+#[derive(Clone, Debug, Default)]
+pub struct IntegrityReport {
+    /// Problems.
+    pub problems: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    /// True if there are no problems.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// True if all the problems were repaired.
+    pub fn is_repaired(&self) -> bool {
+        self.problems.iter().all(|problem| problem.repaired)
+    }
+}
+
+impl Depict for IntegrityReport {
+    fn depict<WriteT>(&self, writer: &mut WriteT, context: &DepictionContext) -> io::Result<()>
+    where
+        WriteT: io::Write,
+    {
+        for (problem, first) in IterateWithFirst::new(&self.problems) {
+            context.separate_or_indent(writer, first)?;
+            problem.id.kind.depict(writer, context)?;
+            write!(writer, " ")?;
+            problem.id.depict(writer, context)?;
+            write!(writer, " {}", context.theme.meta(&problem.field))?;
+            context.theme.write_delimiter(writer, ':')?;
+            write!(writer, " {}", context.theme.error(&problem.kind))?;
+            if problem.repaired {
+                write!(writer, " {}", context.theme.meta("(repaired)"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//
+// StoreIntegrityUtilities
+//
+
+/// Integrity utilities for [Store].
+pub trait StoreIntegrityUtilities {
+    /// Check the integrity of the entities that match a query.
+    ///
+    /// Checks that references point at existing entities of the right kind, that vertexes and
+    /// vertex templates are not contained in themselves, and that containment and edge links are
+    /// symmetric. References are checked against the entire store.
+    ///
+    /// When repairing, broken references are removed, missing back references are added, and
+    /// containment cycles are broken. Edges and edge templates with a broken required reference are
+    /// removed. All repairs are done in a single transaction.
+    fn check_integrity(&self, query: &StoreQuery, repair: bool) -> Result<IntegrityReport, StoreError>;
+}
+
+impl<StoreT> StoreIntegrityUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn check_integrity(&self, query: &StoreQuery, repair: bool) -> Result<IntegrityReport, StoreError> {
+        let mut checker = IntegrityChecker { store: self, repair, report: Default::default() };
+
+        if repair {
+            self.transaction(|| checker.check(query))?;
+        } else {
+            checker.check(query)?;
+        }
+
+        Ok(checker.report)
+    }
+}
+
+//
+// IntegrityChecker
+//
+
+struct IntegrityChecker<'own, StoreT> {
+    store: &'own StoreT,
+    repair: bool,
+    report: IntegrityReport,
+}
+
+impl<'own, StoreT> IntegrityChecker<'own, StoreT>
+where
+    StoreT: Store,
+{
+    fn check(&mut self, query: &StoreQuery) -> Result<(), StoreError> {
+        for kind in [Kind::Class, Kind::VertexTemplate, Kind::EdgeTemplate, Kind::Vertex, Kind::Edge] {
+            // Entities are read again when checked, because repairs may have changed them
+            let ids: Vec<_> =
+                self.store.get_entities(kind, query)?.iter().map(|entity| entity.get_id().clone()).collect();
+
+            for id in &ids {
+                match kind {
+                    Kind::Class => self.check_class(id)?,
+                    Kind::VertexTemplate => self.check_vertex_template(id)?,
+                    Kind::EdgeTemplate => self.check_edge_template(id)?,
+                    Kind::Vertex => self.check_vertex(id)?,
+                    Kind::Edge => self.check_edge(id)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_class(&mut self, id: &ID) -> Result<(), StoreError> {
+        let Some(mut class) = self.store.get_class(id)? else {
+            return Ok(());
+        };
+
+        let mut changed = self.check_references(id, "parent_class_ids", &mut class.parent_class_ids, Kind::Class)?;
+        changed |= self.check_references(id, "child_class_ids", &mut class.child_class_ids, Kind::Class)?;
+
+        if changed {
+            self.store.add_class(class)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_vertex_template(&mut self, id: &ID) -> Result<(), StoreError> {
+        let Some(mut vertex_template) = self.store.get_vertex_template(id)? else {
+            return Ok(());
+        };
+
+        let mut changed = self.check_template(&mut vertex_template.template)?;
+
+        // Containing vertex template
+
+        changed |= self.check_optional_reference(
+            id,
+            "containing_vertex_template_id",
+            &mut vertex_template.containing_vertex_template_id,
+            Kind::VertexTemplate,
+        )?;
+
+        let cycle = self.find_cycle(id, |id| {
+            Ok(self
+                .store
+                .get_vertex_template(id)?
+                .and_then(|vertex_template| vertex_template.containing_vertex_template_id))
+        })?;
+
+        match cycle {
+            Some(cycle) => {
+                if is_reported_by(id, &cycle) {
+                    self.report(id, "containing_vertex_template_id", IntegrityProblemKind::Cycle(cycle), self.repair);
+                    if self.repair
+                        && let Some(containing_vertex_template_id) =
+                            vertex_template.containing_vertex_template_id.take()
+                    {
+                        self.update_vertex_template(
+                            Some(&mut vertex_template),
+                            &containing_vertex_template_id,
+                            |containing_vertex_template| {
+                                containing_vertex_template.contained_vertex_template_ids.retain(|id_| id_ != id)
+                            },
+                        )?;
+                        changed = true;
+                    }
+                }
+            }
+
+            None => {
+                if let Some(containing_vertex_template_id) = vertex_template.containing_vertex_template_id.clone()
+                    && let Some(containing_vertex_template) =
+                        self.store.get_vertex_template(&containing_vertex_template_id)?
+                    && !containing_vertex_template.contained_vertex_template_ids.contains(id)
+                {
+                    self.report(
+                        id,
+                        "containing_vertex_template_id",
+                        IntegrityProblemKind::Asymmetric(containing_vertex_template_id.clone()),
+                        self.repair,
+                    );
+                    if self.repair {
+                        self.update_vertex_template(
+                            Some(&mut vertex_template),
+                            &containing_vertex_template_id,
+                            |containing_vertex_template| {
+                                containing_vertex_template.contained_vertex_template_ids.push(id.clone())
+                            },
+                        )?;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // Contained vertex templates
+
+        changed |= self.check_references(
+            id,
+            "contained_vertex_template_ids",
+            &mut vertex_template.contained_vertex_template_ids,
+            Kind::VertexTemplate,
+        )?;
+
+        for contained_vertex_template_id in vertex_template.contained_vertex_template_ids.clone() {
+            if let Some(contained_vertex_template) = self.store.get_vertex_template(&contained_vertex_template_id)?
+                && contained_vertex_template.containing_vertex_template_id.as_ref() != Some(id)
+            {
+                self.report(
+                    id,
+                    "contained_vertex_template_ids",
+                    IntegrityProblemKind::Asymmetric(contained_vertex_template_id.clone()),
+                    self.repair,
+                );
+                if self.repair {
+                    // Adopt it if it's not contained elsewhere
+                    if contained_vertex_template.containing_vertex_template_id.is_none() {
+                        self.update_vertex_template(
+                            Some(&mut vertex_template),
+                            &contained_vertex_template_id,
+                            |contained_vertex_template| {
+                                contained_vertex_template.containing_vertex_template_id = Some(id.clone())
+                            },
+                        )?;
+                    } else {
+                        vertex_template.contained_vertex_template_ids.retain(|id| *id != contained_vertex_template_id);
+                    }
+                    changed = true;
+                }
+            }
+        }
+
+        // Outgoing edge templates
+
+        changed |= self.check_references(
+            id,
+            "outgoing_edge_template_ids",
+            &mut vertex_template.outgoing_edge_template_ids,
+            Kind::EdgeTemplate,
+        )?;
+
+        for outgoing_edge_template_id in vertex_template.outgoing_edge_template_ids.clone() {
+            if let Some(outgoing_edge_template) = self.store.get_edge_template(&outgoing_edge_template_id)?
+                && outgoing_edge_template.containing_source_vertex_template_id != *id
+            {
+                self.report(
+                    id,
+                    "outgoing_edge_template_ids",
+                    IntegrityProblemKind::Asymmetric(outgoing_edge_template_id.clone()),
+                    self.repair,
+                );
+                if self.repair {
+                    vertex_template.outgoing_edge_template_ids.retain(|id| *id != outgoing_edge_template_id);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.store.add_vertex_template(vertex_template)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_edge_template(&mut self, id: &ID) -> Result<(), StoreError> {
+        let Some(mut edge_template) = self.store.get_edge_template(id)? else {
+            return Ok(());
+        };
+
+        let changed = self.check_template(&mut edge_template.template)?;
+
+        // The containing source vertex template is required, so we can only repair by removing
+        let containing_source_vertex_template_id = edge_template.containing_source_vertex_template_id.clone();
+        if !self.check_reference(
+            id,
+            "containing_source_vertex_template_id",
+            &containing_source_vertex_template_id,
+            Kind::VertexTemplate,
+            self.repair,
+        )? {
+            if self.repair {
+                self.store.remove_edge_template(id)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(containing_source_vertex_template) =
+            self.store.get_vertex_template(&containing_source_vertex_template_id)?
+            && !containing_source_vertex_template.outgoing_edge_template_ids.contains(id)
+        {
+            self.report(
+                id,
+                "containing_source_vertex_template_id",
+                IntegrityProblemKind::Asymmetric(containing_source_vertex_template_id.clone()),
+                self.repair,
+            );
+            if self.repair {
+                self.update_vertex_template(
+                    None,
+                    &containing_source_vertex_template_id,
+                    |containing_source_vertex_template| {
+                        containing_source_vertex_template.outgoing_edge_template_ids.push(id.clone())
+                    },
+                )?;
+            }
+        }
+
+        // We don't know which vertex to select instead, so we can't repair
+        if let VertexSelector::VertexID(target_vertex_id) = &edge_template.target_selector {
+            self.check_reference(id, "target_selector", target_vertex_id, Kind::Vertex, false)?;
+        }
+
+        if changed {
+            self.store.add_edge_template(edge_template)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_vertex(&mut self, id: &ID) -> Result<(), StoreError> {
+        let Some(mut vertex) = self.store.get_vertex(id)? else {
+            return Ok(());
+        };
+
+        let mut changed = self.check_instance(&mut vertex.instance, Kind::VertexTemplate)?;
+
+        // Containing vertex
+
+        changed |=
+            self.check_optional_reference(id, "containing_vertex_id", &mut vertex.containing_vertex_id, Kind::Vertex)?;
+
+        let cycle =
+            self.find_cycle(id, |id| Ok(self.store.get_vertex(id)?.and_then(|vertex| vertex.containing_vertex_id)))?;
+
+        match cycle {
+            Some(cycle) => {
+                if is_reported_by(id, &cycle) {
+                    self.report(id, "containing_vertex_id", IntegrityProblemKind::Cycle(cycle), self.repair);
+                    if self.repair
+                        && let Some(containing_vertex_id) = vertex.containing_vertex_id.take()
+                    {
+                        self.update_vertex(Some(&mut vertex), &containing_vertex_id, |containing_vertex| {
+                            containing_vertex.contained_vertex_ids.retain(|id_| id_ != id)
+                        })?;
+                        changed = true;
+                    }
+                }
+            }
+
+            None => {
+                if let Some(containing_vertex_id) = vertex.containing_vertex_id.clone()
+                    && let Some(containing_vertex) = self.store.get_vertex(&containing_vertex_id)?
+                    && !containing_vertex.contained_vertex_ids.contains(id)
+                {
+                    self.report(
+                        id,
+                        "containing_vertex_id",
+                        IntegrityProblemKind::Asymmetric(containing_vertex_id.clone()),
+                        self.repair,
+                    );
+                    if self.repair {
+                        self.update_vertex(Some(&mut vertex), &containing_vertex_id, |containing_vertex| {
+                            containing_vertex.contained_vertex_ids.push(id.clone())
+                        })?;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // Contained vertexes
+
+        changed |= self.check_references(id, "contained_vertex_ids", &mut vertex.contained_vertex_ids, Kind::Vertex)?;
+
+        for contained_vertex_id in vertex.contained_vertex_ids.clone() {
+            if let Some(contained_vertex) = self.store.get_vertex(&contained_vertex_id)?
+                && contained_vertex.containing_vertex_id.as_ref() != Some(id)
+            {
+                self.report(
+                    id,
+                    "contained_vertex_ids",
+                    IntegrityProblemKind::Asymmetric(contained_vertex_id.clone()),
+                    self.repair,
+                );
+                if self.repair {
+                    // Adopt it if it's not contained elsewhere
+                    if contained_vertex.containing_vertex_id.is_none() {
+                        self.update_vertex(Some(&mut vertex), &contained_vertex_id, |contained_vertex| {
+                            contained_vertex.containing_vertex_id = Some(id.clone())
+                        })?;
+                    } else {
+                        vertex.contained_vertex_ids.retain(|id| *id != contained_vertex_id);
+                    }
+                    changed = true;
+                }
+            }
+        }
+
+        // Outgoing edges
+
+        changed |= self.check_references(id, "outgoing_edge_ids", &mut vertex.outgoing_edge_ids, Kind::Edge)?;
+
+        for outgoing_edge_id in vertex.outgoing_edge_ids.clone() {
+            if let Some(outgoing_edge) = self.store.get_edge(&outgoing_edge_id)?
+                && outgoing_edge.source_vertex_id != *id
+            {
+                self.report(
+                    id,
+                    "outgoing_edge_ids",
+                    IntegrityProblemKind::Asymmetric(outgoing_edge_id.clone()),
+                    self.repair,
+                );
+                if self.repair {
+                    vertex.outgoing_edge_ids.retain(|id| *id != outgoing_edge_id);
+                    changed = true;
+                }
+            }
+        }
+
+        // Incoming edges
+
+        changed |= self.check_references(id, "incoming_edge_ids", &mut vertex.incoming_edge_ids, Kind::Edge)?;
+
+        for incoming_edge_id in vertex.incoming_edge_ids.clone() {
+            if let Some(incoming_edge) = self.store.get_edge(&incoming_edge_id)?
+                && incoming_edge.target_vertex_id != *id
+            {
+                self.report(
+                    id,
+                    "incoming_edge_ids",
+                    IntegrityProblemKind::Asymmetric(incoming_edge_id.clone()),
+                    self.repair,
+                );
+                if self.repair {
+                    vertex.incoming_edge_ids.retain(|id| *id != incoming_edge_id);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.store.add_vertex(vertex)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_edge(&mut self, id: &ID) -> Result<(), StoreError> {
+        let Some(mut edge) = self.store.get_edge(id)? else {
+            return Ok(());
+        };
+
+        let changed = self.check_instance(&mut edge.instance, Kind::EdgeTemplate)?;
+
+        // The source and target vertexes are required, so we can only repair by removing
+        let source_vertex_id = edge.source_vertex_id.clone();
+        let target_vertex_id = edge.target_vertex_id.clone();
+        let source_vertex_ok =
+            self.check_reference(id, "source_vertex_id", &source_vertex_id, Kind::Vertex, self.repair)?;
+        let target_vertex_ok =
+            self.check_reference(id, "target_vertex_id", &target_vertex_id, Kind::Vertex, self.repair)?;
+        if !source_vertex_ok || !target_vertex_ok {
+            if self.repair {
+                self.store.remove_edge(id)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(source_vertex) = self.store.get_vertex(&source_vertex_id)?
+            && !source_vertex.outgoing_edge_ids.contains(id)
+        {
+            self.report(
+                id,
+                "source_vertex_id",
+                IntegrityProblemKind::Asymmetric(source_vertex_id.clone()),
+                self.repair,
+            );
+            if self.repair {
+                self.update_vertex(None, &source_vertex_id, |source_vertex| {
+                    source_vertex.outgoing_edge_ids.push(id.clone())
+                })?;
+            }
+        }
+
+        if let Some(target_vertex) = self.store.get_vertex(&target_vertex_id)?
+            && !target_vertex.incoming_edge_ids.contains(id)
+        {
+            self.report(
+                id,
+                "target_vertex_id",
+                IntegrityProblemKind::Asymmetric(target_vertex_id.clone()),
+                self.repair,
+            );
+            if self.repair {
+                self.update_vertex(None, &target_vertex_id, |target_vertex| {
+                    target_vertex.incoming_edge_ids.push(id.clone())
+                })?;
+            }
+        }
+
+        if changed {
+            self.store.add_edge(edge)?;
+        }
+
+        Ok(())
+    }
+
+    // Check the class IDs of a template and its property templates.
+    //
+    // Returns true if repaired.
+    fn check_template(&mut self, template: &mut Template) -> Result<bool, StoreError> {
+        let id = template.id.clone();
+        let mut changed = self.check_references(&id, "class_ids", &mut template.class_ids, Kind::Class)?;
+        changed |= self.check_properties(&id, "property_templates", &mut template.property_templates)?;
+        Ok(changed)
+    }
+
+    // Check the origin template ID and class IDs of an instance and its properties.
+    //
+    // Returns true if repaired.
+    fn check_instance(&mut self, instance: &mut Instance, origin_template_kind: Kind) -> Result<bool, StoreError> {
+        let id = instance.id.clone();
+        let mut changed = self.check_optional_reference(
+            &id,
+            "origin_template_id",
+            &mut instance.origin_template_id,
+            origin_template_kind,
+        )?;
+        changed |= self.check_references(&id, "class_ids", &mut instance.class_ids, Kind::Class)?;
+        changed |= self.check_properties(&id, "properties", &mut instance.properties)?;
+        Ok(changed)
+    }
+
+    // Check the class IDs of properties.
+    //
+    // Returns true if repaired.
+    fn check_properties(
+        &mut self,
+        id: &ID,
+        field: &str,
+        properties: &mut BTreeMap<ByteString, Property>,
+    ) -> Result<bool, StoreError> {
+        let mut changed = false;
+        for (name, property) in properties {
+            let field = format!("{}.{}.class_ids", field, name);
+            changed |= self.check_references(id, &field, &mut property.class_ids, Kind::Class)?;
+        }
+        Ok(changed)
+    }
+
+    // Check references, removing the broken ones if repairing.
+    //
+    // Returns true if repaired.
+    fn check_references(
+        &mut self,
+        id: &ID,
+        field: &str,
+        target_ids: &mut Vec<ID>,
+        kind: Kind,
+    ) -> Result<bool, StoreError> {
+        let mut retained_target_ids = Vec::with_capacity(target_ids.len());
+        for target_id in target_ids.iter() {
+            if self.check_reference(id, field, target_id, kind, self.repair)? || !self.repair {
+                retained_target_ids.push(target_id.clone());
+            }
+        }
+
+        let changed = retained_target_ids.len() != target_ids.len();
+        *target_ids = retained_target_ids;
+        Ok(changed)
+    }
+
+    // Check an optional reference, removing it if it's broken and we're repairing.
+    //
+    // Returns true if repaired.
+    fn check_optional_reference(
+        &mut self,
+        id: &ID,
+        field: &str,
+        target_id: &mut Option<ID>,
+        kind: Kind,
+    ) -> Result<bool, StoreError> {
+        if let Some(target_id_) = target_id
+            && !self.check_reference(id, field, target_id_, kind, self.repair)?
+            && self.repair
+        {
+            *target_id = None;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // Check that a reference points at an existing entity of the right kind, reporting the problem
+    // if it doesn't.
+    //
+    // Returns true if OK.
+    fn check_reference(
+        &mut self,
+        id: &ID,
+        field: &str,
+        target_id: &ID,
+        kind: Kind,
+        repaired: bool,
+    ) -> Result<bool, StoreError> {
+        let problem = if target_id.kind != kind {
+            IntegrityProblemKind::WrongKind(target_id.clone(), kind)
+        } else if !self.exists(target_id)? {
+            IntegrityProblemKind::Dangling(target_id.clone())
+        } else {
+            return Ok(true);
+        };
+
+        self.report(id, field, problem, repaired);
+        Ok(false)
+    }
+
+    // Follow the containing IDs.
+    //
+    // Returns the cycle if the entity is in one.
+    fn find_cycle<GetContainingIdT>(
+        &self,
+        id: &ID,
+        get_containing_id: GetContainingIdT,
+    ) -> Result<Option<Vec<ID>>, StoreError>
+    where
+        GetContainingIdT: Fn(&ID) -> Result<Option<ID>, StoreError>,
+    {
+        let mut cycle = vec![id.clone()];
+        let mut containing_id = get_containing_id(id)?;
+
+        while let Some(containing_id_) = containing_id {
+            if containing_id_ == *id {
+                return Ok(Some(cycle));
+            }
+
+            // A cycle that doesn't include us
+            if cycle.contains(&containing_id_) {
+                return Ok(None);
+            }
+
+            containing_id = get_containing_id(&containing_id_)?;
+            cycle.push(containing_id_);
+        }
+
+        Ok(None)
+    }
+
+    // Update a vertex template, or apply the update to the one being checked if it has the ID.
+    fn update_vertex_template<UpdateT>(
+        &self,
+        vertex_template: Option<&mut VertexTemplate>,
+        id: &ID,
+        update: UpdateT,
+    ) -> Result<(), StoreError>
+    where
+        UpdateT: FnOnce(&mut VertexTemplate),
+    {
+        match vertex_template {
+            Some(vertex_template) if vertex_template.template.id == *id => update(vertex_template),
+
+            _ => {
+                if let Some(mut vertex_template) = self.store.get_vertex_template(id)? {
+                    update(&mut vertex_template);
+                    self.store.add_vertex_template(vertex_template)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Update a vertex, or apply the update to the one being checked if it has the ID.
+    fn update_vertex<UpdateT>(&self, vertex: Option<&mut Vertex>, id: &ID, update: UpdateT) -> Result<(), StoreError>
+    where
+        UpdateT: FnOnce(&mut Vertex),
+    {
+        match vertex {
+            Some(vertex) if vertex.instance.id == *id => update(vertex),
+
+            _ => {
+                if let Some(mut vertex) = self.store.get_vertex(id)? {
+                    update(&mut vertex);
+                    self.store.add_vertex(vertex)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn exists(&self, id: &ID) -> Result<bool, StoreError> {
+        Ok(match id.kind {
+            Kind::Class => self.store.get_class(id)?.is_some(),
+            Kind::VertexTemplate => self.store.get_vertex_template(id)?.is_some(),
+            Kind::EdgeTemplate => self.store.get_edge_template(id)?.is_some(),
+            Kind::Vertex => self.store.get_vertex(id)?.is_some(),
+            Kind::Edge => self.store.get_edge(id)?.is_some(),
+        })
+    }
+
+    fn report(&mut self, id: &ID, field: &str, kind: IntegrityProblemKind, repaired: bool) {
+        self.report.problems.push(IntegrityProblem { id: id.clone(), field: field.into(), kind, repaired });
+    }
+}
+
+// Utils
+
+// A cycle is reported only once, by the entity with the lowest ID in it.
+fn is_reported_by(id: &ID, cycle: &[ID]) -> bool {
+    let id = id.to_string();
+    cycle.iter().all(|id_| id_.to_string() >= id)
+}
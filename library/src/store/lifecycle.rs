@@ -0,0 +1,108 @@
+use super::{super::data::*, class_hierarchy::*, errors::*, store::*};
+
+//
+// StoreLifecycleUtilities
+//
+
+/// Lifecycle utilities for [Store].
+///
+/// The allowed lifecycle transitions of a vertex or an edge are those declared by its classes and
+/// their ancestors combined. Transitions that are not declared are not allowed, except that a
+/// transition to [LifecycleState::Error] is always allowed, so that failures can be recorded.
+pub trait StoreLifecycleUtilities {
+    /// Allowed lifecycle transitions of classes, including those of their ancestors.
+    fn get_lifecycle_transitions(&self, class_ids: &[ID]) -> Result<Vec<LifecycleTransition>, StoreError>;
+
+    /// Allowed lifecycle transitions from the current lifecycle state of a vertex or an edge,
+    /// including the transition to [LifecycleState::Error].
+    fn get_allowed_lifecycle_transitions(&self, id: &ID) -> Result<Vec<LifecycleTransition>, StoreError>;
+
+    /// Transition a vertex or an edge to a lifecycle state.
+    ///
+    /// The transition is appended to the lifecycle history. Fails with
+    /// [StoreError::InvalidTransition] if the transition is not allowed.
+    fn transition_lifecycle(&self, id: &ID, to: LifecycleState) -> Result<LifecycleTransition, StoreError>;
+}
+
+impl<StoreT> StoreLifecycleUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn get_lifecycle_transitions(&self, class_ids: &[ID]) -> Result<Vec<LifecycleTransition>, StoreError> {
+        let mut lifecycle_transitions = Vec::default();
+
+        for class_id in self.get_inherited_class_ids(class_ids)? {
+            if let Some(class) = self.get_class(&class_id)? {
+                for lifecycle_transition in class.lifecycle_transitions {
+                    if !lifecycle_transitions.contains(&lifecycle_transition) {
+                        lifecycle_transitions.push(lifecycle_transition);
+                    }
+                }
+            }
+        }
+
+        Ok(lifecycle_transitions)
+    }
+
+    fn get_allowed_lifecycle_transitions(&self, id: &ID) -> Result<Vec<LifecycleTransition>, StoreError> {
+        let instance = match id.kind {
+            Kind::Vertex => self.get_vertex(id)?.map(|vertex| vertex.instance),
+            Kind::Edge => self.get_edge(id)?.map(|edge| edge.instance),
+            kind => return Err(StoreError::ID(format!("not a vertex or an edge: {}", kind))),
+        }
+        .ok_or_else(|| StoreError::ID(format!("not found: {}", id)))?;
+
+        let mut lifecycle_transitions = self.get_lifecycle_transitions(&instance.class_ids)?;
+        lifecycle_transitions.retain(|lifecycle_transition| lifecycle_transition.from == instance.lifecycle_state);
+
+        let error_transition = LifecycleTransition::new(instance.lifecycle_state, LifecycleState::Error);
+        if !lifecycle_transitions.contains(&error_transition) {
+            lifecycle_transitions.push(error_transition);
+        }
+
+        Ok(lifecycle_transitions)
+    }
+
+    fn transition_lifecycle(&self, id: &ID, to: LifecycleState) -> Result<LifecycleTransition, StoreError> {
+        self.transaction(|| match id.kind {
+            Kind::Vertex => {
+                let mut vertex = self.get_vertex(id)?.ok_or_else(|| StoreError::ID(format!("not found: {}", id)))?;
+                let transition = apply_transition(self, &mut vertex.instance, to)?;
+                self.update_vertex_if_revision(vertex)?;
+                Ok(transition)
+            }
+
+            Kind::Edge => {
+                let mut edge = self.get_edge(id)?.ok_or_else(|| StoreError::ID(format!("not found: {}", id)))?;
+                let transition = apply_transition(self, &mut edge.instance, to)?;
+                self.update_edge_if_revision(edge)?;
+                Ok(transition)
+            }
+
+            kind => Err(StoreError::ID(format!("not a vertex or an edge: {}", kind))),
+        })
+    }
+}
+
+// Utils
+
+// Checks that the transition is allowed and applies it to the instance. Transitions to the error
+// state are always allowed.
+fn apply_transition<StoreT>(
+    store: &StoreT,
+    instance: &mut Instance,
+    to: LifecycleState,
+) -> Result<LifecycleTransition, StoreError>
+where
+    StoreT: Store,
+{
+    let transition = LifecycleTransition::new(instance.lifecycle_state, to);
+
+    if (to != LifecycleState::Error) && !store.get_lifecycle_transitions(&instance.class_ids)?.contains(&transition) {
+        return Err(StoreError::InvalidTransition(format!("{}: {}", instance.id, transition)));
+    }
+
+    instance.lifecycle_state = to;
+    instance.lifecycle_history.push(transition);
+    Ok(transition)
+}
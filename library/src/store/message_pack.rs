@@ -0,0 +1,126 @@
+use compris::{annotate::*, normal::*};
+
+// Decode MessagePack.
+//
+// Compris's MessagePack parser expects markers before the lengths of strings, blobs, and
+// collections (and before the values of integers) that don't fit in their own marker, so it fails
+// on long strings and large integers. We decode ourselves. Encoding is left to Compris.
+pub(crate) fn decode_message_pack(bytes: &[u8]) -> Result<Variant<WithoutAnnotations>, String> {
+    let mut decoder = MessagePackDecoder { bytes };
+    let variant = decoder.decode()?;
+    if !decoder.bytes.is_empty() {
+        return Err(format!("MessagePack: {} trailing bytes", decoder.bytes.len()));
+    }
+    Ok(variant)
+}
+
+//
+// MessagePackDecoder
+//
+
+struct MessagePackDecoder<'own> {
+    bytes: &'own [u8],
+}
+
+impl<'own> MessagePackDecoder<'own> {
+    fn decode(&mut self) -> Result<Variant<WithoutAnnotations>, String> {
+        let marker = self.read_unsigned(1)? as u8;
+        Ok(match marker {
+            0x00..=0x7f => UnsignedInteger::from(marker as u64).into(),
+            0x80..=0x8f => self.decode_map((marker & 0x0f) as usize)?,
+            0x90..=0x9f => self.decode_list((marker & 0x0f) as usize)?,
+            0xa0..=0xbf => self.decode_text((marker & 0x1f) as usize)?,
+            0xc0 => Null::default().into(),
+            0xc2 => Boolean::from(false).into(),
+            0xc3 => Boolean::from(true).into(),
+
+            0xc4..=0xc6 => {
+                let length = self.read_length(marker - 0xc4)?;
+                Blob::from(self.read_bytes(length)?.to_vec()).into()
+            }
+
+            0xc7..=0xc9 => {
+                let length = self.read_length(marker - 0xc7)?;
+                self.decode_extension(length)?
+            }
+
+            0xca => Float::from(f32::from_bits(self.read_unsigned(4)? as u32) as f64).into(),
+            0xcb => Float::from(f64::from_bits(self.read_unsigned(8)?)).into(),
+            0xcc..=0xcf => UnsignedInteger::from(self.read_unsigned(1 << (marker - 0xcc))?).into(),
+            0xd0..=0xd3 => Integer::from(self.read_signed(1 << (marker - 0xd0))?).into(),
+            0xd4..=0xd8 => self.decode_extension(1 << (marker - 0xd4))?,
+
+            0xd9..=0xdb => {
+                let length = self.read_length(marker - 0xd9)?;
+                self.decode_text(length)?
+            }
+
+            0xdc | 0xdd => {
+                let length = self.read_length(marker - 0xdc + 1)?;
+                self.decode_list(length)?
+            }
+
+            0xde | 0xdf => {
+                let length = self.read_length(marker - 0xde + 1)?;
+                self.decode_map(length)?
+            }
+
+            0xe0..=0xff => Integer::from(marker as i8 as i64).into(),
+
+            _ => return Err(format!("MessagePack: unsupported marker: 0x{:x}", marker)),
+        })
+    }
+
+    fn decode_text(&mut self, length: usize) -> Result<Variant<WithoutAnnotations>, String> {
+        let text = str::from_utf8(self.read_bytes(length)?).map_err(|error| format!("MessagePack: {}", error))?;
+        Ok(Text::from(text.to_string()).into())
+    }
+
+    // Extensions are decoded as blobs labeled with their type, as Compris does.
+    fn decode_extension(&mut self, length: usize) -> Result<Variant<WithoutAnnotations>, String> {
+        let label = self.read_signed(1)?;
+        let blob = Blob::from(self.read_bytes(length)?.to_vec()).with_label(Some(Label::Integer(label)));
+        Ok(blob.into())
+    }
+
+    fn decode_list(&mut self, length: usize) -> Result<Variant<WithoutAnnotations>, String> {
+        let mut list = List::default();
+        for _ in 0..length {
+            list.inner.push(self.decode()?);
+        }
+        Ok(list.into())
+    }
+
+    fn decode_map(&mut self, length: usize) -> Result<Variant<WithoutAnnotations>, String> {
+        let mut map = Map::default();
+        for _ in 0..length {
+            let key = self.decode()?;
+            map.inner.insert(key, self.decode()?);
+        }
+        Ok(map.into())
+    }
+
+    // Read a length of 1, 2, or 4 bytes (for a size of 0, 1, or 2, respectively).
+    fn read_length(&mut self, size: u8) -> Result<usize, String> {
+        Ok(self.read_unsigned(1 << size)? as usize)
+    }
+
+    fn read_signed(&mut self, size: usize) -> Result<i64, String> {
+        let shift = 64 - (8 * size as u32);
+        Ok(((self.read_unsigned(size)? << shift) as i64) >> shift)
+    }
+
+    fn read_unsigned(&mut self, size: usize) -> Result<u64, String> {
+        Ok(self.read_bytes(size)?.iter().fold(0, |unsigned, byte| (unsigned << 8) | *byte as u64))
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'own [u8], String> {
+        if length > self.bytes.len() {
+            return Err("MessagePack: unexpected end".into());
+        }
+
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,358 @@
+use super::{super::data::*, errors::*, events::*, query::*, store::*};
+
+use std::{
+    collections::*,
+    fmt::{self, Write},
+    sync::*,
+    time::*,
+};
+
+/// Upper bounds of the [OperationMetrics] latency histogram buckets, in seconds.
+pub const LATENCY_BUCKETS: [f64; 10] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1., 5.];
+
+//
+// MetricsStore
+//
+
+/// [Store] wrapper that collects metrics.
+///
+/// Clones share the same metrics.
+#[derive(Clone, Debug)]
+pub struct MetricsStore<StoreT> {
+    /// Inner.
+    pub inner: StoreT,
+
+    operations: Arc<Mutex<BTreeMap<&'static str, OperationMetrics>>>,
+}
+
+impl<StoreT> MetricsStore<StoreT>
+where
+    StoreT: Store,
+{
+    /// Constructor.
+    pub fn new(inner: StoreT) -> Self {
+        Self { inner, operations: Default::default() }
+    }
+
+    /// Snapshot of the metrics.
+    ///
+    /// Entities are counted by querying the inner store, so this can be expensive.
+    pub fn snapshot(&self) -> Result<StoreMetricsSnapshot, StoreError> {
+        let operations = self.operations.lock()?.clone();
+
+        let mut entities = Vec::default();
+        for kind in [Kind::Class, Kind::VertexTemplate, Kind::EdgeTemplate, Kind::Vertex, Kind::Edge] {
+            let mut counts = BTreeMap::<_, u64>::default();
+            for entity in self.inner.get_entities(kind, &Default::default())? {
+                *counts.entry(entity.get_id().directory.clone()).or_default() += 1;
+            }
+
+            for (directory, count) in counts {
+                entities.push(EntityCount { kind, directory, count });
+            }
+        }
+
+        Ok(StoreMetricsSnapshot { operations, entities })
+    }
+
+    /// Reset the operation metrics.
+    pub fn reset(&self) -> Result<(), StoreError> {
+        self.operations.lock()?.clear();
+        Ok(())
+    }
+
+    // Call the inner store and record the metrics for the operation.
+    fn measure<ResultT, FunctionT>(&self, operation: &'static str, function: FunctionT) -> Result<ResultT, StoreError>
+    where
+        FunctionT: FnOnce() -> Result<ResultT, StoreError>,
+    {
+        let start = Instant::now();
+        let result = function();
+        let duration = start.elapsed();
+
+        // Failing to record metrics should not fail the operation
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.entry(operation).or_default().record(duration, result.is_err());
+        }
+
+        result
+    }
+}
+
+impl<StoreT> Store for MetricsStore<StoreT>
+where
+    StoreT: Store,
+{
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.measure("begin_transaction", || self.inner.begin_transaction())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        self.measure("commit_transaction", || self.inner.commit_transaction())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        self.measure("rollback_transaction", || self.inner.rollback_transaction())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.measure("subscribe", || self.inner.subscribe(filter))
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        self.measure("create_id", || self.inner.create_id(id))
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.measure("get_next_ids", || self.inner.get_next_ids())
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        self.measure("advance_next_ids", || self.inner.advance_next_ids(next_ids))
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.measure("get_class", || self.inner.get_class(id))
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.measure("add_class", || self.inner.add_class(class))
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        self.measure("update_class_if_revision", || self.inner.update_class_if_revision(class))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.measure("remove_class", || self.inner.remove_class(id))
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.measure("get_vertex_template", || self.inner.get_vertex_template(id))
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.measure("add_vertex_template", || self.inner.add_vertex_template(vertex_template))
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        self.measure("update_vertex_template_if_revision", || {
+            self.inner.update_vertex_template_if_revision(vertex_template)
+        })
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.measure("remove_vertex_template", || self.inner.remove_vertex_template(id))
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.measure("get_edge_template", || self.inner.get_edge_template(id))
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.measure("add_edge_template", || self.inner.add_edge_template(edge_template))
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        self.measure("update_edge_template_if_revision", || self.inner.update_edge_template_if_revision(edge_template))
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.measure("remove_edge_template", || self.inner.remove_edge_template(id))
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.measure("get_vertex", || self.inner.get_vertex(id))
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        self.measure("get_entities", || self.inner.get_entities(kind, query))
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        self.measure("get_vertexes", || self.inner.get_vertexes(directories))
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.measure("add_vertex", || self.inner.add_vertex(vertex))
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        self.measure("update_vertex_if_revision", || self.inner.update_vertex_if_revision(vertex))
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.measure("remove_vertex", || self.inner.remove_vertex(id))
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.measure("get_edge", || self.inner.get_edge(id))
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.measure("add_edge", || self.inner.add_edge(edge))
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        self.measure("update_edge_if_revision", || self.inner.update_edge_if_revision(edge))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.measure("remove_edge", || self.inner.remove_edge(id))
+    }
+}
+
+//
+// OperationMetrics
+//
+
+/// Metrics for a [Store] operation.
+#[derive(Clone, Debug, Default)]
+pub struct OperationMetrics {
+    /// Number of calls.
+    pub calls: u64,
+
+    /// Number of calls that returned an error.
+    pub errors: u64,
+
+    /// Total duration of all calls.
+    pub total_duration: Duration,
+
+    /// Duration of the slowest call.
+    pub max_duration: Duration,
+
+    /// Number of calls per [LATENCY_BUCKETS] bucket (not cumulative). Calls slower than the
+    /// last bucket are not counted here.
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+impl OperationMetrics {
+    /// Average duration of a call.
+    pub fn average_duration(&self) -> Duration {
+        if self.calls == 0 { Duration::ZERO } else { self.total_duration.div_f64(self.calls as f64) }
+    }
+
+    fn record(&mut self, duration: Duration, error: bool) {
+        self.calls += 1;
+        if error {
+            self.errors += 1;
+        }
+
+        self.total_duration += duration;
+        self.max_duration = self.max_duration.max(duration);
+
+        let seconds = duration.as_secs_f64();
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|bucket| seconds <= *bucket) {
+            self.latency_buckets[index] += 1;
+        }
+    }
+}
+
+//
+// EntityCount
+//
+
+/// Number of entities of a [Kind] in a [Directory].
+#[derive(Clone, Debug)]
+pub struct EntityCount {
+    /// Kind.
+    pub kind: Kind,
+
+    /// Directory.
+    pub directory: Directory,
+
+    /// Count.
+    pub count: u64,
+}
+
+//
+// StoreMetricsSnapshot
+//
+
+/// Snapshot of [MetricsStore] metrics.
+#[derive(Clone, Debug, Default)]
+pub struct StoreMetricsSnapshot {
+    /// Metrics per operation.
+    pub operations: BTreeMap<&'static str, OperationMetrics>,
+
+    /// Entity counts.
+    pub entities: Vec<EntityCount>,
+}
+
+impl StoreMetricsSnapshot {
+    /// Render in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut prometheus = String::default();
+        // Writing to a string cannot fail
+        _ = self.write_prometheus(&mut prometheus);
+        prometheus
+    }
+
+    /// Write in the Prometheus text exposition format.
+    pub fn write_prometheus<WriteT>(&self, writer: &mut WriteT) -> fmt::Result
+    where
+        WriteT: Write,
+    {
+        writeln!(writer, "# HELP floria_store_operations_total Number of store operations.")?;
+        writeln!(writer, "# TYPE floria_store_operations_total counter")?;
+        for (operation, metrics) in &self.operations {
+            writeln!(writer, "floria_store_operations_total{{operation=\"{}\"}} {}", operation, metrics.calls)?;
+        }
+
+        writeln!(writer, "# HELP floria_store_operation_errors_total Number of store operations that failed.")?;
+        writeln!(writer, "# TYPE floria_store_operation_errors_total counter")?;
+        for (operation, metrics) in &self.operations {
+            writeln!(writer, "floria_store_operation_errors_total{{operation=\"{}\"}} {}", operation, metrics.errors)?;
+        }
+
+        writeln!(writer, "# HELP floria_store_operation_duration_seconds Duration of store operations.")?;
+        writeln!(writer, "# TYPE floria_store_operation_duration_seconds histogram")?;
+        for (operation, metrics) in &self.operations {
+            let mut cumulative = 0;
+            for (bucket, count) in LATENCY_BUCKETS.iter().zip(metrics.latency_buckets) {
+                cumulative += count;
+                writeln!(
+                    writer,
+                    "floria_store_operation_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                    operation, bucket, cumulative
+                )?;
+            }
+            writeln!(
+                writer,
+                "floria_store_operation_duration_seconds_bucket{{operation=\"{}\",le=\"+Inf\"}} {}",
+                operation, metrics.calls
+            )?;
+            writeln!(
+                writer,
+                "floria_store_operation_duration_seconds_sum{{operation=\"{}\"}} {}",
+                operation,
+                metrics.total_duration.as_secs_f64()
+            )?;
+            writeln!(
+                writer,
+                "floria_store_operation_duration_seconds_count{{operation=\"{}\"}} {}",
+                operation, metrics.calls
+            )?;
+        }
+
+        writeln!(writer, "# HELP floria_store_entities Number of entities in the store.")?;
+        writeln!(writer, "# TYPE floria_store_entities gauge")?;
+        for entity_count in &self.entities {
+            writeln!(
+                writer,
+                "floria_store_entities{{kind=\"{}\",directory=\"{}\"}} {}",
+                entity_count.kind,
+                escape_label_value(&entity_count.directory.to_string()),
+                entity_count.count
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Utils
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
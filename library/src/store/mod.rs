@@ -1,8 +1,45 @@
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod bundle;
+mod caching;
+mod cbor;
+mod class_hierarchy;
+mod dependencies;
+mod diff;
 mod errors;
+mod event_handlers;
+mod events;
+mod filesystem;
+mod formats;
+mod garbage_collection;
+mod graph_query;
+mod id_strategy;
 mod in_memory;
+mod integrity;
+mod lifecycle;
+mod message_pack;
+mod metrics;
+mod property_definitions;
+mod query;
 mod r#ref;
+mod remote;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod store;
+mod transaction_lock;
 mod wrapper;
 
 #[allow(unused_imports)]
-pub use {errors::*, in_memory::*, r#ref::*, store::*, wrapper::*};
+pub use {
+    bundle::*, caching::*, class_hierarchy::*, dependencies::*, diff::*, errors::*, event_handlers::*, events::*,
+    filesystem::*, garbage_collection::*, graph_query::*, id_strategy::*, in_memory::*, integrity::*, lifecycle::*,
+    metrics::*, property_definitions::*, query::*, r#ref::*, remote::*, store::*, transaction_lock::*, wrapper::*,
+};
+
+#[cfg(feature = "tokio")]
+#[allow(unused_imports)]
+pub use asynchronous::*;
+
+#[cfg(feature = "sqlite")]
+#[allow(unused_imports)]
+pub use sqlite::*;
@@ -0,0 +1,90 @@
+use super::{
+    super::{data::*, errors::*},
+    class_hierarchy::*,
+    errors::*,
+    query::*,
+    store::*,
+};
+
+use {
+    kutil::std::{error::*, immutable::*},
+    std::collections::*,
+};
+
+//
+// StorePropertyDefinitionUtilities
+//
+
+/// Property definition utilities for [Store].
+pub trait StorePropertyDefinitionUtilities {
+    /// Property definitions of classes, including those inherited from their ancestors.
+    ///
+    /// Classes are visited nearest first, and the first definition of a property wins. Thus a class
+    /// overrides the definitions of its ancestors, and otherwise earlier classes override later
+    /// ones.
+    fn get_property_definitions(
+        &self,
+        class_ids: &[ID],
+    ) -> Result<BTreeMap<ByteString, PropertyDefinition>, StoreError>;
+
+    /// Apply property definitions to the vertexes and edges that match a query.
+    ///
+    /// See [Instance::apply_property_definitions]. The modified vertexes and edges are written in a
+    /// single transaction.
+    fn apply_property_definitions<ErrorRecipientT>(
+        &self,
+        query: &StoreQuery,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<(), FloriaError>
+    where
+        ErrorRecipientT: ErrorRecipient<FloriaError>;
+}
+
+impl<StoreT> StorePropertyDefinitionUtilities for StoreT
+where
+    StoreT: Store,
+{
+    fn get_property_definitions(
+        &self,
+        class_ids: &[ID],
+    ) -> Result<BTreeMap<ByteString, PropertyDefinition>, StoreError> {
+        let mut property_definitions = BTreeMap::default();
+
+        for class_id in self.get_inherited_class_ids(class_ids)? {
+            if let Some(class) = self.get_class(&class_id)? {
+                for (property_name, property_definition) in class.property_definitions {
+                    property_definitions.entry(property_name).or_insert(property_definition);
+                }
+            }
+        }
+
+        Ok(property_definitions)
+    }
+
+    fn apply_property_definitions<ErrorRecipientT>(
+        &self,
+        query: &StoreQuery,
+        errors: &mut ErrorRecipientT,
+    ) -> Result<(), FloriaError>
+    where
+        ErrorRecipientT: ErrorRecipient<FloriaError>,
+    {
+        self.transaction(|| {
+            for entity in self.get_entities(Kind::Vertex, query)? {
+                if let Entity::Vertex(mut vertex) = entity
+                    && vertex.instance.apply_property_definitions(self, errors)?
+                {
+                    self.update_vertex_if_revision(vertex)?;
+                }
+            }
+
+            for mut edge in self.get_edges(query)? {
+                if edge.instance.apply_property_definitions(self, errors)? {
+                    self.update_edge_if_revision(edge)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use super::super::data::*;
+
+use compris::{annotate::*, normal::*};
+
+//
+// StoreQuery
+//
+
+/// Store query.
+///
+/// All criteria must match. Empty criteria match everything.
+#[derive(Clone, Debug, Default)]
+pub struct StoreQuery {
+    /// Optional directories. The entity must be in one of them.
+    pub directories: Option<Vec<Directory>>,
+
+    /// Whether to also match subdirectories of the directories.
+    pub recursive: bool,
+
+    /// Class IDs. The entity must be a member of all of them.
+    pub class_ids: Vec<ID>,
+
+    /// Optional origin template ID. The entity must have originated from it.
+    pub origin_template_id: Option<ID>,
+
+    /// Metadata. The entity must have all these metadata keys with the same values.
+    pub metadata: Metadata,
+}
+
+impl StoreQuery {
+    /// With directory.
+    pub fn with_directory(mut self, directory: Directory) -> Self {
+        self.directories.get_or_insert_default().push(directory);
+        self
+    }
+
+    /// With directories.
+    pub fn with_directories(mut self, directories: Option<Vec<Directory>>) -> Self {
+        self.directories = directories;
+        self
+    }
+
+    /// With recursive.
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// With class ID.
+    pub fn with_class_id(mut self, class_id: ID) -> Self {
+        self.class_ids.push(class_id);
+        self
+    }
+
+    /// With origin template ID.
+    pub fn with_origin_template_id(mut self, origin_template_id: ID) -> Self {
+        self.origin_template_id = Some(origin_template_id);
+        self
+    }
+
+    /// With metadata.
+    pub fn with_metadata<KeyT, ValueT>(mut self, key: KeyT, value: ValueT) -> Self
+    where
+        KeyT: Into<Variant<WithoutAnnotations>>,
+        ValueT: Into<Variant<WithoutAnnotations>>,
+    {
+        self.metadata.into_insert(key, value);
+        self
+    }
+
+    /// True if the directory matches.
+    pub fn matches_directory(&self, directory: &Directory) -> bool {
+        match &self.directories {
+            Some(directories) => directories.iter().any(|directory_| {
+                if self.recursive { directory.starts_with(directory_) } else { directory == directory_ }
+            }),
+
+            None => true,
+        }
+    }
+
+    /// True if the entity matches.
+    pub fn matches(&self, entity: &Entity) -> bool {
+        if !self.matches_directory(&entity.get_id().directory) {
+            return false;
+        }
+
+        let class_ids = entity.get_class_ids();
+        if !self.class_ids.iter().all(|class_id| class_ids.contains(class_id)) {
+            return false;
+        }
+
+        if let Some(origin_template_id) = &self.origin_template_id
+            && entity.get_origin_template_id() != Some(origin_template_id)
+        {
+            return false;
+        }
+
+        let metadata = entity.get_metadata();
+        self.metadata.inner.iter().all(|(key, value)| metadata.inner.get(key) == Some(value))
+    }
+}
@@ -0,0 +1,384 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, query::*, store::*},
+    protocol::*,
+};
+
+use {
+    compris::{annotate::*, normal::*},
+    std::{
+        collections::*,
+        fmt, io,
+        net::*,
+        sync::{mpsc::*, *},
+        thread,
+    },
+};
+
+#[cfg(unix)]
+use std::{os::unix::net::*, path::*};
+
+//
+// RemoteAddress
+//
+
+/// Address of a [RemoteStoreServer](super::RemoteStoreServer).
+#[derive(Clone, Debug)]
+pub enum RemoteAddress {
+    /// TCP address, e.g. "localhost:8000".
+    TCP(String),
+
+    /// Unix socket path.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl RemoteAddress {
+    // Connect.
+    fn connect(&self) -> Result<Box<dyn RemoteStream>, StoreError> {
+        Ok(match self {
+            Self::TCP(address) => {
+                let stream = TcpStream::connect(address)?;
+                stream.set_nodelay(true)?;
+                Box::new(stream)
+            }
+
+            #[cfg(unix)]
+            Self::Unix(path) => Box::new(UnixStream::connect(path)?),
+        })
+    }
+}
+
+impl fmt::Display for RemoteAddress {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TCP(address) => write!(formatter, "tcp:{}", address),
+
+            #[cfg(unix)]
+            Self::Unix(path) => write!(formatter, "unix:{}", path.display()),
+        }
+    }
+}
+
+//
+// RemoteStore
+//
+
+/// [Store] client for a [RemoteStoreServer](super::RemoteStoreServer).
+///
+/// Requests are sent one at a time over a single connection. If the connection fails it is
+/// reconnected on the next request.
+///
+/// The exception is a connection that fails while transactions are open on it. The server rolls
+/// those back, so we do not reconnect. Instead requests fail until the transactions are rolled back
+/// here, too.
+///
+/// Clones share the same connection, and thus the same transactions.
+#[derive(Clone)]
+pub struct RemoteStore {
+    /// Address.
+    pub address: RemoteAddress,
+
+    connection: Arc<Mutex<RemoteConnection>>,
+}
+
+impl RemoteStore {
+    /// Connect.
+    pub fn connect(address: RemoteAddress) -> Result<Self, StoreError> {
+        let stream = address.connect()?;
+        Ok(Self { address, connection: Arc::new(Mutex::new(RemoteConnection::new(stream))) })
+    }
+
+    /// Connect via TCP.
+    pub fn connect_tcp<AddressT>(address: AddressT) -> Result<Self, StoreError>
+    where
+        AddressT: ToString,
+    {
+        Self::connect(RemoteAddress::TCP(address.to_string()))
+    }
+
+    /// Connect via Unix socket.
+    #[cfg(unix)]
+    pub fn connect_unix<PathT>(path: PathT) -> Result<Self, StoreError>
+    where
+        PathT: AsRef<Path>,
+    {
+        Self::connect(RemoteAddress::Unix(path.as_ref().into()))
+    }
+
+    // Send a request and receive the result.
+    fn call(&self, request: Map<WithoutAnnotations>) -> Result<Variant<WithoutAnnotations>, StoreError> {
+        let mut connection = self.connection.lock()?;
+        self.call_on(&mut connection, request)
+    }
+
+    // Send a request on a locked connection and receive the result.
+    fn call_on(
+        &self,
+        connection: &mut RemoteConnection,
+        request: Map<WithoutAnnotations>,
+    ) -> Result<Variant<WithoutAnnotations>, StoreError> {
+        let stream = match connection.stream.as_mut() {
+            Some(stream) => stream,
+
+            None => {
+                if connection.transactions != 0 {
+                    return Err(StoreError::Transaction("connection failed during transaction".into()));
+                }
+
+                connection.stream.insert(self.address.connect()?)
+            }
+        };
+
+        match round_trip(stream, &request.into()) {
+            Ok(response) => get_result(response),
+
+            Err(error) => {
+                // We can't be sure of the state of the stream
+                connection.stream = None;
+                Err(error)
+            }
+        }
+    }
+
+    fn call_with_id(&self, operation: &'static str, id: &ID) -> Result<Variant<WithoutAnnotations>, StoreError> {
+        let mut request = new_request(operation);
+        request.into_insert("id", id_to_variant(id));
+        self.call(request)
+    }
+
+    fn call_with_entity<EntityT>(
+        &self,
+        operation: &'static str,
+        entity: EntityT,
+    ) -> Result<Variant<WithoutAnnotations>, StoreError>
+    where
+        EntityT: Into<Entity>,
+    {
+        let mut request = new_request(operation);
+        request.into_insert("entity", entity_to_variant(entity, self)?);
+        self.call(request)
+    }
+}
+
+impl fmt::Debug for RemoteStore {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("RemoteStore").field("address", &self.address).finish_non_exhaustive()
+    }
+}
+
+impl Store for RemoteStore {
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        let mut connection = self.connection.lock()?;
+        self.call_on(&mut connection, new_request("begin_transaction"))?;
+        connection.transactions += 1;
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        let mut connection = self.connection.lock()?;
+        self.call_on(&mut connection, new_request("commit_transaction"))?;
+        connection.transactions = connection.transactions.saturating_sub(1);
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        let mut connection = self.connection.lock()?;
+
+        // The server has already rolled back the transactions of the failed connection
+        if connection.stream.is_none() && (connection.transactions != 0) {
+            connection.transactions -= 1;
+            return Ok(());
+        }
+
+        self.call_on(&mut connection, new_request("rollback_transaction"))?;
+        connection.transactions = connection.transactions.saturating_sub(1);
+        Ok(())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        // Subscriptions get their own connection
+        let mut stream = self.address.connect()?;
+
+        let mut request = new_request("subscribe");
+        request.into_insert("filter", filter_to_variant(&filter));
+        get_result(round_trip(&mut stream, &request.into())?)?;
+
+        let (sender, receiver) = channel();
+
+        thread::spawn(move || {
+            // Ends when the receiver is dropped, which we will only notice when sending the next event
+            while let Ok(Some(response)) = read_frame(&mut stream) {
+                match get_result(response).and_then(|event| event_from_variant(&event)) {
+                    Ok(event) => {
+                        if sender.send(event).is_err() {
+                            break;
+                        }
+                    }
+
+                    Err(error) => {
+                        tracing::warn!("remote store subscription: {}", error);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        *id = id_from_variant(&self.call_with_id("create_id", id)?)?;
+        Ok(())
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        next_ids_from_variant(&self.call(new_request("get_next_ids"))?)
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        let mut request = new_request("advance_next_ids");
+        request.into_insert("next_ids", next_ids_to_variant(next_ids));
+        self.call(request)?;
+        Ok(())
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("get_class", id)?)
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.call_with_entity("add_class", class)?;
+        Ok(())
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        u64_from_variant(&self.call_with_entity("update_class_if_revision", class)?)
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("remove_class", id)?)
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("get_vertex_template", id)?)
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.call_with_entity("add_vertex_template", vertex_template)?;
+        Ok(())
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        u64_from_variant(&self.call_with_entity("update_vertex_template_if_revision", vertex_template)?)
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("remove_vertex_template", id)?)
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("get_edge_template", id)?)
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.call_with_entity("add_edge_template", edge_template)?;
+        Ok(())
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        u64_from_variant(&self.call_with_entity("update_edge_template_if_revision", edge_template)?)
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("remove_edge_template", id)?)
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("get_vertex", id)?)
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        let mut request = new_request("get_entities");
+        request.into_insert("kind", kind.to_string());
+        request.into_insert("query", query_to_variant(query));
+        entities_from_variant(&self.call(request)?)
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        let mut request = new_request("get_vertexes");
+        request.into_insert("directories", directories_to_variant(directories.as_ref()));
+        entities_from_variant(&self.call(request)?)
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.call_with_entity("add_vertex", vertex)?;
+        Ok(())
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        u64_from_variant(&self.call_with_entity("update_vertex_if_revision", vertex)?)
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("remove_vertex", id)?)
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("get_edge", id)?)
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.call_with_entity("add_edge", edge)?;
+        Ok(())
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        u64_from_variant(&self.call_with_entity("update_edge_if_revision", edge)?)
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        optional_entity_from_variant(&self.call_with_id("remove_edge", id)?)
+    }
+}
+
+//
+// RemoteConnection
+//
+
+struct RemoteConnection {
+    // None after the connection failed
+    stream: Option<Box<dyn RemoteStream>>,
+
+    // Open transactions
+    transactions: usize,
+}
+
+impl RemoteConnection {
+    fn new(stream: Box<dyn RemoteStream>) -> Self {
+        Self { stream: Some(stream), transactions: 0 }
+    }
+}
+
+//
+// RemoteStream
+//
+
+trait RemoteStream
+where
+    Self: io::Read + io::Write + Send,
+{
+}
+
+impl<StreamT> RemoteStream for StreamT where StreamT: io::Read + io::Write + Send {}
+
+// Write a request and read its response.
+fn round_trip<StreamT>(
+    stream: &mut StreamT,
+    request: &Variant<WithoutAnnotations>,
+) -> Result<Variant<WithoutAnnotations>, StoreError>
+where
+    StreamT: io::Read + io::Write + ?Sized,
+{
+    write_frame(stream, request)?;
+    read_frame(stream)?.ok_or_else(|| StoreError::Backend("connection closed".into()))
+}
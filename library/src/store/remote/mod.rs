@@ -0,0 +1,6 @@
+mod client;
+mod protocol;
+mod server;
+
+#[allow(unused_imports)]
+pub use {client::*, server::*};
@@ -0,0 +1,374 @@
+use super::super::{
+    super::data::{from_variant::*, *},
+    cbor::*,
+    errors::*,
+    events::*,
+    query::*,
+    store::*,
+};
+
+use {
+    compris::{annotate::*, normal::*, parse::*, resolve::*, *},
+    std::{collections::*, io},
+};
+
+// Frames longer than this are rejected
+const MAX_FRAME_LENGTH: u32 = 64 * 1024 * 1024;
+
+// Frames
+
+// Write a frame: a 4-byte big-endian length followed by that many bytes of CBOR.
+pub(crate) fn write_frame<WriteT>(writer: &mut WriteT, variant: &Variant<WithoutAnnotations>) -> Result<(), StoreError>
+where
+    WriteT: io::Write + ?Sized,
+{
+    let mut bytes = Vec::default();
+    encode_cbor(variant, &mut bytes);
+
+    let length = match u32::try_from(bytes.len()) {
+        Ok(length) if length <= MAX_FRAME_LENGTH => length,
+        _ => return Err(StoreError::Backend(format!("frame too long: {} bytes", bytes.len()))),
+    };
+
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Read a frame. Returns [None] if the stream ended before the frame.
+pub(crate) fn read_frame<ReadT>(reader: &mut ReadT) -> Result<Option<Variant<WithoutAnnotations>>, StoreError>
+where
+    ReadT: io::Read + ?Sized,
+{
+    let mut length = [0; 4];
+    match reader.read_exact(&mut length) {
+        Ok(_) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+
+    let length = u32::from_be_bytes(length);
+    if length > MAX_FRAME_LENGTH {
+        return Err(StoreError::Backend(format!("frame too long: {} bytes", length)));
+    }
+
+    let mut bytes = vec![0; length as usize];
+    reader.read_exact(&mut bytes)?;
+
+    Parser::new(Format::CBOR)
+        .parse_reader(&mut bytes.as_slice())
+        .map(Some)
+        .map_err(|error| StoreError::Backend(error.to_string()))
+}
+
+// Requests and responses
+
+// Create a request for an operation.
+pub(crate) fn new_request(operation: &'static str) -> Map<WithoutAnnotations> {
+    let mut request = Map::default();
+    request.into_insert("operation", operation);
+    request
+}
+
+// Get the operation of a request.
+pub(crate) fn get_operation(request: &Map<WithoutAnnotations>) -> Result<&str, StoreError> {
+    Ok(expect_text(get_argument(request, "operation")?).map_err(resolve_error)?)
+}
+
+// Get a required argument of a request.
+pub(crate) fn get_argument<'own>(
+    request: &'own Map<WithoutAnnotations>,
+    key: &'static str,
+) -> Result<&'own Variant<WithoutAnnotations>, StoreError> {
+    get_required(request, key).map_err(resolve_error)
+}
+
+// Create a response from a result.
+pub(crate) fn new_response(result: Result<Variant<WithoutAnnotations>, StoreError>) -> Variant<WithoutAnnotations> {
+    let mut response = Map::default();
+    match result {
+        Ok(result) => {
+            response.into_insert("result", result);
+        }
+
+        Err(error) => {
+            let (kind, message) = match error {
+                StoreError::ID(message) => ("ID", message),
+                StoreError::Concurrency(message) => ("Concurrency", message),
+                StoreError::Conflict(message) => ("Conflict", message),
+                StoreError::ClassCycle(message) => ("ClassCycle", message),
+                StoreError::InvalidTransition(message) => ("InvalidTransition", message),
+                StoreError::Transaction(message) => ("Transaction", message),
+                StoreError::Backend(message) => ("Backend", message),
+            };
+
+            let mut error = Map::default();
+            error.into_insert("kind", kind);
+            error.into_insert("message", message);
+            response.into_insert("error", error);
+        }
+    }
+    response.into()
+}
+
+// Get the result from a response.
+pub(crate) fn get_result(response: Variant<WithoutAnnotations>) -> Result<Variant<WithoutAnnotations>, StoreError> {
+    let response = expect_map(&response).map_err(resolve_error)?;
+
+    if let Some(error) = response.into_get("error") {
+        let error = expect_map(error).map_err(resolve_error)?;
+        let kind: &str = expect_text(get_argument(error, "kind")?).map_err(resolve_error)?;
+        let message = expect_text(get_argument(error, "message")?).map_err(resolve_error)?.to_string();
+        return Err(match kind {
+            "ID" => StoreError::ID(message),
+            "Concurrency" => StoreError::Concurrency(message),
+            "Conflict" => StoreError::Conflict(message),
+            "ClassCycle" => StoreError::ClassCycle(message),
+            "InvalidTransition" => StoreError::InvalidTransition(message),
+            "Transaction" => StoreError::Transaction(message),
+            _ => StoreError::Backend(message),
+        });
+    }
+
+    Ok(get_argument(response, "result")?.clone())
+}
+
+// IDs
+
+pub(crate) fn id_to_variant(id: &ID) -> Variant<WithoutAnnotations> {
+    let mut map = Map::default();
+    map.into_insert("kind", id.kind.to_string());
+    map.into_insert("id", id.to_string());
+    map.into()
+}
+
+pub(crate) fn id_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<ID, StoreError> {
+    let map = expect_map(variant).map_err(resolve_error)?;
+    let kind = get_kind(get_argument(map, "kind")?).map_err(resolve_error)?;
+    get_id(get_argument(map, "id")?, kind).map_err(resolve_error)
+}
+
+pub(crate) fn optional_id_to_variant(id: Option<&ID>) -> Variant<WithoutAnnotations> {
+    match id {
+        Some(id) => id_to_variant(id),
+        None => ().into(),
+    }
+}
+
+pub(crate) fn optional_id_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<Option<ID>, StoreError> {
+    match variant {
+        Variant::Null(_) => Ok(None),
+        _ => id_from_variant(variant).map(Some),
+    }
+}
+
+pub(crate) fn ids_to_variant(ids: &[ID]) -> Variant<WithoutAnnotations> {
+    ids.iter().map(id_to_variant).collect()
+}
+
+pub(crate) fn ids_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<Vec<ID>, StoreError> {
+    expect_list(variant).map_err(resolve_error)?.into_iter().map(id_from_variant).collect()
+}
+
+pub(crate) fn next_ids_to_variant(next_ids: &HashMap<Kind, u64>) -> Variant<WithoutAnnotations> {
+    next_ids.iter().map(|(kind, next_id)| (kind.to_string().into(), (*next_id).into())).collect()
+}
+
+pub(crate) fn next_ids_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<HashMap<Kind, u64>, StoreError> {
+    let mut next_ids = HashMap::default();
+    for (kind, next_id) in expect_map(variant).map_err(resolve_error)? {
+        next_ids.insert(get_kind(kind).map_err(resolve_error)?, u64_from_variant(next_id)?);
+    }
+    Ok(next_ids)
+}
+
+// Directories
+
+pub(crate) fn directories_to_variant(directories: Option<&Vec<Directory>>) -> Variant<WithoutAnnotations> {
+    match directories {
+        Some(directories) => directories
+            .iter()
+            .map(|directory| {
+                directory
+                    .into_iter()
+                    .map(|segment| Variant::<WithoutAnnotations>::from(segment.clone()))
+                    .collect::<Variant<_>>()
+            })
+            .collect(),
+        None => ().into(),
+    }
+}
+
+pub(crate) fn directories_from_variant(
+    variant: &Variant<WithoutAnnotations>,
+) -> Result<Option<Vec<Directory>>, StoreError> {
+    match variant {
+        Variant::Null(_) => Ok(None),
+        _ => {
+            let mut directories = Vec::default();
+            for directory in expect_list(variant).map_err(resolve_error)? {
+                let mut directory_ = Directory::default();
+                for segment in expect_list(directory).map_err(resolve_error)? {
+                    directory_.add_last_segment(expect_text(segment).map_err(resolve_error)?.clone());
+                }
+                directories.push(directory_);
+            }
+            Ok(Some(directories))
+        }
+    }
+}
+
+// Queries and filters
+
+pub(crate) fn query_to_variant(query: &StoreQuery) -> Variant<WithoutAnnotations> {
+    let mut map = Map::default();
+    map.into_insert("directories", directories_to_variant(query.directories.as_ref()));
+    map.into_insert("recursive", query.recursive);
+    map.into_insert("class_ids", ids_to_variant(&query.class_ids));
+    map.into_insert("origin_template_id", optional_id_to_variant(query.origin_template_id.as_ref()));
+    map.into_insert("metadata", query.metadata.clone());
+    map.into()
+}
+
+pub(crate) fn query_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<StoreQuery, StoreError> {
+    let map = expect_map(variant).map_err(resolve_error)?;
+    Ok(StoreQuery {
+        directories: directories_from_variant(get_argument(map, "directories")?)?,
+        recursive: expect_boolean(get_argument(map, "recursive")?).map_err(resolve_error)?,
+        class_ids: ids_from_variant(get_argument(map, "class_ids")?)?,
+        origin_template_id: optional_id_from_variant(get_argument(map, "origin_template_id")?)?,
+        metadata: expect_map(get_argument(map, "metadata")?).map_err(resolve_error)?.clone(),
+    })
+}
+
+pub(crate) fn filter_to_variant(filter: &StoreEventFilter) -> Variant<WithoutAnnotations> {
+    let mut map = Map::default();
+    map.into_insert(
+        "kinds",
+        match &filter.kinds {
+            Some(kinds) => {
+                kinds.iter().map(|kind| Variant::<WithoutAnnotations>::from(kind.to_string())).collect::<Variant<_>>()
+            }
+            None => ().into(),
+        },
+    );
+    map.into_insert("query", query_to_variant(&filter.query));
+    map.into()
+}
+
+pub(crate) fn filter_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<StoreEventFilter, StoreError> {
+    let map = expect_map(variant).map_err(resolve_error)?;
+    let kinds = match get_argument(map, "kinds")? {
+        Variant::Null(_) => None,
+        kinds => Some(
+            expect_list(kinds)
+                .map_err(resolve_error)?
+                .into_iter()
+                .map(|kind| get_kind(kind).map_err(resolve_error))
+                .collect::<Result<_, _>>()?,
+        ),
+    };
+    Ok(StoreEventFilter { kinds, query: query_from_variant(get_argument(map, "query")?)? })
+}
+
+// Entities
+
+pub(crate) fn entity_to_variant<EntityT, StoreT>(
+    entity: EntityT,
+    store: &StoreT,
+) -> Result<Variant<WithoutAnnotations>, StoreError>
+where
+    EntityT: Into<Entity>,
+    StoreT: Store,
+{
+    // Not embedded, so the store is not actually accessed
+    entity.into().to_variant(false, store)
+}
+
+pub(crate) fn optional_entity_to_variant<EntityT, StoreT>(
+    entity: Option<EntityT>,
+    store: &StoreT,
+) -> Result<Variant<WithoutAnnotations>, StoreError>
+where
+    EntityT: Into<Entity>,
+    StoreT: Store,
+{
+    match entity {
+        Some(entity) => entity_to_variant(entity, store),
+        None => Ok(().into()),
+    }
+}
+
+pub(crate) fn entities_to_variant<EntityT, StoreT>(
+    entities: Vec<EntityT>,
+    store: &StoreT,
+) -> Result<Variant<WithoutAnnotations>, StoreError>
+where
+    EntityT: Into<Entity>,
+    StoreT: Store,
+{
+    entities.into_iter().map(|entity| entity_to_variant(entity, store)).collect()
+}
+
+pub(crate) fn entity_from_variant<EntityT>(variant: &Variant<WithoutAnnotations>) -> Result<EntityT, StoreError>
+where
+    EntityT: TryFrom<Entity>,
+{
+    let entity = Entity::from_variant(variant).map_err(resolve_error)?;
+    let kind = entity.get_id().kind;
+    entity.try_into().map_err(|_| StoreError::Backend(format!("unexpected kind: {}", kind)))
+}
+
+pub(crate) fn optional_entity_from_variant<EntityT>(
+    variant: &Variant<WithoutAnnotations>,
+) -> Result<Option<EntityT>, StoreError>
+where
+    EntityT: TryFrom<Entity>,
+{
+    match variant {
+        Variant::Null(_) => Ok(None),
+        _ => entity_from_variant(variant).map(Some),
+    }
+}
+
+pub(crate) fn entities_from_variant<EntityT>(variant: &Variant<WithoutAnnotations>) -> Result<Vec<EntityT>, StoreError>
+where
+    EntityT: TryFrom<Entity>,
+{
+    expect_list(variant).map_err(resolve_error)?.into_iter().map(entity_from_variant).collect()
+}
+
+// Events
+
+pub(crate) fn event_to_variant<StoreT>(
+    event: StoreEvent,
+    store: &StoreT,
+) -> Result<Variant<WithoutAnnotations>, StoreError>
+where
+    StoreT: Store,
+{
+    let mut map = Map::default();
+    map.into_insert("id", id_to_variant(&event.id));
+    map.into_insert("before", optional_entity_to_variant(event.before, store)?);
+    map.into_insert("after", optional_entity_to_variant(event.after, store)?);
+    Ok(map.into())
+}
+
+pub(crate) fn event_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<StoreEvent, StoreError> {
+    let map = expect_map(variant).map_err(resolve_error)?;
+    let id = id_from_variant(get_argument(map, "id")?)?;
+    let before = optional_entity_from_variant(get_argument(map, "before")?)?;
+    let after = optional_entity_from_variant(get_argument(map, "after")?)?;
+    StoreEvent::new(id, before, after).ok_or_else(|| StoreError::Backend("event has no entities".into()))
+}
+
+// Utils
+
+pub(crate) fn u64_from_variant(variant: &Variant<WithoutAnnotations>) -> Result<u64, StoreError> {
+    u64::try_from(variant.clone()).map_err(|error| StoreError::Backend(error.to_string()))
+}
+
+fn resolve_error(error: ResolveError<WithoutAnnotations>) -> StoreError {
+    StoreError::Backend(error.to_string())
+}
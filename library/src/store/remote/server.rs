@@ -0,0 +1,263 @@
+use super::{
+    super::{super::data::from_variant::*, errors::*, store::*, transaction_lock::*},
+    protocol::*,
+};
+
+use {
+    compris::{annotate::*, normal::*},
+    std::{io, net::*, thread},
+};
+
+#[cfg(unix)]
+use std::os::unix::net::*;
+
+//
+// RemoteStoreServer
+//
+
+/// Serves a [Store] to [RemoteStore](super::RemoteStore) clients.
+///
+/// Each connection is handled in its own thread and is its own transaction owner (see
+/// [with_transaction_owner]), so that its transactions are exclusive to it. Transactions begun by a
+/// connection that are still open when it closes are rolled back.
+#[derive(Clone, Debug)]
+pub struct RemoteStoreServer<StoreT> {
+    /// Store.
+    pub store: StoreT,
+}
+
+impl<StoreT> RemoteStoreServer<StoreT>
+where
+    StoreT: 'static + Clone + Send + Store,
+{
+    /// Constructor.
+    pub fn new(store: StoreT) -> Self {
+        Self { store }
+    }
+
+    /// Serve TCP connections.
+    ///
+    /// Blocks until accepting a connection fails.
+    pub fn serve_tcp(&self, listener: TcpListener) -> Result<(), StoreError> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            self.spawn(stream);
+        }
+        Ok(())
+    }
+
+    /// Serve Unix socket connections.
+    ///
+    /// Blocks until accepting a connection fails.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: UnixListener) -> Result<(), StoreError> {
+        for stream in listener.incoming() {
+            self.spawn(stream?);
+        }
+        Ok(())
+    }
+
+    /// Serve a single connection.
+    ///
+    /// Blocks until the connection is closed.
+    pub fn serve_connection<StreamT>(&self, mut stream: StreamT) -> Result<(), StoreError>
+    where
+        StreamT: io::Read + io::Write,
+    {
+        with_transaction_owner(new_transaction_owner(), || {
+            let mut transactions = 0;
+            let result = self.serve_requests(&mut stream, &mut transactions);
+
+            // Only our own transactions can be rolled back
+            for _ in 0..transactions {
+                self.store.rollback_transaction()?;
+            }
+
+            result
+        })
+    }
+
+    fn spawn<StreamT>(&self, stream: StreamT)
+    where
+        StreamT: 'static + io::Read + io::Write + Send,
+    {
+        let server = self.clone();
+        thread::spawn(move || {
+            if let Err(error) = server.serve_connection(stream) {
+                tracing::warn!("remote store connection: {}", error);
+            }
+        });
+    }
+
+    fn serve_requests<StreamT>(&self, stream: &mut StreamT, transactions: &mut usize) -> Result<(), StoreError>
+    where
+        StreamT: io::Read + io::Write,
+    {
+        while let Some(request) = read_frame(stream)? {
+            let request = match expect_map(&request) {
+                Ok(request) => request,
+                Err(error) => {
+                    write_frame(stream, &new_response(Err(StoreError::Backend(error.to_string()))))?;
+                    continue;
+                }
+            };
+
+            // A subscription takes over the connection
+            if let Ok("subscribe") = get_operation(request) {
+                return self.serve_subscription(stream, request);
+            }
+
+            let response = new_response(self.handle(request, transactions));
+            write_frame(stream, &response)?;
+        }
+
+        Ok(())
+    }
+
+    fn serve_subscription<StreamT>(
+        &self,
+        stream: &mut StreamT,
+        request: &Map<WithoutAnnotations>,
+    ) -> Result<(), StoreError>
+    where
+        StreamT: io::Read + io::Write,
+    {
+        let receiver = match filter_from_variant(get_argument(request, "filter")?)
+            .and_then(|filter| self.store.subscribe(filter))
+        {
+            Ok(receiver) => receiver,
+            Err(error) => return write_frame(stream, &new_response(Err(error))),
+        };
+
+        write_frame(stream, &new_response(Ok(().into())))?;
+
+        // Ends when the client disconnects, which we will only notice when writing the next event
+        for event in receiver {
+            write_frame(stream, &new_response(event_to_variant(event, &self.store)))?;
+        }
+
+        Ok(())
+    }
+
+    fn handle(
+        &self,
+        request: &Map<WithoutAnnotations>,
+        transactions: &mut usize,
+    ) -> Result<Variant<WithoutAnnotations>, StoreError> {
+        let store = &self.store;
+        let argument = |key| get_argument(request, key);
+        let id = || id_from_variant(argument("id")?);
+
+        Ok(match get_operation(request)? {
+            "begin_transaction" => {
+                store.begin_transaction()?;
+                *transactions += 1;
+                ().into()
+            }
+
+            "commit_transaction" => {
+                store.commit_transaction()?;
+                *transactions = transactions.saturating_sub(1);
+                ().into()
+            }
+
+            "rollback_transaction" => {
+                store.rollback_transaction()?;
+                *transactions = transactions.saturating_sub(1);
+                ().into()
+            }
+
+            "create_id" => {
+                let mut id = id()?;
+                store.create_id(&mut id)?;
+                id_to_variant(&id)
+            }
+
+            "get_next_ids" => next_ids_to_variant(&store.get_next_ids()?),
+
+            "advance_next_ids" => {
+                store.advance_next_ids(&next_ids_from_variant(argument("next_ids")?)?)?;
+                ().into()
+            }
+
+            "get_class" => optional_entity_to_variant(store.get_class(&id()?)?, store)?,
+
+            "add_class" => {
+                store.add_class(entity_from_variant(argument("entity")?)?)?;
+                ().into()
+            }
+
+            "update_class_if_revision" => {
+                store.update_class_if_revision(entity_from_variant(argument("entity")?)?)?.into()
+            }
+
+            "remove_class" => optional_entity_to_variant(store.remove_class(&id()?)?, store)?,
+
+            "get_vertex_template" => optional_entity_to_variant(store.get_vertex_template(&id()?)?, store)?,
+
+            "add_vertex_template" => {
+                store.add_vertex_template(entity_from_variant(argument("entity")?)?)?;
+                ().into()
+            }
+
+            "update_vertex_template_if_revision" => {
+                store.update_vertex_template_if_revision(entity_from_variant(argument("entity")?)?)?.into()
+            }
+
+            "remove_vertex_template" => optional_entity_to_variant(store.remove_vertex_template(&id()?)?, store)?,
+
+            "get_edge_template" => optional_entity_to_variant(store.get_edge_template(&id()?)?, store)?,
+
+            "add_edge_template" => {
+                store.add_edge_template(entity_from_variant(argument("entity")?)?)?;
+                ().into()
+            }
+
+            "update_edge_template_if_revision" => {
+                store.update_edge_template_if_revision(entity_from_variant(argument("entity")?)?)?.into()
+            }
+
+            "remove_edge_template" => optional_entity_to_variant(store.remove_edge_template(&id()?)?, store)?,
+
+            "get_vertex" => optional_entity_to_variant(store.get_vertex(&id()?)?, store)?,
+
+            "get_entities" => {
+                let kind = get_kind(argument("kind")?).map_err(|error| StoreError::Backend(error.to_string()))?;
+                let query = query_from_variant(argument("query")?)?;
+                entities_to_variant(store.get_entities(kind, &query)?, store)?
+            }
+
+            "get_vertexes" => {
+                let directories = directories_from_variant(argument("directories")?)?;
+                entities_to_variant(store.get_vertexes(directories)?, store)?
+            }
+
+            "add_vertex" => {
+                store.add_vertex(entity_from_variant(argument("entity")?)?)?;
+                ().into()
+            }
+
+            "update_vertex_if_revision" => {
+                store.update_vertex_if_revision(entity_from_variant(argument("entity")?)?)?.into()
+            }
+
+            "remove_vertex" => optional_entity_to_variant(store.remove_vertex(&id()?)?, store)?,
+
+            "get_edge" => optional_entity_to_variant(store.get_edge(&id()?)?, store)?,
+
+            "add_edge" => {
+                store.add_edge(entity_from_variant(argument("entity")?)?)?;
+                ().into()
+            }
+
+            "update_edge_if_revision" => {
+                store.update_edge_if_revision(entity_from_variant(argument("entity")?)?)?.into()
+            }
+
+            "remove_edge" => optional_entity_to_variant(store.remove_edge(&id()?)?, store)?,
+
+            operation => return Err(StoreError::Backend(format!("unsupported operation: {}", operation))),
+        })
+    }
+}
@@ -0,0 +1,146 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, id_strategy::*, transaction_lock::*},
+    schema::*,
+};
+
+use {
+    kutil::std::collections::*,
+    rusqlite::*,
+    std::{collections::*, path::*, sync::*},
+};
+
+//
+// SqliteStoreBackend
+//
+
+/// SQLite store backend.
+pub struct SqliteStoreBackend {
+    /// ID strategy.
+    pub id_strategy: Box<dyn IDStrategy>,
+
+    pub(crate) connection: Mutex<Connection>,
+    pub(crate) next_ids: FastConcurrentHashMap<Kind, u64>,
+    pub(crate) publisher: StoreEventPublisher,
+
+    // Held by transactions and writes so that they would not interleave with other owners'
+    pub(crate) transaction_lock: TransactionLock,
+
+    // A stack of nested transactions, each with the IDs of the entities written in it, all
+    // belonging to the owner of the transaction lock
+    pub(crate) transactions: Mutex<Vec<Vec<ID>>>,
+}
+
+impl SqliteStoreBackend {
+    /// Constructor.
+    ///
+    /// Creates the schema if it doesn't exist.
+    pub fn new(connection: Connection) -> Result<Self, StoreError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            id_strategy: Default::default(),
+            connection: connection.into(),
+            next_ids: Default::default(),
+            publisher: Default::default(),
+            transaction_lock: Default::default(),
+            transactions: Default::default(),
+        })
+    }
+
+    /// Constructor.
+    ///
+    /// Opens (or creates) a database file.
+    pub fn open<PathT>(path: PathT) -> Result<Self, StoreError>
+    where
+        PathT: AsRef<Path>,
+    {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Constructor.
+    ///
+    /// Opens a database in memory.
+    pub fn open_in_memory() -> Result<Self, StoreError> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    /// With ID strategy.
+    pub fn with_id_strategy<IDStrategyT>(mut self, id_strategy: IDStrategyT) -> Self
+    where
+        IDStrategyT: 'static + IDStrategy,
+    {
+        self.id_strategy = Box::new(id_strategy);
+        self
+    }
+
+    /// Next ID.
+    ///
+    /// The counters are persisted in the database. They are also tracked in memory so that they
+    /// would never go back, even if the persisted counters are rolled back by a transaction.
+    pub fn get_next_id(&self, kind: Kind) -> Result<u64, StoreError> {
+        let connection = self.connection.lock()?;
+        let minimum = self.next_ids.pin().get(&kind).map(|id| id + 1).unwrap_or(1);
+        let next_id: u64 = connection.query_row(
+            "INSERT INTO next_ids (kind, next_id) VALUES (?1, ?2) \
+            ON CONFLICT (kind) DO UPDATE SET next_id = MAX(next_id + 1, excluded.next_id) \
+            RETURNING next_id",
+            params![kind.to_string(), minimum],
+            |row| row.get(0),
+        )?;
+        self.next_ids.pin().insert(kind, next_id);
+        Ok(next_id)
+    }
+
+    /// Next IDs.
+    pub fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        let mut next_ids: HashMap<_, _> = self.next_ids.pin().iter().map(|(kind, next_id)| (*kind, *next_id)).collect();
+
+        let connection = self.connection.lock()?;
+        let mut statement = connection.prepare_cached("SELECT kind, next_id FROM next_ids")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?;
+        for row in rows {
+            let (kind, next_id) = row?;
+            let kind: Kind = kind.parse().map_err(|_| StoreError::Backend(format!("unknown kind: {}", kind)))?;
+            let next_id_ = next_ids.entry(kind).or_default();
+            *next_id_ = (*next_id_).max(next_id);
+        }
+
+        Ok(next_ids)
+    }
+
+    /// Advance next ID.
+    ///
+    /// Does nothing if the provided value is not higher.
+    pub fn advance_next_id(&self, kind: Kind, next_id: u64) -> Result<(), StoreError> {
+        let connection = self.connection.lock()?;
+        connection.execute(
+            "INSERT INTO next_ids (kind, next_id) VALUES (?1, ?2) \
+            ON CONFLICT (kind) DO UPDATE SET next_id = MAX(next_id, excluded.next_id)",
+            params![kind.to_string(), next_id],
+        )?;
+        self.next_ids.pin().update_or_insert(kind, |id| (*id).max(next_id), next_id);
+        Ok(())
+    }
+
+    /// True if we are in a transaction.
+    pub fn in_transaction(&self) -> Result<bool, StoreError> {
+        Ok(!self.transactions.lock()?.is_empty())
+    }
+
+    /// Publish a change to subscribers.
+    pub(crate) fn publish(&self, id: ID, before: Option<Entity>, after: Option<Entity>) -> Result<(), StoreError> {
+        match StoreEvent::new(id, before, after) {
+            Some(event) => self.publisher.publish(event),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a written entity in the current transaction.
+    ///
+    /// Does nothing if we are not in a transaction.
+    pub(crate) fn record(&self, id: ID) -> Result<(), StoreError> {
+        if let Some(transaction) = self.transactions.lock()?.last_mut() {
+            transaction.push(id);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use super::super::{super::data::*, errors::*};
+
+use compris::{annotate::*, normal::*, parse::*, ser::*, *};
+
+// Conversions between entity fields and SQLite columns.
+//
+// Directories and IDs are stored as text. Variants, expressions, and calls are stored as XJSON
+// text, which preserves the types of all variants.
+
+/// [Directory] to column.
+pub fn directory_to_column(directory: &Directory) -> String {
+    directory.to_string()
+}
+
+/// [Directory] from column.
+pub fn directory_from_column(directory: &str) -> Directory {
+    if directory.is_empty() { Default::default() } else { ID::parse_directory(directory) }
+}
+
+/// [ID] to column.
+pub fn id_to_column(id: &ID) -> String {
+    id.to_string()
+}
+
+/// [ID] from column.
+pub fn id_from_column(kind: Kind, id: &str) -> ID {
+    ID::parse(kind, id)
+}
+
+/// [Variant] to column.
+pub fn variant_to_column(variant: &Variant<WithoutAnnotations>) -> Result<String, StoreError> {
+    Serializer::new(Format::XJSON)
+        .stringify_modal(variant, &SerializationMode::for_xjson())
+        .map(|string| string.to_string())
+        .map_err(|error| StoreError::Backend(format!("encode variant: {}", error)))
+}
+
+/// [Variant] from column.
+pub fn variant_from_column(variant: &str) -> Result<Variant<WithoutAnnotations>, StoreError> {
+    Parser::new(Format::XJSON)
+        .parse_string(variant)
+        .map_err(|error| StoreError::Backend(format!("decode variant: {}", error)))
+}
+
+/// [Metadata] to column.
+pub fn metadata_to_column(metadata: &Metadata) -> Result<String, StoreError> {
+    variant_to_column(&metadata.clone().into())
+}
+
+/// [Metadata] from column.
+pub fn metadata_from_column(column: &str) -> Result<Metadata, StoreError> {
+    match variant_from_column(column)? {
+        Variant::Map(map) => Ok(map),
+        variant => Err(malformed("metadata", &variant)),
+    }
+}
+
+/// [Expression] to column.
+pub fn expression_to_column(expression: &Expression) -> Result<String, StoreError> {
+    variant_to_column(&expression.into())
+}
+
+/// [Expression] from column.
+pub fn expression_from_column(column: &str) -> Result<Expression, StoreError> {
+    Expression::from_variant(&variant_from_column(column)?)
+        .map_err(|error| StoreError::Backend(format!("decode expression: {}", error)))
+}
+
+/// [Call] to column.
+pub fn call_to_column(call: &Call) -> Result<String, StoreError> {
+    variant_to_column(&call.into())
+}
+
+/// [Call] from column.
+pub fn call_from_column(column: &str) -> Result<Call, StoreError> {
+    Call::from_variant(&variant_from_column(column)?)
+        .map_err(|error| StoreError::Backend(format!("decode call: {}", error)))
+}
+
+/// [PropertyDefinition] to column.
+pub fn property_definition_to_column(property_definition: &PropertyDefinition) -> Result<String, StoreError> {
+    variant_to_column(&property_definition.to_variant())
+}
+
+/// [PropertyDefinition] from column.
+pub fn property_definition_from_column(column: &str) -> Result<PropertyDefinition, StoreError> {
+    PropertyDefinition::from_variant(&variant_from_column(column)?)
+        .map_err(|error| StoreError::Backend(format!("decode property definition: {}", error)))
+}
+
+/// [LifecycleState] from column.
+pub fn lifecycle_state_from_column(column: &str) -> Result<LifecycleState, StoreError> {
+    LifecycleState::parse(column).map_err(StoreError::Backend)
+}
+
+/// [VertexFinder] to column.
+pub fn vertex_finder_to_column(vertex_finder: &VertexFinder) -> Result<String, StoreError> {
+    variant_to_column(&vertex_finder.to_variant())
+}
+
+/// [VertexFinder] from column.
+pub fn vertex_finder_from_column(column: &str) -> Result<VertexFinder, StoreError> {
+    VertexFinder::from_variant(&variant_from_column(column)?)
+        .map_err(|error| StoreError::Backend(format!("decode vertex finder: {}", error)))
+}
+
+// Utils
+
+fn malformed(name: &str, variant: &Variant<WithoutAnnotations>) -> StoreError {
+    StoreError::Backend(format!("malformed {}: {}", name, variant.type_name()))
+}
@@ -0,0 +1,719 @@
+use super::{
+    super::{super::data::*, errors::*},
+    codec::*,
+};
+
+use {kutil::std::immutable::*, rusqlite::*, std::collections::*};
+
+//
+// SqliteEntity
+//
+
+/// Entity that can be read from and written to SQLite.
+pub trait SqliteEntity
+where
+    Self: Clone + Into<Entity> + Revisioned + Sized,
+{
+    /// ID.
+    fn get_id(&self) -> &ID;
+
+    /// Read.
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError>;
+
+    /// Write.
+    ///
+    /// Assumes that the entity's rows have already been deleted.
+    fn write(&self, connection: &Connection) -> Result<(), StoreError>;
+}
+
+/// Table for a kind.
+pub fn table(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Class => "classes",
+        Kind::VertexTemplate => "vertex_templates",
+        Kind::EdgeTemplate => "edge_templates",
+        Kind::Vertex => "vertexes",
+        Kind::Edge => "edges",
+    }
+}
+
+/// Read the revision of an entity.
+///
+/// Returns 0 if the entity does not exist.
+pub fn read_revision(connection: &Connection, id: &ID) -> Result<u64, StoreError> {
+    Ok(connection
+        .query_row(
+            &format!("SELECT revision FROM {} WHERE directory = ?1 AND id = ?2", table(id.kind)),
+            params![directory_to_column(&id.directory), &*id.id],
+            |row| row.get::<_, u64>(0),
+        )
+        .optional()?
+        .unwrap_or(0))
+}
+
+/// Delete all the rows of an entity.
+///
+/// Returns true if the entity existed.
+pub fn delete_rows(connection: &Connection, id: &ID) -> Result<bool, StoreError> {
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+    let id_ = &*id.id;
+
+    for table in [
+        "links",
+        "properties",
+        "property_class_ids",
+        "property_definitions",
+        "event_handlers",
+        "lifecycle_transitions",
+        "lifecycle_states",
+        "lifecycle_history",
+    ] {
+        connection.execute(
+            &format!("DELETE FROM {} WHERE kind = ?1 AND directory = ?2 AND id = ?3", table),
+            params![kind, directory, id_],
+        )?;
+    }
+
+    let deleted = connection.execute(
+        &format!("DELETE FROM {} WHERE directory = ?1 AND id = ?2", table(id.kind)),
+        params![directory, id_],
+    )?;
+
+    Ok(deleted > 0)
+}
+
+impl SqliteEntity for Class {
+    fn get_id(&self) -> &ID {
+        &self.id
+    }
+
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError> {
+        let row = connection
+            .query_row(
+                "SELECT revision, metadata FROM classes WHERE directory = ?1 AND id = ?2",
+                params![directory_to_column(&id.directory), &*id.id],
+                |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+
+        let Some((revision, metadata)) = row else {
+            return Ok(None);
+        };
+
+        let mut class = Class::new_with(id.clone());
+        class.revision = revision;
+        class.metadata = metadata_from_column(&metadata)?;
+        class.parent_class_ids = read_links(connection, id, "parent_class_ids", Kind::Class)?;
+        class.child_class_ids = read_links(connection, id, "child_class_ids", Kind::Class)?;
+        class.property_definitions = read_property_definitions(connection, id)?;
+        class.event_handlers = read_event_handlers(connection, id)?;
+        class.lifecycle_transitions = read_lifecycle_transitions(connection, "lifecycle_transitions", id)?;
+        Ok(Some(class))
+    }
+
+    fn write(&self, connection: &Connection) -> Result<(), StoreError> {
+        connection.execute(
+            "INSERT INTO classes (directory, id, revision, metadata) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                directory_to_column(&self.id.directory),
+                &*self.id.id,
+                self.revision,
+                metadata_to_column(&self.metadata)?
+            ],
+        )?;
+
+        write_links(connection, &self.id, "parent_class_ids", &self.parent_class_ids)?;
+        write_links(connection, &self.id, "child_class_ids", &self.child_class_ids)?;
+        write_property_definitions(connection, &self.id, &self.property_definitions)?;
+        write_event_handlers(connection, &self.id, &self.event_handlers)?;
+        write_lifecycle_transitions(connection, "lifecycle_transitions", &self.id, &self.lifecycle_transitions)
+    }
+}
+
+impl SqliteEntity for VertexTemplate {
+    fn get_id(&self) -> &ID {
+        &self.template.id
+    }
+
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError> {
+        let row = connection
+            .query_row(
+                "SELECT revision, metadata, containing_vertex_template_id FROM vertex_templates \
+                WHERE directory = ?1 AND id = ?2",
+                params![directory_to_column(&id.directory), &*id.id],
+                |row| Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)),
+            )
+            .optional()?;
+
+        let Some((revision, metadata, containing_vertex_template_id)) = row else {
+            return Ok(None);
+        };
+
+        let mut vertex_template = VertexTemplate::new_with(id.clone(), None);
+        vertex_template.template = read_template(connection, id, revision, &metadata)?;
+        vertex_template.containing_vertex_template_id = containing_vertex_template_id
+            .map(|containing_vertex_template_id| id_from_column(Kind::VertexTemplate, &containing_vertex_template_id));
+        vertex_template.contained_vertex_template_ids =
+            read_links(connection, id, "contained_vertex_template_ids", Kind::VertexTemplate)?;
+        vertex_template.outgoing_edge_template_ids =
+            read_links(connection, id, "outgoing_edge_template_ids", Kind::EdgeTemplate)?;
+        Ok(Some(vertex_template))
+    }
+
+    fn write(&self, connection: &Connection) -> Result<(), StoreError> {
+        let id = &self.template.id;
+
+        connection.execute(
+            "INSERT INTO vertex_templates (directory, id, revision, metadata, containing_vertex_template_id) \
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                directory_to_column(&id.directory),
+                &*id.id,
+                self.template.revision,
+                metadata_to_column(&self.template.metadata)?,
+                self.containing_vertex_template_id.as_ref().map(id_to_column),
+            ],
+        )?;
+
+        write_template(connection, &self.template)?;
+        write_links(connection, id, "contained_vertex_template_ids", &self.contained_vertex_template_ids)?;
+        write_links(connection, id, "outgoing_edge_template_ids", &self.outgoing_edge_template_ids)
+    }
+}
+
+impl SqliteEntity for EdgeTemplate {
+    fn get_id(&self) -> &ID {
+        &self.template.id
+    }
+
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError> {
+        let row = connection
+            .query_row(
+                "SELECT revision, metadata, containing_source_vertex_template_id, target_vertex_id, \
+                target_vertex_finder FROM edge_templates WHERE directory = ?1 AND id = ?2",
+                params![directory_to_column(&id.directory), &*id.id],
+                |row| {
+                    Ok((
+                        row.get::<_, u64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((revision, metadata, containing_source_vertex_template_id, target_vertex_id, target_vertex_finder)) =
+            row
+        else {
+            return Ok(None);
+        };
+
+        let target_selector = match (target_vertex_id, target_vertex_finder) {
+            (Some(target_vertex_id), _) => VertexSelector::VertexID(id_from_column(Kind::Vertex, &target_vertex_id)),
+            (None, Some(target_vertex_finder)) => {
+                VertexSelector::Finder(vertex_finder_from_column(&target_vertex_finder)?)
+            }
+            (None, None) => return Err(StoreError::Backend(format!("edge template has no target selector: {}", id))),
+        };
+
+        let mut edge_template = EdgeTemplate::new_with(
+            id.clone(),
+            id_from_column(Kind::VertexTemplate, &containing_source_vertex_template_id),
+            target_selector,
+        );
+        edge_template.template = read_template(connection, id, revision, &metadata)?;
+        Ok(Some(edge_template))
+    }
+
+    fn write(&self, connection: &Connection) -> Result<(), StoreError> {
+        let id = &self.template.id;
+
+        let (target_vertex_id, target_vertex_finder) = match &self.target_selector {
+            VertexSelector::VertexID(target_vertex_id) => (Some(id_to_column(target_vertex_id)), None),
+            VertexSelector::Finder(vertex_finder) => (None, Some(vertex_finder_to_column(vertex_finder)?)),
+        };
+
+        connection.execute(
+            "INSERT INTO edge_templates \
+            (directory, id, revision, metadata, containing_source_vertex_template_id, target_vertex_id, \
+            target_vertex_finder) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                directory_to_column(&id.directory),
+                &*id.id,
+                self.template.revision,
+                metadata_to_column(&self.template.metadata)?,
+                id_to_column(&self.containing_source_vertex_template_id),
+                target_vertex_id,
+                target_vertex_finder,
+            ],
+        )?;
+
+        write_template(connection, &self.template)
+    }
+}
+
+impl SqliteEntity for Vertex {
+    fn get_id(&self) -> &ID {
+        &self.instance.id
+    }
+
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError> {
+        let row = connection
+            .query_row(
+                "SELECT revision, metadata, origin_template_id, containing_vertex_id \
+                FROM vertexes WHERE directory = ?1 AND id = ?2",
+                params![directory_to_column(&id.directory), &*id.id],
+                |row| {
+                    Ok((
+                        row.get::<_, u64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((revision, metadata, origin_template_id, containing_vertex_id)) = row else {
+            return Ok(None);
+        };
+
+        let origin_template_id =
+            origin_template_id.map(|origin_template_id| id_from_column(Kind::VertexTemplate, &origin_template_id));
+
+        let mut vertex = Vertex::new_with(id.clone(), origin_template_id.clone());
+        vertex.instance = read_instance(connection, id, origin_template_id, revision, &metadata)?;
+        vertex.containing_vertex_id =
+            containing_vertex_id.map(|containing_vertex_id| id_from_column(Kind::Vertex, &containing_vertex_id));
+        vertex.contained_vertex_ids = read_links(connection, id, "contained_vertex_ids", Kind::Vertex)?;
+        vertex.outgoing_edge_ids = read_links(connection, id, "outgoing_edge_ids", Kind::Edge)?;
+        vertex.incoming_edge_ids = read_links(connection, id, "incoming_edge_ids", Kind::Edge)?;
+        Ok(Some(vertex))
+    }
+
+    fn write(&self, connection: &Connection) -> Result<(), StoreError> {
+        let id = &self.instance.id;
+
+        connection.execute(
+            "INSERT INTO vertexes (directory, id, revision, metadata, origin_template_id, containing_vertex_id) \
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                directory_to_column(&id.directory),
+                &*id.id,
+                self.instance.revision,
+                metadata_to_column(&self.instance.metadata)?,
+                self.instance.origin_template_id.as_ref().map(id_to_column),
+                self.containing_vertex_id.as_ref().map(id_to_column),
+            ],
+        )?;
+
+        write_instance(connection, &self.instance)?;
+        write_links(connection, id, "contained_vertex_ids", &self.contained_vertex_ids)?;
+        write_links(connection, id, "outgoing_edge_ids", &self.outgoing_edge_ids)?;
+        write_links(connection, id, "incoming_edge_ids", &self.incoming_edge_ids)
+    }
+}
+
+impl SqliteEntity for Edge {
+    fn get_id(&self) -> &ID {
+        &self.instance.id
+    }
+
+    fn read(connection: &Connection, id: &ID) -> Result<Option<Self>, StoreError> {
+        let row = connection
+            .query_row(
+                "SELECT revision, metadata, origin_template_id, source_vertex_id, target_vertex_id \
+                FROM edges WHERE directory = ?1 AND id = ?2",
+                params![directory_to_column(&id.directory), &*id.id],
+                |row| {
+                    Ok((
+                        row.get::<_, u64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((revision, metadata, origin_template_id, source_vertex_id, target_vertex_id)) = row else {
+            return Ok(None);
+        };
+
+        let origin_template_id =
+            origin_template_id.map(|origin_template_id| id_from_column(Kind::EdgeTemplate, &origin_template_id));
+
+        Ok(Some(Edge {
+            instance: read_instance(connection, id, origin_template_id, revision, &metadata)?,
+            source_vertex_id: id_from_column(Kind::Vertex, &source_vertex_id),
+            target_vertex_id: id_from_column(Kind::Vertex, &target_vertex_id),
+        }))
+    }
+
+    fn write(&self, connection: &Connection) -> Result<(), StoreError> {
+        let id = &self.instance.id;
+
+        connection.execute(
+            "INSERT INTO edges \
+            (directory, id, revision, metadata, origin_template_id, source_vertex_id, target_vertex_id) \
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                directory_to_column(&id.directory),
+                &*id.id,
+                self.instance.revision,
+                metadata_to_column(&self.instance.metadata)?,
+                self.instance.origin_template_id.as_ref().map(id_to_column),
+                id_to_column(&self.source_vertex_id),
+                id_to_column(&self.target_vertex_id),
+            ],
+        )?;
+
+        write_instance(connection, &self.instance)
+    }
+}
+
+// Template
+
+fn read_template(connection: &Connection, id: &ID, revision: u64, metadata: &str) -> Result<Template, StoreError> {
+    let mut template = Template::new(id.clone());
+    template.revision = revision;
+    template.metadata = metadata_from_column(metadata)?;
+    template.class_ids = read_links(connection, id, "class_ids", Kind::Class)?;
+    template.property_templates = read_properties(connection, id)?;
+    template.event_handlers = read_event_handlers(connection, id)?;
+    Ok(template)
+}
+
+fn write_template(connection: &Connection, template: &Template) -> Result<(), StoreError> {
+    write_links(connection, &template.id, "class_ids", &template.class_ids)?;
+    write_properties(connection, &template.id, &template.property_templates)?;
+    write_event_handlers(connection, &template.id, &template.event_handlers)
+}
+
+// Instance
+
+fn read_instance(
+    connection: &Connection,
+    id: &ID,
+    origin_template_id: Option<ID>,
+    revision: u64,
+    metadata: &str,
+) -> Result<Instance, StoreError> {
+    let mut instance = Instance::new_with(id.clone(), origin_template_id);
+    instance.revision = revision;
+    instance.metadata = metadata_from_column(metadata)?;
+    instance.class_ids = read_links(connection, id, "class_ids", Kind::Class)?;
+    instance.properties = read_properties(connection, id)?;
+    instance.event_handlers = read_event_handlers(connection, id)?;
+    instance.lifecycle_state = read_lifecycle_state(connection, id)?;
+    instance.lifecycle_history = read_lifecycle_transitions(connection, "lifecycle_history", id)?;
+    Ok(instance)
+}
+
+fn write_instance(connection: &Connection, instance: &Instance) -> Result<(), StoreError> {
+    write_links(connection, &instance.id, "class_ids", &instance.class_ids)?;
+    write_properties(connection, &instance.id, &instance.properties)?;
+    write_event_handlers(connection, &instance.id, &instance.event_handlers)?;
+    write_lifecycle_state(connection, &instance.id, instance.lifecycle_state)?;
+    write_lifecycle_transitions(connection, "lifecycle_history", &instance.id, &instance.lifecycle_history)
+}
+
+// Links
+
+fn read_links(connection: &Connection, id: &ID, relation: &str, kind: Kind) -> Result<Vec<ID>, StoreError> {
+    let mut statement = connection.prepare_cached(
+        "SELECT target_id FROM links WHERE kind = ?1 AND directory = ?2 AND id = ?3 AND relation = ?4 \
+        ORDER BY position",
+    )?;
+
+    let rows = statement
+        .query_map(params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id, relation], |row| {
+            row.get::<_, String>(0)
+        })?;
+
+    let mut ids = Vec::default();
+    for target_id in rows {
+        ids.push(id_from_column(kind, &target_id?));
+    }
+    Ok(ids)
+}
+
+fn write_links(connection: &Connection, id: &ID, relation: &str, target_ids: &[ID]) -> Result<(), StoreError> {
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO links (kind, directory, id, relation, position, target_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+    for (position, target_id) in target_ids.iter().enumerate() {
+        statement.execute(params![kind, directory, &*id.id, relation, position, id_to_column(target_id)])?;
+    }
+
+    Ok(())
+}
+
+// Properties
+
+fn read_properties(connection: &Connection, id: &ID) -> Result<BTreeMap<ByteString, Property>, StoreError> {
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+
+    let mut statement = connection.prepare_cached(
+        "SELECT name, metadata, value, updater, validator, read_only FROM properties \
+        WHERE kind = ?1 AND directory = ?2 AND id = ?3",
+    )?;
+
+    let rows = statement.query_map(params![kind, directory, &*id.id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, bool>(5)?,
+        ))
+    })?;
+
+    let mut class_ids_statement = connection.prepare_cached(
+        "SELECT class_id FROM property_class_ids WHERE kind = ?1 AND directory = ?2 AND id = ?3 AND name = ?4 \
+        ORDER BY position",
+    )?;
+
+    let mut properties = BTreeMap::default();
+    for row in rows {
+        let (name, metadata, value, updater, validator, read_only) = row?;
+
+        let mut property = Property::new(
+            value.map(|value| variant_from_column(&value)).transpose()?,
+            updater.map(|updater| expression_from_column(&updater)).transpose()?,
+            validator.map(|validator| expression_from_column(&validator)).transpose()?,
+            read_only,
+        );
+
+        property.metadata = metadata_from_column(&metadata)?;
+
+        let class_ids =
+            class_ids_statement.query_map(params![kind, directory, &*id.id, name], |row| row.get::<_, String>(0))?;
+        for class_id in class_ids {
+            property.class_ids.push(id_from_column(Kind::Class, &class_id?));
+        }
+
+        properties.insert(name.into(), property);
+    }
+
+    Ok(properties)
+}
+
+fn write_properties(
+    connection: &Connection,
+    id: &ID,
+    properties: &BTreeMap<ByteString, Property>,
+) -> Result<(), StoreError> {
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO properties (kind, directory, id, name, metadata, value, updater, validator, read_only) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+
+    let mut class_ids_statement = connection.prepare_cached(
+        "INSERT INTO property_class_ids (kind, directory, id, name, position, class_id) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+
+    for (name, property) in properties {
+        let name: &str = name.as_ref();
+
+        statement.execute(params![
+            kind,
+            directory,
+            &*id.id,
+            name,
+            metadata_to_column(&property.metadata)?,
+            property.value.as_ref().map(variant_to_column).transpose()?,
+            property.updater.as_ref().map(expression_to_column).transpose()?,
+            property.validator.as_ref().map(expression_to_column).transpose()?,
+            property.read_only,
+        ])?;
+
+        for (position, class_id) in property.class_ids.iter().enumerate() {
+            class_ids_statement.execute(params![kind, directory, &*id.id, name, position, id_to_column(class_id)])?;
+        }
+    }
+
+    Ok(())
+}
+
+// Property definitions
+
+fn read_property_definitions(
+    connection: &Connection,
+    id: &ID,
+) -> Result<BTreeMap<ByteString, PropertyDefinition>, StoreError> {
+    let mut statement = connection.prepare_cached(
+        "SELECT name, definition FROM property_definitions WHERE kind = ?1 AND directory = ?2 AND id = ?3",
+    )?;
+
+    let rows = statement
+        .query_map(params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+    let mut property_definitions = BTreeMap::default();
+    for row in rows {
+        let (name, definition) = row?;
+        property_definitions.insert(name.into(), property_definition_from_column(&definition)?);
+    }
+    Ok(property_definitions)
+}
+
+fn write_property_definitions(
+    connection: &Connection,
+    id: &ID,
+    property_definitions: &BTreeMap<ByteString, PropertyDefinition>,
+) -> Result<(), StoreError> {
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO property_definitions (kind, directory, id, name, definition) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+    for (name, property_definition) in property_definitions {
+        let name: &str = name.as_ref();
+        statement.execute(params![
+            kind,
+            directory,
+            &*id.id,
+            name,
+            property_definition_to_column(property_definition)?
+        ])?;
+    }
+
+    Ok(())
+}
+
+// Event handlers
+
+fn read_event_handlers(connection: &Connection, id: &ID) -> Result<Vec<EventHandler>, StoreError> {
+    let mut statement = connection.prepare_cached(
+        "SELECT event_id, call FROM event_handlers WHERE kind = ?1 AND directory = ?2 AND id = ?3 ORDER BY position",
+    )?;
+
+    let rows = statement
+        .query_map(params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+    let mut event_handlers = Vec::default();
+    for row in rows {
+        let (event_id, call) = row?;
+        event_handlers.push(EventHandler { event_id: event_id.into(), call: call_from_column(&call)? });
+    }
+    Ok(event_handlers)
+}
+
+fn write_event_handlers(connection: &Connection, id: &ID, event_handlers: &[EventHandler]) -> Result<(), StoreError> {
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO event_handlers (kind, directory, id, position, event_id, call) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+    for (position, event_handler) in event_handlers.iter().enumerate() {
+        let event_id: &str = event_handler.event_id.as_ref();
+        statement.execute(params![
+            kind,
+            directory,
+            &*id.id,
+            position,
+            event_id,
+            call_to_column(&event_handler.call)?
+        ])?;
+    }
+
+    Ok(())
+}
+
+// Lifecycle
+
+fn read_lifecycle_state(connection: &Connection, id: &ID) -> Result<LifecycleState, StoreError> {
+    let state = connection
+        .query_row(
+            "SELECT state FROM lifecycle_states WHERE kind = ?1 AND directory = ?2 AND id = ?3",
+            params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+
+    Ok(match state {
+        Some(state) => lifecycle_state_from_column(&state)?,
+        None => Default::default(),
+    })
+}
+
+fn write_lifecycle_state(connection: &Connection, id: &ID, state: LifecycleState) -> Result<(), StoreError> {
+    if state != LifecycleState::Initial {
+        connection.execute(
+            "INSERT INTO lifecycle_states (kind, directory, id, state) VALUES (?1, ?2, ?3, ?4)",
+            params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id, state.to_string()],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn read_lifecycle_transitions(
+    connection: &Connection,
+    table: &str,
+    id: &ID,
+) -> Result<Vec<LifecycleTransition>, StoreError> {
+    let mut statement = connection.prepare_cached(&format!(
+        "SELECT from_state, to_state FROM {} WHERE kind = ?1 AND directory = ?2 AND id = ?3 ORDER BY position",
+        table
+    ))?;
+
+    let rows = statement
+        .query_map(params![id.kind.to_string(), directory_to_column(&id.directory), &*id.id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+    let mut transitions = Vec::default();
+    for row in rows {
+        let (from, to) = row?;
+        transitions
+            .push(LifecycleTransition::new(lifecycle_state_from_column(&from)?, lifecycle_state_from_column(&to)?));
+    }
+    Ok(transitions)
+}
+
+fn write_lifecycle_transitions(
+    connection: &Connection,
+    table: &str,
+    id: &ID,
+    transitions: &[LifecycleTransition],
+) -> Result<(), StoreError> {
+    let mut statement = connection.prepare_cached(&format!(
+        "INSERT INTO {} (kind, directory, id, position, from_state, to_state) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        table
+    ))?;
+
+    let kind = id.kind.to_string();
+    let directory = directory_to_column(&id.directory);
+    for (position, transition) in transitions.iter().enumerate() {
+        statement.execute(params![
+            kind,
+            directory,
+            &*id.id,
+            position,
+            transition.from.to_string(),
+            transition.to.to_string()
+        ])?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,8 @@
+mod backend;
+mod codec;
+mod entity;
+mod schema;
+mod store;
+
+#[allow(unused_imports)]
+pub use {backend::*, store::*};
@@ -0,0 +1,167 @@
+// Entities are keyed by directory and ID. Referenced IDs are stored in their full text form, with
+// their kinds implied by the columns.
+//
+// Ordered lists of referenced IDs are stored in the "links" table, where "relation" is the name of
+// the entity field.
+//
+// Properties (and property templates) are stored in the "properties" table, keyed by the kind,
+// directory, and ID of their owner.
+//
+// Property definitions of classes are stored in the "property_definitions" table, as XJSON text.
+
+/// SQLite schema.
+pub const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS next_ids (
+    kind TEXT NOT NULL PRIMARY KEY,
+    next_id INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS classes (
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    metadata TEXT NOT NULL,
+    PRIMARY KEY (directory, id)
+);
+
+CREATE INDEX IF NOT EXISTS classes_directory ON classes (directory);
+
+CREATE TABLE IF NOT EXISTS vertex_templates (
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    metadata TEXT NOT NULL,
+    containing_vertex_template_id TEXT,
+    PRIMARY KEY (directory, id)
+);
+
+CREATE INDEX IF NOT EXISTS vertex_templates_directory ON vertex_templates (directory);
+
+CREATE TABLE IF NOT EXISTS edge_templates (
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    metadata TEXT NOT NULL,
+    containing_source_vertex_template_id TEXT NOT NULL,
+    target_vertex_id TEXT,
+    target_vertex_finder TEXT,
+    PRIMARY KEY (directory, id)
+);
+
+CREATE INDEX IF NOT EXISTS edge_templates_directory ON edge_templates (directory);
+CREATE INDEX IF NOT EXISTS edge_templates_containing_source_vertex_template_id
+    ON edge_templates (containing_source_vertex_template_id);
+
+CREATE TABLE IF NOT EXISTS vertexes (
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    metadata TEXT NOT NULL,
+    origin_template_id TEXT,
+    containing_vertex_id TEXT,
+    PRIMARY KEY (directory, id)
+);
+
+CREATE INDEX IF NOT EXISTS vertexes_directory ON vertexes (directory);
+CREATE INDEX IF NOT EXISTS vertexes_origin_template_id ON vertexes (origin_template_id);
+
+CREATE TABLE IF NOT EXISTS edges (
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    metadata TEXT NOT NULL,
+    origin_template_id TEXT,
+    source_vertex_id TEXT NOT NULL,
+    target_vertex_id TEXT NOT NULL,
+    PRIMARY KEY (directory, id)
+);
+
+CREATE INDEX IF NOT EXISTS edges_directory ON edges (directory);
+CREATE INDEX IF NOT EXISTS edges_origin_template_id ON edges (origin_template_id);
+CREATE INDEX IF NOT EXISTS edges_source_vertex_id ON edges (source_vertex_id);
+CREATE INDEX IF NOT EXISTS edges_target_vertex_id ON edges (target_vertex_id);
+
+CREATE TABLE IF NOT EXISTS links (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    relation TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    target_id TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, relation, position)
+);
+
+CREATE INDEX IF NOT EXISTS links_target_id ON links (target_id);
+
+CREATE TABLE IF NOT EXISTS properties (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    value TEXT,
+    updater TEXT,
+    validator TEXT,
+    read_only INTEGER NOT NULL,
+    PRIMARY KEY (kind, directory, id, name)
+);
+
+CREATE TABLE IF NOT EXISTS property_class_ids (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    class_id TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, name, position)
+);
+
+CREATE INDEX IF NOT EXISTS property_class_ids_class_id ON property_class_ids (class_id);
+
+CREATE TABLE IF NOT EXISTS property_definitions (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, name)
+);
+
+CREATE TABLE IF NOT EXISTS event_handlers (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    event_id TEXT NOT NULL,
+    call TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, position)
+);
+
+CREATE TABLE IF NOT EXISTS lifecycle_transitions (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    from_state TEXT NOT NULL,
+    to_state TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, position)
+);
+
+CREATE TABLE IF NOT EXISTS lifecycle_states (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    state TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id)
+);
+
+CREATE TABLE IF NOT EXISTS lifecycle_history (
+    kind TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    from_state TEXT NOT NULL,
+    to_state TEXT NOT NULL,
+    PRIMARY KEY (kind, directory, id, position)
+);
+"#;
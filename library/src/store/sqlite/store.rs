@@ -0,0 +1,597 @@
+use super::{
+    super::{super::data::*, errors::*, events::*, query::*, store::*, wrapper::*},
+    backend::*,
+    codec::*,
+    entity::*,
+};
+
+use {
+    rusqlite::*,
+    std::{collections::*, sync::*},
+};
+
+//
+// SqliteStore
+//
+
+/// SQLite store.
+///
+/// All clones share the same connection.
+#[derive(Clone)]
+pub struct SqliteStore {
+    /// Backend.
+    pub backend: Arc<SqliteStoreBackend>,
+}
+
+impl SqliteStore {
+    /// Constructor.
+    pub fn new(backend: Arc<SqliteStoreBackend>) -> StoreWrapper<Self> {
+        StoreWrapper::new(Self { backend })
+    }
+
+    // Read an entity.
+    fn read<EntityT>(&self, id: &ID) -> Result<Option<EntityT>, StoreError>
+    where
+        EntityT: SqliteEntity,
+    {
+        let connection = self.backend.connection.lock()?;
+        EntityT::read(&connection, id)
+    }
+
+    // Read entities.
+    fn read_all<EntityT>(&self, ids: &[ID]) -> Result<Vec<EntityT>, StoreError>
+    where
+        EntityT: SqliteEntity,
+    {
+        let connection = self.backend.connection.lock()?;
+        let mut entities = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(entity) = EntityT::read(&connection, id)? {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    }
+
+    // Select IDs of a kind in the query's directories.
+    fn select_ids(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<ID>, StoreError> {
+        let connection = self.backend.connection.lock()?;
+        let table = table(kind);
+
+        let mut ids = Vec::default();
+        let mut add_ids = |sql: &str, params: &[&dyn ToSql]| -> Result<(), StoreError> {
+            let mut statement = connection.prepare_cached(sql)?;
+            let rows = statement.query_map(params, |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (directory, id) = row?;
+                ids.push(ID::new_for(kind, directory_from_column(&directory), id.into()));
+            }
+            Ok(())
+        };
+
+        match &query.directories {
+            Some(directories) => {
+                for directory in directories {
+                    let directory = directory_to_column(directory);
+                    if !query.recursive {
+                        add_ids(&format!("SELECT directory, id FROM {} WHERE directory = ?1", table), &[&directory])?;
+                    } else if directory.is_empty() {
+                        add_ids(&format!("SELECT directory, id FROM {}", table), &[])?;
+                    } else {
+                        // Subdirectories are in the range between "directory:" and "directory;"
+                        add_ids(
+                            &format!(
+                                "SELECT directory, id FROM {} \
+                                WHERE directory = ?1 OR (directory >= ?2 AND directory < ?3)",
+                                table
+                            ),
+                            &[&directory, &format!("{}:", directory), &format!("{};", directory)],
+                        )?;
+                    }
+                }
+
+                // Overlapping directories may select the same IDs more than once
+                let mut unique_ids = HashSet::new();
+                ids.retain(|id| unique_ids.insert(id.clone()));
+            }
+
+            None => add_ids(&format!("SELECT directory, id FROM {}", table), &[])?,
+        }
+
+        Ok(ids)
+    }
+
+    // Select IDs of a kind for which a column equals a referenced ID.
+    fn select_ids_by_reference(&self, kind: Kind, column: &str, id: &ID) -> Result<Vec<ID>, StoreError> {
+        let connection = self.backend.connection.lock()?;
+        let mut statement =
+            connection.prepare_cached(&format!("SELECT directory, id FROM {} WHERE {} = ?1", table(kind), column))?;
+        let rows = statement
+            .query_map(params![id_to_column(id)], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut ids = Vec::default();
+        for row in rows {
+            let (directory, id) = row?;
+            ids.push(ID::new_for(kind, directory_from_column(&directory), id.into()));
+        }
+        Ok(ids)
+    }
+
+    // Select IDs of a kind that refer to a class.
+    fn select_ids_by_class(&self, kind: Kind, class_id: &ID) -> Result<Vec<ID>, StoreError> {
+        let connection = self.backend.connection.lock()?;
+        let mut statement = connection.prepare_cached(
+            "SELECT directory, id FROM links \
+            WHERE kind = ?1 AND target_id = ?2 AND relation IN ('class_ids', 'parent_class_ids', 'child_class_ids') \
+            UNION SELECT directory, id FROM property_class_ids WHERE kind = ?1 AND class_id = ?2",
+        )?;
+        let rows = statement.query_map(params![kind.to_string(), id_to_column(class_id)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut ids = Vec::default();
+        for row in rows {
+            let (directory, id) = row?;
+            ids.push(ID::new_for(kind, directory_from_column(&directory), id.into()));
+        }
+        Ok(ids)
+    }
+
+    // Insert an entity, recording it and publishing the change.
+    fn insert_entity<EntityT>(&self, entity: EntityT) -> Result<(), StoreError>
+    where
+        EntityT: SqliteEntity,
+    {
+        self.insert_entity_if_revision(entity, None)?;
+        Ok(())
+    }
+
+    // Insert an entity if the revision matches (when provided), recording it and publishing the
+    // change. Returns the new revision.
+    fn insert_entity_if_revision<EntityT>(
+        &self,
+        mut entity: EntityT,
+        expected_revision: Option<u64>,
+    ) -> Result<u64, StoreError>
+    where
+        EntityT: SqliteEntity,
+    {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let id = entity.get_id().clone();
+        let publish = self.backend.publisher.has_subscribers()?;
+
+        let (before, revision) = {
+            let mut connection = self.backend.connection.lock()?;
+
+            let revision = read_revision(&connection, &id)?;
+            check_revision(&id, expected_revision, revision)?;
+            entity.set_revision(revision + 1);
+
+            let before = if publish { EntityT::read(&connection, &id)? } else { None };
+
+            let savepoint = connection.savepoint()?;
+            delete_rows(&savepoint, &id)?;
+            entity.write(&savepoint)?;
+            savepoint.commit()?;
+
+            (before, revision + 1)
+        };
+
+        if publish {
+            self.backend.publish(id.clone(), before.map(|before| before.into()), Some(entity.into()))?;
+        }
+
+        self.backend.record(id)?;
+        Ok(revision)
+    }
+
+    // Remove an entity, recording it and publishing the change.
+    fn remove_entity<EntityT>(&self, id: &ID) -> Result<Option<EntityT>, StoreError>
+    where
+        EntityT: SqliteEntity,
+    {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        let before = {
+            let mut connection = self.backend.connection.lock()?;
+            let Some(before) = EntityT::read(&connection, id)? else {
+                return Ok(None);
+            };
+
+            let savepoint = connection.savepoint()?;
+            delete_rows(&savepoint, id)?;
+            savepoint.commit()?;
+
+            before
+        };
+
+        self.backend.publish(id.clone(), Some(before.clone().into()), None)?;
+        self.backend.record(id.clone())?;
+
+        Ok(Some(before))
+    }
+
+    // Update an entity (if it exists), recording it and publishing the change.
+    fn update_entity<EntityT, UpdateT>(&self, id: &ID, update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: SqliteEntity,
+        UpdateT: Fn(&mut EntityT),
+    {
+        self.update_entity_if(id, |entity: &mut EntityT| {
+            update(entity);
+            true
+        })
+    }
+
+    // Update an entity (if it exists) if the update function returns true, recording it and
+    // publishing the change.
+    //
+    // The entity is written only if it has not changed since we read it. Otherwise we read it
+    // again and retry, so that concurrent changes would not be lost.
+    fn update_entity_if<EntityT, UpdateT>(&self, id: &ID, update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: SqliteEntity,
+        UpdateT: Fn(&mut EntityT) -> bool,
+    {
+        while let Some(mut entity) = self.read::<EntityT>(id)? {
+            let revision = entity.get_revision();
+            if !update(&mut entity) {
+                break;
+            }
+
+            match self.insert_entity_if_revision(entity, Some(revision)) {
+                Err(StoreError::Conflict(_)) => {}
+                result => return result.map(|_| ()),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Update the entities for which the update function returns true, recording them and
+    // publishing the changes.
+    fn update_entities<EntityT, UpdateT>(&self, ids: &[ID], update: UpdateT) -> Result<(), StoreError>
+    where
+        EntityT: SqliteEntity,
+        UpdateT: Fn(&mut EntityT) -> bool,
+    {
+        for id in ids {
+            self.update_entity_if(id, &update)?;
+        }
+        Ok(())
+    }
+
+    // Read any entity.
+    fn read_entity(connection: &Connection, id: &ID) -> Result<Option<Entity>, StoreError> {
+        Ok(match id.kind {
+            Kind::Class => Class::read(connection, id)?.map(Into::into),
+            Kind::VertexTemplate => VertexTemplate::read(connection, id)?.map(Into::into),
+            Kind::EdgeTemplate => EdgeTemplate::read(connection, id)?.map(Into::into),
+            Kind::Vertex => Vertex::read(connection, id)?.map(Into::into),
+            Kind::Edge => Edge::read(connection, id)?.map(Into::into),
+        })
+    }
+}
+
+impl Store for SqliteStore {
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        self.backend.transaction_lock.begin()?;
+        let mut transactions = self.backend.transactions.lock()?;
+
+        if let Err(error) = self.backend.connection.lock()?.execute_batch("SAVEPOINT floria") {
+            self.backend.transaction_lock.end()?;
+            return Err(error.into());
+        }
+
+        transactions.push(Default::default());
+        Ok(())
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let mut transactions = self.backend.transactions.lock()?;
+        let transaction = transactions.pop().ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        self.backend.connection.lock()?.execute_batch("RELEASE floria")?;
+
+        // Merge into the enclosing transaction so that it can still be rolled back
+        if let Some(enclosing_transaction) = transactions.last_mut() {
+            enclosing_transaction.extend(transaction);
+        }
+
+        Ok(())
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        let _transaction_lock = self.backend.transaction_lock.hold()?;
+        self.backend.transaction_lock.end()?;
+
+        let mut transactions = self.backend.transactions.lock()?;
+        let transaction = transactions.pop().ok_or_else(|| StoreError::Transaction("not in a transaction".into()))?;
+
+        let connection = self.backend.connection.lock()?;
+
+        if !self.backend.publisher.has_subscribers()? {
+            connection.execute_batch("ROLLBACK TO floria; RELEASE floria")?;
+            return Ok(());
+        }
+
+        // Read the entities before and after the rollback in order to publish the changes
+
+        let mut unique_ids = HashSet::new();
+        let ids: Vec<_> = transaction.into_iter().filter(|id| unique_ids.insert(id.clone())).collect();
+
+        let mut befores = Vec::with_capacity(ids.len());
+        for id in &ids {
+            befores.push(Self::read_entity(&connection, id)?);
+        }
+
+        connection.execute_batch("ROLLBACK TO floria; RELEASE floria")?;
+
+        let mut changes = Vec::with_capacity(ids.len());
+        for (id, before) in ids.into_iter().zip(befores) {
+            let after = Self::read_entity(&connection, &id)?;
+            changes.push((id, before, after));
+        }
+
+        drop(connection);
+        drop(transactions);
+
+        for (id, before, after) in changes {
+            self.backend.publish(id, before, after)?;
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        self.backend.publisher.subscribe(filter)
+    }
+
+    fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
+        let kind = id.kind;
+        self.backend.id_strategy.create_id(id, &mut || self.backend.get_next_id(kind))
+    }
+
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        self.backend.get_next_ids()
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        for (kind, next_id) in next_ids {
+            self.backend.advance_next_id(*kind, *next_id)?;
+        }
+        Ok(())
+    }
+
+    fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_class(&self, class: Class) -> Result<(), StoreError> {
+        self.insert_entity(class)
+    }
+
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        let revision = class.get_revision();
+        self.insert_entity_if_revision(class, Some(revision))
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        self.transaction(|| {
+            let Some(class) = self.remove_entity::<Class>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entities(&self.select_ids_by_class(Kind::Class, id)?, |class: &mut Class| {
+                class.remove_class_id(id)
+            })?;
+            self.update_entities(
+                &self.select_ids_by_class(Kind::VertexTemplate, id)?,
+                |vertex_template: &mut VertexTemplate| vertex_template.template.remove_class_id(id),
+            )?;
+            self.update_entities(
+                &self.select_ids_by_class(Kind::EdgeTemplate, id)?,
+                |edge_template: &mut EdgeTemplate| edge_template.template.remove_class_id(id),
+            )?;
+            self.update_entities(&self.select_ids_by_class(Kind::Vertex, id)?, |vertex: &mut Vertex| {
+                vertex.instance.remove_class_id(id)
+            })?;
+            self.update_entities(&self.select_ids_by_class(Kind::Edge, id)?, |edge: &mut Edge| {
+                edge.instance.remove_class_id(id)
+            })?;
+
+            Ok(Some(class))
+        })
+    }
+
+    fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError> {
+        self.insert_entity(vertex_template)
+    }
+
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        let revision = vertex_template.get_revision();
+        self.insert_entity_if_revision(vertex_template, Some(revision))
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex_template) = self.remove_entity::<VertexTemplate>(id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_template_id in &vertex_template.contained_vertex_template_ids {
+                self.remove_vertex_template(contained_vertex_template_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_template_ids
+            let outgoing_edge_template_ids: Vec<_> = self
+                .select_ids_by_reference(Kind::EdgeTemplate, "containing_source_vertex_template_id", id)?
+                .into_iter()
+                .chain(vertex_template.outgoing_edge_template_ids.iter().cloned())
+                .collect();
+
+            for outgoing_edge_template_id in &outgoing_edge_template_ids {
+                self.remove_edge_template(outgoing_edge_template_id)?;
+            }
+
+            if let Some(containing_vertex_template_id) = &vertex_template.containing_vertex_template_id {
+                self.update_entity(
+                    containing_vertex_template_id,
+                    |containing_vertex_template: &mut VertexTemplate| {
+                        containing_vertex_template
+                            .contained_vertex_template_ids
+                            .retain(|contained_vertex_template_id| contained_vertex_template_id != id);
+                    },
+                )?;
+            }
+
+            self.update_entities(
+                &self.select_ids_by_reference(Kind::Vertex, "origin_template_id", id)?,
+                |vertex: &mut Vertex| vertex.instance.remove_origin_template_id(id),
+            )?;
+
+            Ok(Some(vertex_template))
+        })
+    }
+
+    fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
+        self.insert_entity(edge_template)
+    }
+
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        let revision = edge_template.get_revision();
+        self.insert_entity_if_revision(edge_template, Some(revision))
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        self.transaction(|| {
+            let Some(edge_template) = self.remove_entity::<EdgeTemplate>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entity(
+                &edge_template.containing_source_vertex_template_id,
+                |vertex_template: &mut VertexTemplate| {
+                    vertex_template
+                        .outgoing_edge_template_ids
+                        .retain(|outgoing_edge_template_id| outgoing_edge_template_id != id);
+                },
+            )?;
+
+            self.update_entities(
+                &self.select_ids_by_reference(Kind::Edge, "origin_template_id", id)?,
+                |edge: &mut Edge| edge.instance.remove_origin_template_id(id),
+            )?;
+
+            Ok(Some(edge_template))
+        })
+    }
+
+    fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.read(id)
+    }
+
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        let ids = self.select_ids(kind, query)?;
+        let connection = self.backend.connection.lock()?;
+
+        let mut entities = Vec::with_capacity(ids.len());
+        for id in &ids {
+            if let Some(entity) = Self::read_entity(&connection, id)?
+                && query.matches(&entity)
+            {
+                entities.push(entity);
+            }
+        }
+
+        Ok(entities)
+    }
+
+    fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
+        let query = StoreQuery::default().with_directories(directories);
+        self.read_all(&self.select_ids(Kind::Vertex, &query)?)
+    }
+
+    fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError> {
+        self.insert_entity(vertex)
+    }
+
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        let revision = vertex.get_revision();
+        self.insert_entity_if_revision(vertex, Some(revision))
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        self.transaction(|| {
+            let Some(vertex) = self.remove_entity::<Vertex>(id)? else {
+                return Ok(None);
+            };
+
+            for contained_vertex_id in &vertex.contained_vertex_ids {
+                self.remove_vertex(contained_vertex_id)?;
+            }
+
+            // Note that we are not relying only on outgoing_edge_ids and incoming_edge_ids
+            let edge_ids: Vec<_> = self
+                .select_ids_by_reference(Kind::Edge, "source_vertex_id", id)?
+                .into_iter()
+                .chain(self.select_ids_by_reference(Kind::Edge, "target_vertex_id", id)?)
+                .chain(vertex.outgoing_edge_ids.iter().cloned())
+                .chain(vertex.incoming_edge_ids.iter().cloned())
+                .collect();
+
+            for edge_id in &edge_ids {
+                self.remove_edge(edge_id)?;
+            }
+
+            if let Some(containing_vertex_id) = &vertex.containing_vertex_id {
+                self.update_entity(containing_vertex_id, |containing_vertex: &mut Vertex| {
+                    containing_vertex.contained_vertex_ids.retain(|contained_vertex_id| contained_vertex_id != id);
+                })?;
+            }
+
+            Ok(Some(vertex))
+        })
+    }
+
+    fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.read(id)
+    }
+
+    fn add_edge(&self, edge: Edge) -> Result<(), StoreError> {
+        self.insert_entity(edge)
+    }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        let revision = edge.get_revision();
+        self.insert_entity_if_revision(edge, Some(revision))
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        self.transaction(|| {
+            let Some(edge) = self.remove_entity::<Edge>(id)? else {
+                return Ok(None);
+            };
+
+            self.update_entity(&edge.source_vertex_id, |source_vertex: &mut Vertex| {
+                source_vertex.outgoing_edge_ids.retain(|outgoing_edge_id| outgoing_edge_id != id);
+            })?;
+
+            self.update_entity(&edge.target_vertex_id, |target_vertex: &mut Vertex| {
+                target_vertex.incoming_edge_ids.retain(|incoming_edge_id| incoming_edge_id != id);
+            })?;
+
+            Ok(Some(edge))
+        })
+    }
+}
@@ -1,6 +1,9 @@
-use super::{super::data::*, errors::*};
+use super::{super::data::*, errors::*, events::*, query::*};
 
-use compris::{annotate::*, normal::*};
+use {
+    compris::{annotate::*, normal::*},
+    std::collections::*,
+};
 
 //
 // Store
@@ -11,15 +14,60 @@ use compris::{annotate::*, normal::*};
 /// Implementations should ensure that cloning is cheap and clones always refer to the same shared
 /// state.
 pub trait Store {
+    /// Begin a transaction.
+    ///
+    /// Transactions can be nested. They belong to the current transaction owner, by default the
+    /// current thread (see [with_transaction_owner](super::with_transaction_owner)). While an owner
+    /// has an open transaction, transactions and writes by other owners, via any clone of the
+    /// store, block until it commits or rolls back its outermost transaction. Reads are not
+    /// blocked and so may see uncommitted writes.
+    fn begin_transaction(&self) -> Result<(), StoreError>;
+
+    /// Commit the current transaction.
+    ///
+    /// Fails if the current owner is not in a transaction.
+    fn commit_transaction(&self) -> Result<(), StoreError>;
+
+    /// Roll back the current transaction, undoing all writes made since it began.
+    ///
+    /// Created IDs are not rolled back. Fails if the current owner is not in a transaction.
+    fn rollback_transaction(&self) -> Result<(), StoreError>;
+
+    /// Subscribe to changes.
+    ///
+    /// Dropping the receiver unsubscribes.
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError>;
+
     /// Create ID.
     fn create_id(&self, id: &mut ID) -> Result<(), StoreError>;
 
+    /// Get the next ID counters.
+    ///
+    /// Each counter is the last ID created by [create_id](Store::create_id) for its kind.
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError>;
+
+    /// Advance the next ID counters.
+    ///
+    /// Counters never go back, so a counter is only changed if the provided value is higher.
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError>;
+
     /// Get class.
     fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError>;
 
     /// Add class.
     fn add_class(&self, class: Class) -> Result<(), StoreError>;
 
+    /// Update class if its revision matches that of the stored class.
+    ///
+    /// A revision of 0 means that the class must not already be stored. Returns the new
+    /// revision or [StoreError::Conflict] if it does not match.
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError>;
+
+    /// Remove class.
+    ///
+    /// Also removes all references to the class from other entities.
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError>;
+
     /// Get vertex template.
     fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError>;
 
@@ -28,26 +76,78 @@ pub trait Store {
     /// Checks to make sure we aren't creating infinite nesting.
     fn add_vertex_template(&self, vertex_template: VertexTemplate) -> Result<(), StoreError>;
 
+    /// Update vertex template if its revision matches that of the stored vertex template.
+    ///
+    /// A revision of 0 means that the vertex template must not already be stored. Returns the new
+    /// revision or [StoreError::Conflict] if it does not match.
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError>;
+
+    /// Remove vertex template.
+    ///
+    /// Also removes its contained vertex templates and its outgoing edge templates, detaches it
+    /// from its containing vertex template, and detaches instances that originated from it.
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError>;
+
     /// Get edge template.
     fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError>;
 
     /// Add edge template.
     fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError>;
 
+    /// Update edge template if its revision matches that of the stored edge template.
+    ///
+    /// A revision of 0 means that the edge template must not already be stored. Returns the new
+    /// revision or [StoreError::Conflict] if it does not match.
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError>;
+
+    /// Remove edge template.
+    ///
+    /// Also detaches it from its containing source vertex template and detaches instances that
+    /// originated from it.
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError>;
+
     /// Get vertex.
     fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError>;
 
+    /// Get entities of a kind that match a query.
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError>;
+
     /// Get vertexes.
+    ///
+    /// If directories are provided, only vertexes in those exact directories are returned.
     fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError>;
 
     /// Add vertex.
     fn add_vertex(&self, vertex: Vertex) -> Result<(), StoreError>;
 
+    /// Update vertex if its revision matches that of the stored vertex.
+    ///
+    /// A revision of 0 means that the vertex must not already be stored. Returns the new
+    /// revision or [StoreError::Conflict] if it does not match.
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError>;
+
+    /// Remove vertex.
+    ///
+    /// Also removes its contained vertexes (recursively) and all its outgoing and incoming edges,
+    /// and detaches it from its containing vertex.
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError>;
+
     /// Get edge.
     fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError>;
 
     /// Add edge.
     fn add_edge(&self, edge: Edge) -> Result<(), StoreError>;
+
+    /// Update edge if its revision matches that of the stored edge.
+    ///
+    /// A revision of 0 means that the edge must not already be stored. Returns the new
+    /// revision or [StoreError::Conflict] if it does not match.
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError>;
+
+    /// Remove edge.
+    ///
+    /// Also detaches it from its source and target vertexes.
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError>;
 }
 
 //
@@ -56,16 +156,55 @@ pub trait Store {
 
 /// Utilities for [Store].
 pub trait StoreUtilities {
+    /// Call a function within a transaction.
+    ///
+    /// The transaction is committed if the function succeeds and rolled back if it fails.
+    fn transaction<FunctionT, ResultT, ErrorT>(&self, function: FunctionT) -> Result<ResultT, ErrorT>
+    where
+        FunctionT: FnOnce() -> Result<ResultT, ErrorT>,
+        ErrorT: From<StoreError>;
+
     /// Get entity as [Variant].
     fn get_entity_as_variant<AnnotatedT>(&self, id: &ID) -> Result<Option<Variant<AnnotatedT>>, StoreError>
     where
         AnnotatedT: Annotated + Clone + Default;
+
+    /// Get classes that match a query.
+    fn get_classes(&self, query: &StoreQuery) -> Result<Vec<Class>, StoreError>;
+
+    /// Get vertex templates that match a query.
+    fn get_vertex_templates(&self, query: &StoreQuery) -> Result<Vec<VertexTemplate>, StoreError>;
+
+    /// Get edge templates that match a query.
+    fn get_edge_templates(&self, query: &StoreQuery) -> Result<Vec<EdgeTemplate>, StoreError>;
+
+    /// Get edges that match a query.
+    fn get_edges(&self, query: &StoreQuery) -> Result<Vec<Edge>, StoreError>;
 }
 
 impl<StoreT> StoreUtilities for StoreT
 where
     StoreT: Store,
 {
+    fn transaction<FunctionT, ResultT, ErrorT>(&self, function: FunctionT) -> Result<ResultT, ErrorT>
+    where
+        FunctionT: FnOnce() -> Result<ResultT, ErrorT>,
+        ErrorT: From<StoreError>,
+    {
+        self.begin_transaction()?;
+        match function() {
+            Ok(result) => {
+                self.commit_transaction()?;
+                Ok(result)
+            }
+
+            Err(error) => {
+                self.rollback_transaction()?;
+                Err(error)
+            }
+        }
+    }
+
     fn get_entity_as_variant<AnnotatedT>(&self, id: &ID) -> Result<Option<Variant<AnnotatedT>>, StoreError>
     where
         AnnotatedT: Annotated + Clone + Default,
@@ -87,4 +226,42 @@ where
             None => None,
         })
     }
+
+    fn get_classes(&self, query: &StoreQuery) -> Result<Vec<Class>, StoreError> {
+        Ok(self
+            .get_entities(Kind::Class, query)?
+            .into_iter()
+            .filter_map(|entity| if let Entity::Class(class) = entity { Some(class) } else { None })
+            .collect())
+    }
+
+    fn get_vertex_templates(&self, query: &StoreQuery) -> Result<Vec<VertexTemplate>, StoreError> {
+        Ok(self
+            .get_entities(Kind::VertexTemplate, query)?
+            .into_iter()
+            .filter_map(
+                |entity| {
+                    if let Entity::VertexTemplate(vertex_template) = entity { Some(vertex_template) } else { None }
+                },
+            )
+            .collect())
+    }
+
+    fn get_edge_templates(&self, query: &StoreQuery) -> Result<Vec<EdgeTemplate>, StoreError> {
+        Ok(self
+            .get_entities(Kind::EdgeTemplate, query)?
+            .into_iter()
+            .filter_map(
+                |entity| if let Entity::EdgeTemplate(edge_template) = entity { Some(edge_template) } else { None },
+            )
+            .collect())
+    }
+
+    fn get_edges(&self, query: &StoreQuery) -> Result<Vec<Edge>, StoreError> {
+        Ok(self
+            .get_entities(Kind::Edge, query)?
+            .into_iter()
+            .filter_map(|entity| if let Entity::Edge(edge) = entity { Some(edge) } else { None })
+            .collect())
+    }
 }
@@ -0,0 +1,138 @@
+use super::errors::*;
+
+use std::{
+    cell::*,
+    sync::{atomic::*, *},
+};
+
+static NEXT_TRANSACTION_OWNER: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_TRANSACTION_OWNER: u64 = new_transaction_owner();
+    static TRANSACTION_OWNER: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Create a new transaction owner.
+///
+/// See [with_transaction_owner].
+pub fn new_transaction_owner() -> u64 {
+    NEXT_TRANSACTION_OWNER.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Call a function as a transaction owner.
+///
+/// By default each thread is its own transaction owner. This allows a transaction to span calls
+/// that are made on different threads, e.g. from a thread pool.
+pub fn with_transaction_owner<FunctionT, ResultT>(owner: u64, function: FunctionT) -> ResultT
+where
+    FunctionT: FnOnce() -> ResultT,
+{
+    let previous = TRANSACTION_OWNER.replace(Some(owner));
+    let result = function();
+    TRANSACTION_OWNER.set(previous);
+    result
+}
+
+/// Current transaction owner.
+pub fn get_transaction_owner() -> u64 {
+    TRANSACTION_OWNER.get().unwrap_or_else(|| THREAD_TRANSACTION_OWNER.with(|owner| *owner))
+}
+
+//
+// TransactionLock
+//
+
+/// Transaction lock.
+///
+/// Only one owner at a time can have open transactions or be writing (see
+/// [get_transaction_owner]). Other owners block until the owner commits or rolls back its
+/// outermost transaction and finishes writing. The lock is reentrant for the owner.
+///
+/// Reads are not blocked, and so may see uncommitted writes.
+#[derive(Debug, Default)]
+pub struct TransactionLock {
+    state: Mutex<TransactionLockState>,
+    released: Condvar,
+}
+
+impl TransactionLock {
+    /// Begin a transaction, waiting for other owners to release the lock.
+    pub fn begin(&self) -> Result<(), StoreError> {
+        self.acquire()?.transactions += 1;
+        Ok(())
+    }
+
+    /// End the current transaction.
+    ///
+    /// Fails if the current owner is not in a transaction.
+    pub fn end(&self) -> Result<(), StoreError> {
+        let mut state = self.state.lock()?;
+
+        if (state.owner != Some(get_transaction_owner())) || (state.transactions == 0) {
+            return Err(StoreError::Transaction("not in a transaction".into()));
+        }
+
+        state.transactions -= 1;
+        self.release(state);
+        Ok(())
+    }
+
+    /// Hold the lock until the guard is dropped, waiting for other owners to release it.
+    ///
+    /// Writes should hold the lock so that they would not interleave with other owners'
+    /// transactions.
+    pub fn hold(&self) -> Result<TransactionLockGuard<'_>, StoreError> {
+        self.acquire()?.holds += 1;
+        Ok(TransactionLockGuard { lock: self })
+    }
+
+    // Wait until the lock is free or ours, then take it.
+    fn acquire(&self) -> Result<MutexGuard<'_, TransactionLockState>, StoreError> {
+        let owner = get_transaction_owner();
+        let mut state =
+            self.released.wait_while(self.state.lock()?, |state| state.owner.is_some_and(|owner_| owner_ != owner))?;
+        state.owner = Some(owner);
+        Ok(state)
+    }
+
+    // Give up the lock if we are done with it.
+    fn release(&self, mut state: MutexGuard<'_, TransactionLockState>) {
+        if (state.transactions == 0) && (state.holds == 0) {
+            state.owner = None;
+            drop(state);
+            self.released.notify_all();
+        }
+    }
+}
+
+//
+// TransactionLockGuard
+//
+
+/// Transaction lock guard.
+///
+/// See [TransactionLock::hold].
+pub struct TransactionLockGuard<'own> {
+    lock: &'own TransactionLock,
+}
+
+impl<'own> Drop for TransactionLockGuard<'own> {
+    fn drop(&mut self) {
+        // A poisoned lock would have failed when acquired
+        if let Ok(mut state) = self.lock.state.lock() {
+            state.holds -= 1;
+            self.lock.release(state);
+        }
+    }
+}
+
+//
+// TransactionLockState
+//
+
+#[derive(Debug, Default)]
+struct TransactionLockState {
+    owner: Option<u64>,
+    transactions: usize,
+    holds: usize,
+}
@@ -1,4 +1,6 @@
-use super::{super::data::*, errors::*, store::*};
+use super::{super::data::*, errors::*, events::*, query::*, store::*};
+
+use std::collections::*;
 
 //
 // StoreWrapper
@@ -25,12 +27,42 @@ impl<StoreT> Store for StoreWrapper<StoreT>
 where
     StoreT: Store,
 {
+    fn begin_transaction(&self) -> Result<(), StoreError> {
+        tracing::debug!("begin_transaction");
+        self.inner.begin_transaction()
+    }
+
+    fn commit_transaction(&self) -> Result<(), StoreError> {
+        tracing::debug!("commit_transaction");
+        self.inner.commit_transaction()
+    }
+
+    fn rollback_transaction(&self) -> Result<(), StoreError> {
+        tracing::debug!("rollback_transaction");
+        self.inner.rollback_transaction()
+    }
+
+    fn subscribe(&self, filter: StoreEventFilter) -> Result<StoreEventReceiver, StoreError> {
+        tracing::debug!("subscribe");
+        self.inner.subscribe(filter)
+    }
+
     fn create_id(&self, id: &mut ID) -> Result<(), StoreError> {
         self.inner.create_id(id)?;
         tracing::debug!(id = id.to_string(), "create_id");
         Ok(())
     }
 
+    fn get_next_ids(&self) -> Result<HashMap<Kind, u64>, StoreError> {
+        tracing::debug!("get_next_ids");
+        self.inner.get_next_ids()
+    }
+
+    fn advance_next_ids(&self, next_ids: &HashMap<Kind, u64>) -> Result<(), StoreError> {
+        tracing::debug!("advance_next_ids");
+        self.inner.advance_next_ids(next_ids)
+    }
+
     fn get_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
         tracing::debug!(id = id.to_string(), "get_class");
         if id.kind != Kind::Class {
@@ -47,6 +79,22 @@ where
         self.inner.add_class(class)
     }
 
+    fn update_class_if_revision(&self, class: Class) -> Result<u64, StoreError> {
+        tracing::debug!(id = class.id.to_string(), revision = class.get_revision(), "update_class_if_revision");
+        if class.id.kind != Kind::Class {
+            return Err(StoreError::ID(format!("kind is not Class: {}", class.id.kind)));
+        }
+        self.inner.update_class_if_revision(class)
+    }
+
+    fn remove_class(&self, id: &ID) -> Result<Option<Class>, StoreError> {
+        tracing::debug!(id = id.to_string(), "remove_class");
+        if id.kind != Kind::Class {
+            return Err(StoreError::ID(format!("kind is not Class: {}", id.kind)));
+        }
+        self.inner.remove_class(id)
+    }
+
     fn get_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
         tracing::debug!(id = id.to_string(), "get_vertex_template");
         if id.kind != Kind::VertexTemplate {
@@ -63,12 +111,32 @@ where
         self.inner.add_vertex_template(vertex_template)
     }
 
+    fn update_vertex_template_if_revision(&self, vertex_template: VertexTemplate) -> Result<u64, StoreError> {
+        tracing::debug!(
+            id = vertex_template.template.id.to_string(),
+            revision = vertex_template.get_revision(),
+            "update_vertex_template_if_revision"
+        );
+        if vertex_template.template.id.kind != Kind::VertexTemplate {
+            return Err(StoreError::ID(format!("kind is not VertexTemplate: {}", vertex_template.template.id.kind)));
+        }
+        self.inner.update_vertex_template_if_revision(vertex_template)
+    }
+
+    fn remove_vertex_template(&self, id: &ID) -> Result<Option<VertexTemplate>, StoreError> {
+        tracing::debug!(id = id.to_string(), "remove_vertex_template");
+        if id.kind != Kind::VertexTemplate {
+            return Err(StoreError::ID(format!("kind is not VertexTemplate: {}", id.kind)));
+        }
+        self.inner.remove_vertex_template(id)
+    }
+
     fn get_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
         tracing::debug!(id = id.to_string(), "get_edge_template");
         if id.kind != Kind::EdgeTemplate {
             return Err(StoreError::ID(format!("kind is not EdgeTemplate: {}", id.kind)));
         }
-        self.get_edge_template(id)
+        self.inner.get_edge_template(id)
     }
 
     fn add_edge_template(&self, edge_template: EdgeTemplate) -> Result<(), StoreError> {
@@ -79,6 +147,26 @@ where
         self.inner.add_edge_template(edge_template)
     }
 
+    fn update_edge_template_if_revision(&self, edge_template: EdgeTemplate) -> Result<u64, StoreError> {
+        tracing::debug!(
+            id = edge_template.template.id.to_string(),
+            revision = edge_template.get_revision(),
+            "update_edge_template_if_revision"
+        );
+        if edge_template.template.id.kind != Kind::EdgeTemplate {
+            return Err(StoreError::ID(format!("kind is not EdgeTemplate: {}", edge_template.template.id.kind)));
+        }
+        self.inner.update_edge_template_if_revision(edge_template)
+    }
+
+    fn remove_edge_template(&self, id: &ID) -> Result<Option<EdgeTemplate>, StoreError> {
+        tracing::debug!(id = id.to_string(), "remove_edge_template");
+        if id.kind != Kind::EdgeTemplate {
+            return Err(StoreError::ID(format!("kind is not EdgeTemplate: {}", id.kind)));
+        }
+        self.inner.remove_edge_template(id)
+    }
+
     fn get_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
         tracing::debug!(id = id.to_string(), "get_vertex");
         if id.kind != Kind::Vertex {
@@ -87,6 +175,11 @@ where
         self.inner.get_vertex(id)
     }
 
+    fn get_entities(&self, kind: Kind, query: &StoreQuery) -> Result<Vec<Entity>, StoreError> {
+        tracing::debug!(kind = kind.to_string(), "get_entities");
+        self.inner.get_entities(kind, query)
+    }
+
     fn get_vertexes(&self, directories: Option<Vec<Directory>>) -> Result<Vec<Vertex>, StoreError> {
         tracing::debug!("get_vertexes");
         self.inner.get_vertexes(directories)
@@ -100,6 +193,26 @@ where
         self.inner.add_vertex(vertex)
     }
 
+    fn update_vertex_if_revision(&self, vertex: Vertex) -> Result<u64, StoreError> {
+        tracing::debug!(
+            id = vertex.instance.id.to_string(),
+            revision = vertex.get_revision(),
+            "update_vertex_if_revision"
+        );
+        if vertex.instance.id.kind != Kind::Vertex {
+            return Err(StoreError::ID(format!("kind is not Vertex: {}", vertex.instance.id.kind)));
+        }
+        self.inner.update_vertex_if_revision(vertex)
+    }
+
+    fn remove_vertex(&self, id: &ID) -> Result<Option<Vertex>, StoreError> {
+        tracing::debug!(id = id.to_string(), "remove_vertex");
+        if id.kind != Kind::Vertex {
+            return Err(StoreError::ID(format!("kind is not Vertex: {}", id.kind)));
+        }
+        self.inner.remove_vertex(id)
+    }
+
     fn get_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
         tracing::debug!(id = id.to_string(), "get_edge");
         if id.kind != Kind::Edge {
@@ -115,4 +228,20 @@ where
         }
         self.inner.add_edge(edge)
     }
+
+    fn update_edge_if_revision(&self, edge: Edge) -> Result<u64, StoreError> {
+        tracing::debug!(id = edge.instance.id.to_string(), revision = edge.get_revision(), "update_edge_if_revision");
+        if edge.instance.id.kind != Kind::Edge {
+            return Err(StoreError::ID(format!("kind is not Edge: {}", edge.instance.id.kind)));
+        }
+        self.inner.update_edge_if_revision(edge)
+    }
+
+    fn remove_edge(&self, id: &ID) -> Result<Option<Edge>, StoreError> {
+        tracing::debug!(id = id.to_string(), "remove_edge");
+        if id.kind != Kind::Edge {
+            return Err(StoreError::ID(format!("kind is not Edge: {}", id.kind)));
+        }
+        self.inner.remove_edge(id)
+    }
 }
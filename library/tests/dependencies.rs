@@ -0,0 +1,52 @@
+#![cfg(feature = "plugins")]
+
+use {
+    floria::{plugins::*, *},
+    kutil::std::error::*,
+};
+
+// Instantiates an "app" template with an edge to a "db" vertex instantiated from another template.
+fn instantiate() -> (StoreWrapper<InMemoryStore>, ID, ID) {
+    let store = InMemoryStore::new(Default::default());
+    let directory = Directory::default();
+    let mut library = Library::new(Default::default(), store.clone());
+    let mut errors = Errors::<FloriaError>::default();
+
+    let db_template = VertexTemplate::new_for(directory.clone(), "db".into(), None);
+    store.add_vertex_template(db_template.clone()).unwrap();
+    let db = db_template.instantiate(&directory, None, &mut library, &mut errors).unwrap();
+
+    let mut app_template = VertexTemplate::new_for(directory.clone(), "app".into(), None);
+    let edge_template = EdgeTemplate::new_for(
+        directory.clone(),
+        "app-db".into(),
+        app_template.template.id.clone(),
+        VertexSelector::new_vertex(db.instance.id.clone()),
+    );
+    app_template.outgoing_edge_template_ids.push(edge_template.template.id.clone());
+    store.add_edge_template(edge_template).unwrap();
+    store.add_vertex_template(app_template.clone()).unwrap();
+    let app = app_template.instantiate(&directory, None, &mut library, &mut errors).unwrap();
+
+    assert!(errors.errors.is_empty());
+    (store, app.instance.id, db.instance.id)
+}
+
+#[test]
+fn source_depends_on_target() {
+    let (store, app, db) = instantiate();
+
+    let graph = store.dependency_graph(&Default::default(), &Default::default()).unwrap();
+    assert_eq!(graph.dependencies(&app), vec![db.clone()]);
+    assert_eq!(graph.order().unwrap(), vec![db, app]);
+}
+
+#[test]
+fn target_depends_on_source() {
+    let (store, app, db) = instantiate();
+
+    let options = DependencyOptions::default().with_edge_dependency(EdgeDependency::TargetDependsOnSource);
+    let graph = store.dependency_graph(&Default::default(), &options).unwrap();
+    assert_eq!(graph.dependencies(&db), vec![app.clone()]);
+    assert_eq!(graph.order().unwrap(), vec![app, db]);
+}
@@ -33,35 +33,67 @@ impl Entity {
         Ok(Self::new(id.clone(), host::get_entity(id)?))
     }
 
-    /// Is in class.
+    /// Is in class, directly or via inheritance.
+    ///
+    /// A class entity is in a class if it is that class or one of its descendants. Classes that
+    /// cannot be found are treated as having no parent classes.
     pub fn is_in_class(&self, class_id: &Id) -> Result<bool, String> {
-        match self.any.into_get("class_ids") {
-            Some(class_ids) => match class_ids {
-                Any::AnyList(class_ids) => {
-                    for class_id_ in &class_ids.to_list().inner {
-                        match class_id_ {
-                            Any::Text(class_id_) => {
-                                if Id::parse(Kind::Class, class_id_) == *class_id {
-                                    return Ok(true);
-                                }
-                            }
+        let mut pending = match &self.id.kind {
+            Kind::Class => vec![self.id.clone()],
+            _ => self.get_class_ids()?,
+        };
 
-                            _ => {
-                                return Err(format!("entity {} has malformed class_ids: not a string", self.id));
-                            }
-                        }
-                    }
-                }
+        if pending.contains(class_id) {
+            return Ok(true);
+        }
 
-                _ => return Err(format!("entity {} has malformed class_ids: not a list", self.id)),
-            },
+        let mut visited = Vec::default();
+        while let Some(class_id_) = pending.pop() {
+            if class_id_ == *class_id {
+                return Ok(true);
+            }
 
-            None => {}
+            if !visited.contains(&class_id_) {
+                // The host only fails to get an entity if it's not found
+                if let Ok(class) = Self::get(&class_id_) {
+                    pending.extend(class.get_parent_class_ids()?);
+                }
+                visited.push(class_id_);
+            }
         }
 
         Ok(false)
     }
 
+    /// Is directly in class (ignoring inheritance).
+    pub fn is_directly_in_class(&self, class_id: &Id) -> Result<bool, String> {
+        Ok(self.get_class_ids()?.contains(class_id))
+    }
+
+    /// Get an entity's class IDs.
+    pub fn get_class_ids(&self) -> Result<Vec<Id>, String> {
+        match &self.id.kind {
+            Kind::Class => Err(format!("entity {} is a class", self.id)),
+            _ => self.get_ids("class_ids", Kind::Class),
+        }
+    }
+
+    /// Get a class's parent class IDs.
+    pub fn get_parent_class_ids(&self) -> Result<Vec<Id>, String> {
+        match &self.id.kind {
+            Kind::Class => self.get_ids("parent_class_ids", Kind::Class),
+            kind => Err(format!("entity {} is not a class: {}", self.id, kind)),
+        }
+    }
+
+    /// Get a class's child class IDs.
+    pub fn get_child_class_ids(&self) -> Result<Vec<Id>, String> {
+        match &self.id.kind {
+            Kind::Class => self.get_ids("child_class_ids", Kind::Class),
+            kind => Err(format!("entity {} is not a class: {}", self.id, kind)),
+        }
+    }
+
     /// Get a vertex's containing vertex.
     pub fn get_containing_vertex(&self) -> Result<Option<Entity>, String> {
         Ok(match self.get_containing_vertex_id()? {
@@ -114,6 +146,23 @@ impl Entity {
         }
     }
 
+    /// Get an entity's lifecycle state, e.g. "initial", "creating", "started".
+    pub fn get_lifecycle_state(&self) -> Result<String, String> {
+        match &self.id.kind {
+            Kind::Vertex | Kind::Edge => match self.any.into_get("lifecycle_state") {
+                Some(lifecycle_state) => match lifecycle_state {
+                    Any::Text(lifecycle_state) => Ok(lifecycle_state.clone()),
+
+                    _ => Err(format!("entity {} has malformed lifecycle_state: not a string", self.id)),
+                },
+
+                None => Ok("initial".into()),
+            },
+
+            kind => Err(format!("entity {} is not a vertex or an edge: {}", self.id, kind)),
+        }
+    }
+
     /// Get a vertex's contained vertex IDs.
     pub fn get_contained_vertex_ids(&self) -> Result<Vec<Id>, String> {
         match &self.id.kind {
@@ -273,4 +322,28 @@ impl Entity {
             self.any.into_get("properties").ok_or_else(|| format!("entity {} is missing properties", self.id))?;
         Ok(object.get(&property_name.into()).map(|property| Property::new(property_name.into(), property)))
     }
+
+    fn get_ids(&self, name: &str, kind: Kind) -> Result<Vec<Id>, String> {
+        match self.any.into_get(name) {
+            Some(ids) => match ids {
+                Any::AnyList(ids) => {
+                    let ids = ids.to_list();
+
+                    let mut ids_ = Vec::with_capacity(ids.inner.len());
+                    for id in &ids.inner {
+                        match id {
+                            Any::Text(id) => ids_.push(Id::parse(kind, id)),
+                            _ => return Err(format!("entity {} has malformed {}: not a string", self.id, name)),
+                        }
+                    }
+
+                    Ok(ids_)
+                }
+
+                _ => Err(format!("entity {} has malformed {}: not a list", self.id, name)),
+            },
+
+            None => Ok(Default::default()),
+        }
+    }
 }